@@ -14,6 +14,7 @@ use osrank::benchmarks::util::{
 };
 use osrank::protocol_traits::graph::GraphExtras;
 use osrank::protocol_traits::ledger::{LedgerView, MockLedger};
+use osrank::types::alias::AliasTables;
 use osrank::types::mock::MockNetwork;
 use rand::SeedableRng;
 use rand_xoshiro::Xoshiro256StarStar;
@@ -55,6 +56,32 @@ fn bench_random_walk_on_csv(c: &mut Criterion) {
     });
 }
 
+// Like `bench_random_walk_on_csv`, but sampling the next node out of
+// precomputed alias tables, which are built once outside the benchmark loop.
+fn bench_random_walk_with_alias_tables_on_csv(c: &mut Criterion) {
+    let network = construct_network(1_000, 10_000);
+    let tables = AliasTables::new(&network);
+    let mut ledger = MockLedger::default();
+    ledger.set_random_walks_num(1);
+    let info = &dev(format!(
+        "random walks with alias tables with {:?} nodes, iter: 1",
+        &network.node_count()
+    )
+    .as_str());
+    c.bench_function(&info, move |b| {
+        b.iter(|| {
+            random_walk::<MockLedger, Normalised<MockNetwork>, Xoshiro256StarStar>(
+                None,
+                &network,
+                &ledger,
+                &Xoshiro256StarStar::from_seed([0; 32]),
+                Some(&tables),
+            )
+            .unwrap()
+        })
+    });
+}
+
 fn bench_rank_network(c: &mut Criterion) {
     let mut network = construct_network(1_000, 10_000);
 
@@ -66,6 +93,7 @@ fn bench_rank_network(c: &mut Criterion) {
         &network,
         &ctx.ledger_view,
         &Xoshiro256StarStar::from_seed([0; 32]),
+        None,
     )
     .unwrap()
     .walks;
@@ -83,6 +111,7 @@ fn bench_rank_network(c: &mut Criterion) {
                     &walks,
                     &mut network,
                     &ctx.ledger_view,
+                    ctx.options.normalise,
                     &mut annotator,
                     &ctx.to_annotation,
                 )
@@ -97,6 +126,7 @@ criterion_group!(
     bench_osrank_naive_on_small_network,
     bench_osrank_naive_on_sample_csv,
     bench_random_walk_on_csv,
+    bench_random_walk_with_alias_tables_on_csv,
     bench_rank_network,
 );
 criterion_main!(benches);
@@ -20,11 +20,13 @@ extern crate quickcheck_macros;
 use clap::{App, Arg};
 use ndarray::Array2;
 use osrank::adjacency::new_network_matrix;
-use osrank::collections::{Rank, WithLabels};
-use osrank::exporters::csv::{export_rank_to_csv, CsvExporterError};
+use osrank::collections::{Rank, RankError, WithLabels};
+use osrank::exporters::csv::write_rank_to_csv;
+use osrank::exporters::{ExportError, WritePolicy};
 use osrank::importers::csv::{
-    new_contribution_adjacency_matrix, new_dependency_adjacency_matrix, ContribRow,
-    ContributionsMetadata, CsvImportError, DepMetaRow, DependenciesMetadata, DisplayAsF64,
+    new_contribution_adjacency_matrix, new_dependency_adjacency_matrix, read_rows, ContribRow,
+    ContributionsMetadata, CsvFile, CsvImportError, DepMetaRow, DependenciesMetadata, DisplayAsF64,
+    ImportReport,
 };
 use osrank::linalg::{transpose_storage_naive, DenseMatrix, SparseMatrix};
 use osrank::types::HyperParams;
@@ -34,6 +36,7 @@ use sprs::CsMat;
 use core::fmt::Debug;
 use num_traits::{Num, One, Signed, Zero};
 use std::fs::File;
+use std::io::BufWriter;
 use std::rc::Rc;
 
 #[derive(Debug, Fail)]
@@ -43,7 +46,10 @@ enum AppError {
     CsvImportFailed(CsvImportError),
     // Returned in case the csv export fails.
     #[fail(display = "i/o error when generating the output rank file {}", _0)]
-    CsvExportFailed(CsvExporterError),
+    CsvExportFailed(ExportError),
+    // Returned in case the rank can't be built or sorted.
+    #[fail(display = "rank error {}", _0)]
+    RankFailed(RankError),
 }
 
 impl From<std::io::Error> for AppError {
@@ -64,12 +70,18 @@ impl From<CsvImportError> for AppError {
     }
 }
 
-impl From<CsvExporterError> for AppError {
-    fn from(err: CsvExporterError) -> AppError {
+impl From<ExportError> for AppError {
+    fn from(err: ExportError) -> AppError {
         AppError::CsvExportFailed(err)
     }
 }
 
+impl From<RankError> for AppError {
+    fn from(err: RankError) -> AppError {
+        AppError::RankFailed(err)
+    }
+}
+
 //
 // Functions
 //
@@ -170,8 +182,11 @@ where
     dense
 }
 
-fn debug_pagerank_to_csv(rank: &Rank<f64>, out_path: &str) -> Result<(), CsvExporterError> {
-    export_rank_to_csv(rank.into_iter(), Box::new(|v| *v), out_path)
+fn debug_pagerank_to_csv(rank: Rank<f64>, out_path: &str) -> Result<(), AppError> {
+    let out = BufWriter::new(WritePolicy::Overwrite.open(out_path)?);
+    let sorted = rank.sorted()?;
+    write_rank_to_csv(sorted.into_iter(), Box::new(|v| *v), out)?;
+    Ok(())
 }
 
 /// Naive porting of the Python algorithm.
@@ -188,12 +203,14 @@ fn build_adjacency_matrix(
     let mut deps_meta = DependenciesMetadata::new();
     let mut contribs_meta = ContributionsMetadata::new();
     let mut contrib_rows = Vec::default();
+    let mut report = ImportReport::default();
 
     // Iterate once over the dependencies metadata and store the name and id.
     // We need to maintain some sort of mapping between the order of visit
     // (which will be used as the index in the matrix) and the project id.
-    for result in deps_meta_csv.into_records().filter_map(|e| e.ok()) {
-        let row: DepMetaRow = result.deserialize(None)?;
+    for (_, row) in
+        read_rows::<DepMetaRow, _>(deps_meta_csv, CsvFile::DependenciesMetadata, &mut report)?
+    {
         deps_meta.ids.insert(row.id);
         deps_meta.labels.push(row.name);
         deps_meta
@@ -203,11 +220,9 @@ fn build_adjacency_matrix(
 
     // Iterate once over the contributions and build a matrix where
     // rows are the project names and columns the (unique) contributors.
-    for result in contribs_csv_first_pass
-        .into_records()
-        .filter_map(|e| e.ok())
+    for (_, row) in
+        read_rows::<ContribRow, _>(contribs_csv_first_pass, CsvFile::Contributions, &mut report)?
     {
-        let row: ContribRow = result.deserialize(None)?;
         let contributor = Rc::new(row.contributor.clone());
 
         contribs_meta.contributors.insert(Rc::clone(&contributor));
@@ -221,7 +236,8 @@ fn build_adjacency_matrix(
     //TODO(adn) For now the maintenance matrix is empty.
 
     println!("Assembling the dependency matrix...");
-    let dep_adj_matrix = new_dependency_adjacency_matrix(&deps_meta, deps_csv)?;
+    let dep_adj_matrix = new_dependency_adjacency_matrix(&deps_meta, deps_csv, &mut report)?;
+    println!("Import report: {}", report);
     println!(
         "Generated a matrix of {}x{}",
         dep_adj_matrix.rows(),
@@ -269,13 +285,12 @@ fn build_adjacency_matrix(
     let pagerank_matrix = pagerank_naive_iterative(&network_t_norm, 0.85, outbound_links_factor);
 
     let pagerank_matrix_labeled =
-        Rank::from(pagerank_matrix.labeled((deps_meta.labels.to_vec().as_slice(), &[])))
-            .unwrap_or_else(|e| panic!(e));
+        Rank::from(pagerank_matrix.labeled((deps_meta.labels.to_vec().as_slice(), &[])))?;
 
     println!("Write the matrix to file (skipped for now)");
 
     // Just for fun/debug: write this as a CSV file.
-    debug_pagerank_to_csv(&pagerank_matrix_labeled, "data/cargo-page-rank.csv")?;
+    debug_pagerank_to_csv(pagerank_matrix_labeled, "data/cargo-page-rank.csv")?;
 
     Ok(())
 }
@@ -12,9 +12,7 @@ use clap::{App, Arg};
 use oscoin_graph_api::GraphAlgorithm;
 use osrank::algorithm::naive::{OsrankNaiveAlgorithm, OsrankNaiveMockContext};
 use osrank::algorithm::{Normalised, OsrankError};
-use osrank::exporters::gexf::GexfExporter;
-use osrank::exporters::graphml::GraphMlExporter;
-use osrank::exporters::{gexf, graphml, Exporter};
+use osrank::exporters::{dot, gexf, graphml, ExportError, WritePolicy};
 use osrank::importers::csv::{import_network, CsvImportError};
 use osrank::protocol_traits::ledger::{LedgerView, MockLedger};
 use osrank::types::mock::{Mock, MockAnnotator, MockNetwork};
@@ -25,8 +23,7 @@ use std::fs::File;
 enum AppError {
     AlgorithmError(OsrankError),
     ImportError(CsvImportError),
-    GexfExportError,
-    GraphMlExportError,
+    ExportError(ExportError),
 }
 
 impl From<OsrankError> for AppError {
@@ -41,15 +38,9 @@ impl From<CsvImportError> for AppError {
     }
 }
 
-impl From<gexf::ExportError> for AppError {
-    fn from(_err: gexf::ExportError) -> AppError {
-        AppError::GexfExportError
-    }
-}
-
-impl From<graphml::ExportError> for AppError {
-    fn from(_err: graphml::ExportError) -> AppError {
-        AppError::GraphMlExportError
+impl From<ExportError> for AppError {
+    fn from(err: ExportError) -> AppError {
+        AppError::ExportError(err)
     }
 }
 
@@ -78,6 +69,13 @@ fn main() -> Result<(), AppError> {
                 .takes_value(true)
                 .required(true),
         )
+        .arg(
+            Arg::with_name("maintainers")
+                .long("maintainers")
+                .help("Path to the (optional) <platform>_maintainers.csv file")
+                .takes_value(true)
+                .required(false),
+        )
         .arg(
             Arg::with_name("output-path")
                 .short("o")
@@ -85,6 +83,18 @@ fn main() -> Result<(), AppError> {
                 .takes_value(true)
                 .required(true),
         )
+        .arg(
+            Arg::with_name("overwrite")
+                .long("overwrite")
+                .help("Overwrite the output files, if they already exist"),
+        )
+        .arg(
+            Arg::with_name("dot-top-n")
+                .long("dot-top-n")
+                .help("Only export the N highest-ranked nodes to the .dot file")
+                .takes_value(true)
+                .required(false),
+        )
         .get_matches();
 
     let deps = matches
@@ -99,10 +109,21 @@ fn main() -> Result<(), AppError> {
     let out = matches
         .value_of("output-path")
         .expect("output csv file not specified.");
+    let dot_top_n = matches
+        .value_of("dot-top-n")
+        .map(|n| n.parse::<usize>().expect("Couldn't parse the top-n."));
+    let policy = if matches.is_present("overwrite") {
+        WritePolicy::Overwrite
+    } else {
+        WritePolicy::CreateNew
+    };
 
     let deps_csv_file = File::open(deps).unwrap();
     let deps_meta_csv_file = File::open(deps_meta).unwrap();
     let contribs_csv_file = File::open(contribs).unwrap();
+    let maintainers_csv_file = matches
+        .value_of("maintainers")
+        .map(|maintainers| File::open(maintainers).unwrap());
 
     debug!("Importing the network...");
 
@@ -122,7 +143,7 @@ fn main() -> Result<(), AppError> {
         csv::Reader::from_reader(deps_csv_file),
         csv::Reader::from_reader(deps_meta_csv_file),
         csv::Reader::from_reader(contribs_csv_file),
-        None,
+        maintainers_csv_file.map(csv::Reader::from_reader),
         &ctx.ledger_view,
     )?;
 
@@ -135,12 +156,19 @@ fn main() -> Result<(), AppError> {
 
     debug!("Exporting the network to .gexf ...");
 
-    let gexf = GexfExporter::new(&network, &annotator, &out);
-    gexf.export()?;
+    gexf::export_graph_to_file(&network, &annotator, format!("{}.gexf", out), policy)?;
 
     debug!("Exporting the network to .graphml ...");
-    let graphml = GraphMlExporter::new(&network, &annotator, &out);
-    graphml.export()?;
+    graphml::export_graph_to_file(&network, &annotator, format!("{}.graphml", out), policy)?;
+
+    debug!("Exporting the network to .dot ...");
+    dot::export_graph_to_file(
+        &network,
+        &annotator,
+        dot_top_n,
+        format!("{}.dot", out),
+        policy,
+    )?;
 
     debug!("Done.");
 
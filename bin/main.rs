@@ -2,7 +2,10 @@ extern crate osrank;
 
 use std::fs::File;
 
+use self::osrank::algorithm::Normalised;
 use self::osrank::importers::gephi_json::{from_gephi_json, GephiEdge, GephiNode};
+use self::osrank::types::mock::MockNetwork;
+use self::osrank::types::HyperParams;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cargo_nodes = File::open("data/cargo_nodes.json")?;
@@ -11,7 +14,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let gephi_edges: Vec<GephiEdge> = serde_json::from_reader(cargo_edges)?;
 
     println!("Building a network from a file..");
-    let cargo_network = from_gephi_json(gephi_nodes, gephi_edges);
+    let cargo_network: Normalised<MockNetwork> =
+        from_gephi_json(gephi_nodes, gephi_edges, &HyperParams::default())
+            .map_err(|e| e.to_string())?;
 
     // Render to file
     println!("Rendering the network to a file..");
@@ -20,19 +20,31 @@ use clap::{App, Arg};
 use core::fmt::Debug;
 use fraction::Ratio;
 use oscoin_graph_api::{Graph, GraphAlgorithm, GraphObject};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::BufReader;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
 use std::str::FromStr;
 
+use osrank::algorithm::incremental::{OsrankIncrementalAlgorithm, OsrankIncrementalMockContext};
 use osrank::algorithm::naive::{OsrankNaiveAlgorithm, OsrankNaiveMockContext};
-use osrank::algorithm::{Normalised, OsrankError};
-use osrank::exporters::csv::CsvExporterError;
-use osrank::exporters::Exporter;
-use osrank::importers::csv::{import_network, CsvImportError};
+use osrank::algorithm::{AdaptiveWalks, Normalised, OsrankError, OsrankOptions, TeleportMode};
+use osrank::exporters::report::{RankReportExporter, ReportFormat};
+use osrank::exporters::{ExportError, Exporter, WritePolicy};
+use osrank::importers::csv::{
+    import_network_with_report, import_platforms, CsvImportError, ImportMode, ImportReport,
+};
+use osrank::protocol_traits::file_ledger::{
+    FileLedger, FileLedgerError, LedgerVersion, ParamChange,
+};
 use osrank::protocol_traits::ledger::{LedgerView, MockLedger};
 use osrank::types;
-use osrank::types::mock::{Mock, MockAnnotator, MockAnnotatorCsvExporter, MockNetwork};
+use osrank::types::mock::{
+    KeyValueAnnotator, Mock, MockAnnotatorCsvExporter, MockEstimateAnnotator,
+    MockEstimateAnnotatorCsvExporter, MockNetwork,
+};
+use osrank::types::snapshot::{load_snapshot_from_file, save_snapshot_to_file, SnapshotError};
 use osrank::types::walk::SeedSet;
 
 #[derive(Debug, Fail)]
@@ -43,12 +55,22 @@ enum AppError {
     #[fail(display = "import error when reading/writing on the CSV file {}", _0)]
     ImportError(CsvImportError),
     #[fail(display = "export error when reading/writing on the CSV file {}", _0)]
-    ExportError(CsvExporterError),
+    ExportError(ExportError),
     #[fail(
         display = "export when running the Osrank algorithm on the graph {}",
         _0
     )]
     AlgorithmError(OsrankError),
+    #[fail(display = "snapshot error {}", _0)]
+    SnapshotError(SnapshotError),
+    #[fail(display = "ledger error {}", _0)]
+    LedgerError(FileLedgerError),
+    #[fail(display = "invalid value {} for --{}", value, arg)]
+    InvalidArgument { arg: String, value: String },
+    #[fail(
+        display = "the parameters can't be changed when reading an older version of the ledger"
+    )]
+    ParamsWithLedgerVersion,
 }
 
 impl From<std::io::Error> for AppError {
@@ -63,8 +85,8 @@ impl From<CsvImportError> for AppError {
     }
 }
 
-impl From<CsvExporterError> for AppError {
-    fn from(err: CsvExporterError) -> AppError {
+impl From<ExportError> for AppError {
+    fn from(err: ExportError) -> AppError {
         AppError::ExportError(err)
     }
 }
@@ -75,121 +97,260 @@ impl From<OsrankError> for AppError {
     }
 }
 
+impl From<SnapshotError> for AppError {
+    fn from(err: SnapshotError) -> AppError {
+        AppError::SnapshotError(err)
+    }
+}
+
+impl From<FileLedgerError> for AppError {
+    fn from(err: FileLedgerError) -> AppError {
+        AppError::LedgerError(err)
+    }
+}
+
 #[derive(Debug)]
 pub enum OsrankAlgorithm {
-    Naive,
-    Incremental,
+    Naive(OsrankOptions),
+    Incremental(OsrankOptions),
 }
 
-fn run_osrank(
+/// Imports the network out of the CSV files.
+fn import_csv_network(
     deps_file: &str,
     deps_meta_file: &str,
     contrib_file: &str,
-    out_path: &str,
-    osrank_algo: OsrankAlgorithm,
-    ledger: MockLedger,
-    seed_set: Option<SeedSet<<<MockNetwork as Graph>::Node as GraphObject>::Id>>,
-) -> Result<(), AppError> {
+    maintainers_file: Option<&str>,
+    ledger: &MockLedger,
+    import_mode: ImportMode,
+) -> Result<Normalised<MockNetwork>, AppError> {
     let deps_csv_file = File::open(deps_file)?;
     let deps_meta_csv_file = File::open(deps_meta_file)?;
     let contribs_csv_file = File::open(contrib_file)?;
+    let maintainers_csv_file = match maintainers_file {
+        None => None,
+        Some(f) => Some(File::open(f)?),
+    };
+
+    debug!("Importing the network...");
+    debug!("Using {:#?}", ledger.get_hyperparams());
+
+    let mut report = ImportReport::new(import_mode);
+    let network = import_network_with_report::<MockNetwork, MockLedger, File>(
+        csv::Reader::from_reader(deps_csv_file),
+        csv::Reader::from_reader(deps_meta_csv_file),
+        csv::Reader::from_reader(contribs_csv_file),
+        maintainers_csv_file.map(csv::Reader::from_reader),
+        ledger,
+        &mut report,
+    )?;
 
+    if !report.is_clean() {
+        warn!("Import report: {}", report);
+        for issue in &report.issues {
+            debug!("{}", issue);
+        }
+    }
+
+    Ok(network)
+}
+
+/// Runs the selected algorithm, returning the estimated ranks (alongside
+/// their standard errors).
+///
+/// The incremental algorithm resumes from the walks saved at `state_path` (if
+/// any), re-simulating only the ones affected by the nodes whose outgoing
+/// edges changed since, and saves the updated walks back.
+fn run_osrank(
+    network: &Normalised<MockNetwork>,
+    osrank_algo: OsrankAlgorithm,
+    ledger: MockLedger,
+    seed_set: Option<SeedSet<<<MockNetwork as Graph>::Node as GraphObject>::Id>>,
+    state_path: Option<&str>,
+) -> Result<MockEstimateAnnotator<Normalised<MockNetwork>>, AppError> {
     let trusted_nodes_num = match &seed_set {
         None => 0,
         Some(r) => r.len(),
     };
 
-    debug!("Importing the network...");
-    debug!("Using {:#?}", ledger.get_hyperparams());
-
     let ss = match &seed_set {
         None => None,
         Some(r) => Some(r),
     };
 
-    let (algo, mut ctx, network) = match osrank_algo {
-        OsrankAlgorithm::Naive => {
+    debug!(
+        "{}",
+        format!(
+            "Calculating the osrank ({:#?} algorithm, {} trusted nodes)...",
+            osrank_algo, trusted_nodes_num
+        )
+    );
+
+    let initial_seed = [0; 32];
+    let mut annotator: MockEstimateAnnotator<Normalised<MockNetwork>> = Default::default();
+
+    match osrank_algo {
+        OsrankAlgorithm::Naive(options) => {
             debug!("Selecting the naive algorithm...");
-            let a: Mock<
+            let algo: Mock<
                 OsrankNaiveAlgorithm<
                     Normalised<MockNetwork>,
                     MockLedger,
-                    MockAnnotator<Normalised<MockNetwork>>,
+                    MockEstimateAnnotator<Normalised<MockNetwork>>,
                 >,
             > = Mock {
                 unmock: OsrankNaiveAlgorithm::default(),
             };
             let mut ctx = OsrankNaiveMockContext::default();
             ctx.seed_set = ss;
+            ctx.options = options;
             ctx.ledger_view = ledger;
-            let network = import_network::<MockNetwork, MockLedger, File>(
-                csv::Reader::from_reader(deps_csv_file),
-                csv::Reader::from_reader(deps_meta_csv_file),
-                csv::Reader::from_reader(contribs_csv_file),
-                None,
-                &ctx.ledger_view,
-            )?;
-
-            (a, ctx, network)
+
+            if let Some(convergence) =
+                algo.execute(&mut ctx, network, &mut annotator, initial_seed)?
+            {
+                debug!("Adaptive walks: {:#?}", convergence);
+            }
         }
-        OsrankAlgorithm::Incremental => {
-            debug!("Selecting the incremental (not-implemented) algorithm...");
-            let a: Mock<
-                OsrankNaiveAlgorithm<
+        OsrankAlgorithm::Incremental(options) => {
+            debug!("Selecting the incremental algorithm...");
+            let algo: Mock<
+                OsrankIncrementalAlgorithm<
                     Normalised<MockNetwork>,
                     MockLedger,
-                    MockAnnotator<Normalised<MockNetwork>>,
+                    MockEstimateAnnotator<Normalised<MockNetwork>>,
                 >,
             > = Mock {
-                unmock: OsrankNaiveAlgorithm::default(),
+                unmock: OsrankIncrementalAlgorithm::default(),
             };
-            let mut ctx = OsrankNaiveMockContext::default();
+            let mut ctx = OsrankIncrementalMockContext::default();
             ctx.seed_set = ss;
+            ctx.options = options;
             ctx.ledger_view = ledger;
-            let network = import_network::<MockNetwork, MockLedger, File>(
-                csv::Reader::from_reader(deps_csv_file),
-                csv::Reader::from_reader(deps_meta_csv_file),
-                csv::Reader::from_reader(contribs_csv_file),
-                None,
-                &ctx.ledger_view,
-            )?;
-
-            (a, ctx, network)
+
+            if let Some(path) = state_path.filter(|p| Path::new(p).exists()) {
+                debug!("Loading the walks of the last run...");
+                ctx.state = load_snapshot_from_file(path)?;
+                info!(
+                    "{} nodes changed since the last run (#{})",
+                    ctx.state.detect_changes(network),
+                    ctx.state.generation()
+                );
+            }
+
+            algo.execute(&mut ctx, network, &mut annotator, initial_seed)?;
+
+            if let Some(path) = state_path {
+                debug!("Saving the walks for the next run...");
+                save_snapshot_to_file(&ctx.state, path)?;
+            }
         }
-    };
+    }
 
-    debug!(
-        "{}",
-        format!(
-            "Calculating the osrank ({:#?} algorithm, {} trusted nodes)...",
-            osrank_algo, trusted_nodes_num
-        )
-    );
+    Ok(annotator)
+}
 
-    let initial_seed = [0; 32];
-    let mut annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+/// Exports the ranks into a csv file, optionally followed by their standard
+/// error and the bounds of their 95% confidence interval.
+fn export_ranks(
+    annotator: MockEstimateAnnotator<Normalised<MockNetwork>>,
+    out_path: &str,
+    policy: WritePolicy,
+    with_std_errors: bool,
+) -> Result<(), AppError> {
+    debug!("Exporting the ranks into a .csv file ...");
+    let out = BufWriter::new(policy.open(out_path)?);
+    if with_std_errors {
+        MockEstimateAnnotatorCsvExporter::new(annotator, 1.96, out).export()?;
+    } else {
+        let ranks = KeyValueAnnotator {
+            annotator: annotator
+                .annotator
+                .into_iter()
+                .map(|(id, estimate)| (id, estimate.rank))
+                .collect(),
+        };
+        MockAnnotatorCsvExporter::new(ranks, out).export()?;
+    }
 
-    algo.execute(&mut ctx, &network, &mut annotator, initial_seed)?;
+    debug!("Done.");
+    Ok(())
+}
 
-    debug!("Exporting the ranks into a .csv file ...");
-    // Export the ranks into a csv file.
-    let rank_exporter = MockAnnotatorCsvExporter::new(annotator, out_path);
-    rank_exporter.export()?;
+/// Exports the ranks into a report, which also tells the type, the number of
+/// visits, the in/out degree and the platform (if known) of every node.
+fn export_report(
+    network: &Normalised<MockNetwork>,
+    annotator: &MockEstimateAnnotator<Normalised<MockNetwork>>,
+    platforms: &HashMap<String, String>,
+    format: ReportFormat,
+    out_path: &str,
+    policy: WritePolicy,
+) -> Result<(), AppError> {
+    debug!("Exporting the rank report ({:?}) ...", format);
+    let file = match format {
+        ReportFormat::Json => policy.open_document(out_path)?,
+        ReportFormat::Csv | ReportFormat::JsonLines => policy.open(out_path)?,
+    };
+    // When appending to an existing report, its header row is already there.
+    let headers = policy != WritePolicy::Append || file.metadata()?.len() == 0;
+    RankReportExporter::new(network, annotator, platforms, format, BufWriter::new(file))
+        .headers(headers)
+        .export()?;
 
     debug!("Done.");
     Ok(())
 }
 
 /// Parses the algorithm to use from a stringly-typed representation into a
-/// typed one.
-fn parse_algorithm(algo_str: &str) -> Option<OsrankAlgorithm> {
-    match algo_str {
-        "naive" => Some(OsrankAlgorithm::Naive),
-        "incremental" => Some(OsrankAlgorithm::Incremental),
+/// typed one. The incremental algorithm supports only the uniform teleport
+/// mode, and doesn't support the adaptive mode.
+fn parse_algorithm(algo_str: &str, options: OsrankOptions) -> Option<OsrankAlgorithm> {
+    match (algo_str, options.teleport, options.adaptive) {
+        ("naive", _, _) => Some(OsrankAlgorithm::Naive(options)),
+        ("incremental", TeleportMode::Uniform, None) => Some(OsrankAlgorithm::Incremental(options)),
         _ => None,
     }
 }
 
+/// Parses the configuration for the adaptive mode, which is enabled only if
+/// a tolerance is given.
+fn parse_adaptive(matches: &clap::ArgMatches) -> Result<Option<AdaptiveWalks>, AppError> {
+    let tolerance = match given(matches, "adaptive-tolerance", |t| t.parse::<f64>().ok())? {
+        None => return Ok(None),
+        Some(t) => t,
+    };
+
+    // The walk budget has a default, so it's parsed even when not given.
+    let max_walks_txt = matches
+        .value_of("adaptive-max-walks")
+        .expect("the adaptive-max-walks flag has a default value");
+    let max_walks = max_walks_txt
+        .parse::<usize>()
+        .map_err(|_| AppError::InvalidArgument {
+            arg: "adaptive-max-walks".to_string(),
+            value: max_walks_txt.to_string(),
+        })?;
+
+    Ok(Some(AdaptiveWalks {
+        tolerance,
+        top_k: given(matches, "adaptive-top-k", |k| k.parse::<usize>().ok())?,
+        max_walks,
+    }))
+}
+
+/// Parses the teleport mode to use from a stringly-typed representation into
+/// a typed one.
+fn parse_teleport(teleport_str: &str) -> Option<TeleportMode> {
+    match teleport_str {
+        "uniform" => Some(TeleportMode::Uniform),
+        "personalized" => Some(TeleportMode::Personalized),
+        _ => None,
+    }
+}
+
+/// Parses the seed set file, where each line contains a node id, optionally
+/// followed by a comma and its trust score (which defaults to 1.0).
 fn parse_seed_set(
     path_to_seed_file: &str,
 ) -> Result<Option<SeedSet<<<MockNetwork as Graph>::Node as GraphObject>::Id>>, AppError>
@@ -200,7 +361,18 @@ where
 
     let seed_sets = File::open(path_to_seed_file)?;
     for line in BufReader::new(seed_sets).lines() {
-        trusted_nodes.add_node(line.expect("Couldn't read line from seed set file.").into());
+        let line = line.expect("Couldn't read line from seed set file.");
+        let mut fields = line.rsplitn(2, ',');
+        match (fields.next(), fields.next()) {
+            (Some(trust), Some(node_id)) => {
+                let trust = trust
+                    .trim()
+                    .parse::<f64>()
+                    .expect("Couldn't parse the trust score from seed set file.");
+                trusted_nodes.add_trusted_node(node_id.to_string().into(), trust)
+            }
+            _ => trusted_nodes.add_node(line.into()),
+        }
     }
 
     if trusted_nodes.is_empty() {
@@ -212,14 +384,13 @@ where
 
 /// Overrides the `HyperParams` with the ones passed as input (if any).
 fn parse_hyperparams(
+    mut hyperparams: types::HyperParams,
     contrib_txt: Option<&str>,
     contrib_prime_txt: Option<&str>,
     depend_txt: Option<&str>,
     maintain_txt: Option<&str>,
     maintain_prime_txt: Option<&str>,
 ) -> Result<types::HyperParams, AppError> {
-    let mut hyperparams = types::HyperParams::default();
-
     if let Some(contrib) = contrib_txt.and_then(to_weight) {
         hyperparams.contrib_factor = contrib;
     }
@@ -243,6 +414,149 @@ fn parse_hyperparams(
     Ok(hyperparams)
 }
 
+/// The arguments which set the parameters stored in the ledger.
+const LEDGER_ARGS: [&str; 10] = [
+    "tau",
+    "iter",
+    "max-walk-length",
+    "accounts-damping-factor",
+    "projects-damping-factor",
+    "contrib-factor",
+    "contrib-prime-factor",
+    "depend-factor",
+    "maintain-factor",
+    "maintain-prime-factor",
+];
+
+/// Reads the parameters out of the ledger at `ledger_path`, either as of the
+/// requested `--ledger-version`, or as of the latest version, after recording
+/// the parameters passed as input. Returns them alongside their version.
+fn read_ledger(
+    ledger_path: &str,
+    matches: &clap::ArgMatches,
+) -> Result<(MockLedger, LedgerVersion), AppError> {
+    let mut ledger = FileLedger::open(ledger_path)?;
+
+    let (params, version) = match given(matches, "ledger-version", |s| s.parse::<u64>().ok())? {
+        Some(version) => {
+            if LEDGER_ARGS
+                .iter()
+                .any(|arg| matches.occurrences_of(arg) > 0)
+            {
+                return Err(AppError::ParamsWithLedgerVersion);
+            }
+            (ledger.as_of(version)?, version)
+        }
+        None => {
+            record_params(&mut ledger, matches)?;
+            (ledger.latest(), ledger.version())
+        }
+    };
+
+    info!(
+        "Using the parameters at version {} of the ledger {}",
+        version, ledger_path
+    );
+    Ok((params, version))
+}
+
+/// Records the version of the ledger the ranks were computed with into
+/// `<out_path>.ledger-version`, so that they can be reproduced later on.
+fn write_ledger_version(
+    out_path: &str,
+    policy: WritePolicy,
+    version: LedgerVersion,
+) -> Result<(), AppError> {
+    let mut out = policy.open(format!("{}.ledger-version", out_path))?;
+    writeln!(out, "{}", version)?;
+    Ok(())
+}
+
+/// Parses the value of `arg` via `parse`, if it's been explicitly passed as
+/// input, failing if it's malformed.
+fn given<T, F>(matches: &clap::ArgMatches, arg: &str, parse: F) -> Result<Option<T>, AppError>
+where
+    F: Fn(&str) -> Option<T>,
+{
+    if matches.occurrences_of(arg) == 0 {
+        return Ok(None);
+    }
+
+    match matches.value_of(arg) {
+        None => Ok(None),
+        Some(value) => parse(value)
+            .map(Some)
+            .ok_or_else(|| AppError::InvalidArgument {
+                arg: arg.to_string(),
+                value: value.to_string(),
+            }),
+    }
+}
+
+/// Records into the ledger the parameters which have been explicitly passed
+/// as input, leaving the others as they are.
+fn record_params(ledger: &mut FileLedger, matches: &clap::ArgMatches) -> Result<(), AppError> {
+    if let Some(tau) = given(matches, "tau", |s| s.parse::<types::Tau>().ok())? {
+        ledger.record(ParamChange::Tau(tau))?;
+    }
+
+    if let Some(r) = given(matches, "iter", |s| s.parse::<types::R>().ok())? {
+        ledger.record(ParamChange::RandomWalksNum(r))?;
+    }
+
+    if let Some(len) = given(matches, "max-walk-length", parse_max_walk_length)? {
+        ledger.record(ParamChange::MaxWalkLength(len))?;
+    }
+
+    let acc_damping_factor = given(matches, "accounts-damping-factor", |s| {
+        s.parse::<f64>().ok()
+    })?;
+    let prj_damping_factor = given(matches, "projects-damping-factor", |s| {
+        s.parse::<f64>().ok()
+    })?;
+    if acc_damping_factor.is_some() || prj_damping_factor.is_some() {
+        let current = ledger.get_damping_factors();
+        let damping_factors = types::DampingFactors {
+            project: prj_damping_factor.unwrap_or(current.project),
+            account: acc_damping_factor.unwrap_or(current.account),
+        };
+        ledger.record(ParamChange::DampingFactors(damping_factors))?;
+    }
+
+    let mut hyperparams = ledger.get_hyperparams().clone();
+    let mut hyperparams_given = false;
+    let factors = vec![
+        ("contrib-factor", &mut hyperparams.contrib_factor),
+        (
+            "contrib-prime-factor",
+            &mut hyperparams.contrib_prime_factor,
+        ),
+        ("depend-factor", &mut hyperparams.depend_factor),
+        ("maintain-factor", &mut hyperparams.maintain_factor),
+        (
+            "maintain-prime-factor",
+            &mut hyperparams.maintain_prime_factor,
+        ),
+    ];
+    for (arg, factor) in factors {
+        if let Some(weight) = given(matches, arg, to_weight)? {
+            *factor = weight;
+            hyperparams_given = true;
+        }
+    }
+    if hyperparams_given {
+        ledger.record(ParamChange::HyperParams(hyperparams))?;
+    }
+
+    Ok(())
+}
+
+fn parse_max_walk_length(s: &str) -> Option<types::MaxWalkLength> {
+    s.parse::<types::MaxWalkLength>()
+        .ok()
+        .filter(|len| *len > 0)
+}
+
 fn to_weight(s: &str) -> Option<types::Weight> {
     Ratio::from_str(s)
         .map(|r| types::Weight::new(*r.numer(), *r.denom()))
@@ -257,21 +571,60 @@ fn main() -> Result<(), AppError> {
                 .long("deps")
                 .help("Path to the <platform>_dependencies.csv file")
                 .takes_value(true)
-                .required(true),
+                .required_unless("snapshot"),
         )
         .arg(
             Arg::with_name("dependencies-with-metadata")
                 .long("deps-meta")
                 .help("Path to the <platform>_dependencies_meta.csv file")
                 .takes_value(true)
-                .required(true),
+                .required_unless("snapshot"),
         )
         .arg(
             Arg::with_name("contributions")
                 .long("contribs")
                 .help("Path to the <platform>_contributions.csv file")
                 .takes_value(true)
-                .required(true),
+                .required_unless("snapshot"),
+        )
+        .arg(
+            Arg::with_name("maintainers")
+                .long("maintainers")
+                .help("Path to the (optional) <platform>_maintainers.csv file")
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .help("Fail on the first malformed or inconsistent row of the CSV files, rather than skipping it.")
+                .takes_value(false)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("snapshot")
+                .long("snapshot")
+                .help("Path to a snapshot of the (normalised) network to rank, to use in place of the CSV files. The network is already normalised, so the hyperparameters can't be changed.")
+                .takes_value(true)
+                .conflicts_with_all(&[
+                    "dependencies",
+                    "dependencies-with-metadata",
+                    "contributions",
+                    "maintainers",
+                    "contrib-factor",
+                    "contrib-prime-factor",
+                    "depend-factor",
+                    "maintain-factor",
+                    "maintain-prime-factor",
+                ])
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("save-snapshot")
+                .long("save-snapshot")
+                .help("Path to the file where to save a snapshot of the (normalised) network, to rank it again without importing it")
+                .takes_value(true)
+                .required(false),
         )
         .arg(
             Arg::with_name("output-path")
@@ -281,6 +634,30 @@ fn main() -> Result<(), AppError> {
                 .takes_value(true)
                 .required(true),
         )
+        .arg(
+            Arg::with_name("report")
+                .long("report")
+                .help("Export a report (csv|json|jsonl) with the position, type, visits, in/out degree and platform of every node, rather than only the ranks.")
+                .takes_value(true)
+                .possible_values(&["csv", "json", "jsonl"])
+                .conflicts_with("std-errors")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("overwrite")
+                .long("overwrite")
+                .help("Overwrite the output .csv file, if it already exists.")
+                .conflicts_with("append")
+                .takes_value(false)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("append")
+                .long("append")
+                .help("Append the ranks to the output file, if it already exists. Not supported by the json report.")
+                .takes_value(false)
+                .required(false),
+        )
         .arg(
             Arg::with_name("algorithm")
                 .long("algorithm")
@@ -290,6 +667,64 @@ fn main() -> Result<(), AppError> {
                 .default_value("naive")
                 .required(false),
         )
+        .arg(
+            Arg::with_name("state")
+                .long("state")
+                .help("Path to the file where the incremental algorithm keeps its walks between runs. If it exists, only the walks affected by the changes to the network since the last run get re-simulated.")
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("teleport")
+                .long("teleport")
+                .help("Where walks start from (uniform|personalized). The personalized mode walks only from the seed set, and requires the naive algorithm.")
+                .takes_value(true)
+                .default_value("uniform")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("std-errors")
+                .long("std-errors")
+                .help("Also export the standard error of each rank, and the bounds of its 95% confidence interval.")
+                .takes_value(false)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("normalise")
+                .long("normalise")
+                .help("Scale the ranks so that they sum up to exactly 1.")
+                .takes_value(false)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("alias-tables")
+                .long("alias-tables")
+                .help("Precompute alias tables to pick the next node of the walks in constant time.")
+                .takes_value(false)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("adaptive-tolerance")
+                .long("adaptive-tolerance")
+                .help("Enables the adaptive mode, which performs R walks per node in rounds, until the L1 distance between the ranks of two consecutive rounds falls below this tolerance.")
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("adaptive-top-k")
+                .long("adaptive-top-k")
+                .help("In adaptive mode, also require the k highest-ranked nodes to be the same in two consecutive rounds.")
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("adaptive-max-walks")
+                .long("adaptive-max-walks")
+                .help("In adaptive mode, the maximum number of walks to perform across all rounds.")
+                .takes_value(true)
+                .default_value("10000000")
+                .required(false),
+        )
         .arg(
             Arg::with_name("tau")
                 .long("tau")
@@ -307,6 +742,14 @@ fn main() -> Result<(), AppError> {
                 .default_value("10")
                 .required(false),
         )
+        .arg(
+            Arg::with_name("max-walk-length")
+                .long("max-walk-length")
+                .help("The maximum number of steps of each random walk. The ranking fails if a walk gets any longer.")
+                .takes_value(true)
+                .default_value("1000")
+                .required(false),
+        )
         .arg(
             Arg::with_name("accounts-damping-factor")
                 .long("accounts-damping-factors")
@@ -326,7 +769,7 @@ fn main() -> Result<(), AppError> {
         .arg(
             Arg::with_name("seed-set")
                 .long("seed-set")
-                .help("The initial seed set file, a list of project IDs, one each line.")
+                .help("The initial seed set file, a list of project IDs, one each line, optionally followed by ',<trust>'.")
                 .takes_value(true)
                 .required(false),
         )
@@ -370,6 +813,21 @@ fn main() -> Result<(), AppError> {
                 .takes_value(true)
                 .required(false),
         )
+        .arg(
+            Arg::with_name("ledger")
+                .long("ledger")
+                .help("Path to the ledger file the parameters are read from, created if missing. The parameters passed as input are recorded into it first, and the version of the ledger used is written into <output-path>.ledger-version.")
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("ledger-version")
+                .long("ledger-version")
+                .help("Use the parameters as they were at this version of the ledger, rather than the latest ones.")
+                .takes_value(true)
+                .requires("ledger")
+                .required(false),
+        )
         .get_matches();
 
     let tau = matches
@@ -398,6 +856,7 @@ fn main() -> Result<(), AppError> {
     };
 
     let hyperparams = parse_hyperparams(
+        types::HyperParams::default(),
         matches.value_of("contrib-factor"),
         matches.value_of("contrib-prime-factor"),
         matches.value_of("depend-factor"),
@@ -408,29 +867,119 @@ fn main() -> Result<(), AppError> {
     let mut ledger_view = MockLedger::default();
     ledger_view.set_tau(tau);
     ledger_view.set_random_walks_num(r);
+    if let Some(len) = given(&matches, "max-walk-length", parse_max_walk_length)? {
+        ledger_view.set_max_walk_length(len);
+    }
     ledger_view.set_damping_factors(damping_factors);
     ledger_view.set_hyperparams(hyperparams);
 
-    run_osrank(
-        matches
-            .value_of("dependencies")
-            .expect("dependencies csv file not given."),
-        matches
-            .value_of("dependencies-with-metadata")
-            .expect("dependencies with metadata csv file not given."),
-        matches
-            .value_of("contributions")
-            .expect("contributions csv file not given."),
-        matches
-            .value_of("output-path")
-            .expect("output csv file not specified."),
-        matches
-            .value_of("algorithm")
-            .and_then(parse_algorithm)
-            .expect("Failed to parse algorithm. Possible choices: naive|incremental."),
+    let mut ledger_version = None;
+    if let Some(ledger_path) = matches.value_of("ledger") {
+        let (params, version) = read_ledger(ledger_path, &matches)?;
+        ledger_view = params;
+        ledger_version = Some(version);
+    }
+
+    let options = OsrankOptions {
+        teleport: parse_teleport(matches.value_of("teleport").unwrap_or("uniform"))
+            .expect("Failed to parse the teleport mode. Possible choices: uniform|personalized."),
+        normalise: matches.is_present("normalise"),
+        adaptive: parse_adaptive(&matches)?,
+        alias_tables: matches.is_present("alias-tables"),
+    };
+
+    let network = match matches.value_of("snapshot") {
+        Some(snapshot) => {
+            debug!("Loading the network from the snapshot...");
+            load_snapshot_from_file(snapshot)?
+        }
+        None => import_csv_network(
+            matches
+                .value_of("dependencies")
+                .expect("dependencies csv file not given."),
+            matches
+                .value_of("dependencies-with-metadata")
+                .expect("dependencies with metadata csv file not given."),
+            matches
+                .value_of("contributions")
+                .expect("contributions csv file not given."),
+            matches.value_of("maintainers"),
+            &ledger_view,
+            if matches.is_present("strict") {
+                ImportMode::Strict
+            } else {
+                ImportMode::Lenient
+            },
+        )?,
+    };
+
+    if let Some(snapshot) = matches.value_of("save-snapshot") {
+        debug!("Saving a snapshot of the network...");
+        save_snapshot_to_file(&network, snapshot)?;
+    }
+
+    let osrank_algo = parse_algorithm(matches.value_of("algorithm").unwrap_or("naive"), options).expect(
+        "Failed to parse algorithm. Possible choices: naive|incremental, with uniform|personalized teleport (incremental supports only uniform, without the adaptive mode).",
+    );
+
+    if let (OsrankAlgorithm::Naive(_), true) = (&osrank_algo, matches.is_present("state")) {
+        warn!("The --state is used only by the incremental algorithm, ignoring it.");
+    }
+
+    let annotator = run_osrank(
+        &network,
+        osrank_algo,
         ledger_view,
         matches
             .value_of("seed-set")
             .and_then(|ss| parse_seed_set(ss).expect("Seed set parsing failed.")),
-    )
+        matches.value_of("state"),
+    )?;
+
+    let policy = if matches.is_present("overwrite") {
+        WritePolicy::Overwrite
+    } else if matches.is_present("append") {
+        WritePolicy::Append
+    } else {
+        WritePolicy::CreateNew
+    };
+
+    let out_path = matches
+        .value_of("output-path")
+        .expect("output csv file not specified.");
+
+    match matches.value_of("report") {
+        None => export_ranks(
+            annotator,
+            out_path,
+            policy,
+            matches.is_present("std-errors"),
+        )?,
+        Some(format) => {
+            // The platforms are known only when importing from the CSV files.
+            let platforms = match matches.value_of("dependencies-with-metadata") {
+                None => HashMap::new(),
+                Some(deps_meta) => import_platforms(
+                    csv::Reader::from_reader(File::open(deps_meta)?),
+                    &mut ImportReport::new(ImportMode::Lenient),
+                )?,
+            };
+            export_report(
+                &network,
+                &annotator,
+                &platforms,
+                format
+                    .parse()
+                    .expect("Failed to parse the report format. Possible choices: csv|json|jsonl."),
+                out_path,
+                policy,
+            )?
+        }
+    }
+
+    if let Some(version) = ledger_version {
+        write_ledger_version(out_path, policy, version)?;
+    }
+
+    Ok(())
 }
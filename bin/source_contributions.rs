@@ -1,5 +1,6 @@
 extern crate clap;
 extern crate csv;
+extern crate osrank;
 extern crate reqwest;
 extern crate serde;
 
@@ -9,6 +10,7 @@ extern crate failure_derive;
 
 use clap::{App, Arg};
 use csv::StringRecord;
+use osrank::importers::csv::{read_rows, CsvFile, CsvImportError, ImportReport};
 use reqwest::{Client, Url};
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
@@ -33,6 +35,10 @@ enum AppError {
     #[fail(display = "i/o error when reading/writing on the CSV file {}", _0)]
     IOError(std::io::Error),
 
+    // Returned when the projects file couldn't be read.
+    #[fail(display = "{}", _0)]
+    CsvImportFailed(CsvImportError),
+
     // Returned when the OSRANK_GITHUB_TOKEN is not present as an env var.
     #[fail(display = "Couldn't find OSRANK_GITHUB_TOKEN in your env vars: {}", _0)]
     GithubTokenNotFound(std::env::VarError),
@@ -64,6 +70,12 @@ impl From<std::io::Error> for AppError {
     }
 }
 
+impl From<CsvImportError> for AppError {
+    fn from(err: CsvImportError) -> AppError {
+        AppError::CsvImportFailed(err)
+    }
+}
+
 impl From<std::env::VarError> for AppError {
     fn from(err: std::env::VarError) -> AppError {
         AppError::GithubTokenNotFound(err)
@@ -226,9 +238,10 @@ fn source_contributors(
     let projects_file = File::open(path)?;
 
     // Build the CSV reader and iterate over each record.
-    let mut rdr = csv::ReaderBuilder::new()
+    let rdr = csv::ReaderBuilder::new()
         .flexible(true)
         .from_reader(projects_file);
+    let mut report = ImportReport::default();
     let mut contributions = OpenOptions::new()
         .append(resume_from.is_some())
         .write(resume_from.is_none())
@@ -244,9 +257,11 @@ fn source_contributors(
         contributions.write_all(b"ID,MAINTAINER,REPO,CONTRIBUTIONS,NAME\n")?;
     }
 
-    for result in rdr
-        .records()
-        .filter_map(|e| e.ok())
+    let rows = read_rows::<Vec<String>, _>(rdr, CsvFile::Projects, &mut report)?;
+
+    for result in rows
+        .into_iter()
+        .map(|(_, fields)| StringRecord::from(fields))
         .filter(by_platform(platform))
         .skip_while(resumes(resume_from))
     {
@@ -269,6 +284,8 @@ fn source_contributors(
         }
     }
 
+    println!("Import report: {}", report);
+
     Ok(())
 }
 
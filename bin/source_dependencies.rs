@@ -1,7 +1,8 @@
 extern crate csv;
+extern crate osrank;
 extern crate serde;
 
-use csv::StringRecord;
+use osrank::importers::csv::{read_rows, CsvFile, ImportReport};
 use serde::Deserialize;
 
 use std::collections::HashSet;
@@ -35,7 +36,8 @@ fn source_dependencies(path: &str, platform: &str) -> Result<(), Box<dyn Error>>
     let dependencies_file = File::open(path)?;
 
     // Build the CSV reader and iterate over each record.
-    let mut rdr = csv::Reader::from_reader(dependencies_file);
+    let rdr = csv::Reader::from_reader(dependencies_file);
+    let mut report = ImportReport::default();
     let mut dependencies = OpenOptions::new()
         .write(true)
         .create_new(true)
@@ -53,12 +55,14 @@ fn source_dependencies(path: &str, platform: &str) -> Result<(), Box<dyn Error>>
     dependencies.write_all(b"FROM_ID,TO_ID\n")?;
     dependencies_meta.write_all(b"ID,NAME,PLATFORM\n")?;
 
-    for result in rdr
-        .records()
-        .filter_map(|e| e.ok())
+    let rows = read_rows::<Dependency, _>(rdr, CsvFile::Dependencies, &mut report)
+        .map_err(|e| e.to_string())?;
+
+    for dependency in rows
+        .into_iter()
+        .map(|(_, row)| row)
         .filter(by_platform(platform))
     {
-        let dependency: Dependency = result.deserialize(None)?;
         extract_dependency(&mut dependencies, &dependency, &mut unique_dependencies)?;
         extract_metadata(
             &mut unique_projects,
@@ -68,6 +72,8 @@ fn source_dependencies(path: &str, platform: &str) -> Result<(), Box<dyn Error>>
         )?;
     }
 
+    println!("Import report: {}", report);
+
     Ok(())
 }
 
@@ -115,8 +121,8 @@ fn extract_metadata(
     Ok(())
 }
 
-fn by_platform<'a>(platform: &'a str) -> Box<dyn FnMut(&StringRecord) -> bool + 'a> {
-    Box::new(move |e| e[1] == *platform)
+fn by_platform<'a>(platform: &'a str) -> Box<dyn FnMut(&Dependency) -> bool + 'a> {
+    Box::new(move |e| e.platform == *platform)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -1,45 +1,463 @@
 #![allow(unknown_lints)]
 #![warn(clippy::all)]
 
+/// Implementation of the incremental version of the Osrank algorithm, which
+/// stores the random walks between runs and re-simulates only the ones
+/// affected by a change in the graph, as described in
+/// [Bahmani et al.](http://snap.stanford.edu/class/cs224w-readings/bahmani10pagerank.pdf).
+extern crate fnv;
 extern crate oscoin_graph_api;
 extern crate petgraph;
 extern crate rand;
 extern crate rayon;
+extern crate serde;
 extern crate sprs;
 
+use crate::algorithm::naive::{
+    alias_tables_for, derive_rng, random_walk, rank_network, resume_walks, walks_from,
+};
 use crate::protocol_traits::graph::GraphExtras;
 use crate::protocol_traits::ledger::{LedgerView, MockLedger};
-use crate::types::mock::{Mock, MockAnnotator, MockNetwork};
-use crate::types::network::Artifact;
-use crate::types::walk::SeedSet;
-use crate::types::Osrank;
-use oscoin_graph_api::{Graph, GraphAlgorithm, GraphAnnotator, GraphObject, Id};
+use crate::types::mock::{Mock, MockAnnotator, MockEstimateAnnotator, MockNetwork};
+use crate::types::network::{Artifact, ArtifactType};
+use crate::types::snapshot::{Snapshot, SnapshotError, SnapshotKind};
+use crate::types::walk::{RandomWalk, RandomWalks, SeedSet};
+use crate::types::{Osrank, RankEstimate, R};
+use fnv::FnvHasher;
+use oscoin_graph_api::{Direction, Edge, Graph, GraphAlgorithm, GraphAnnotator, GraphObject, Id};
 use rand::distributions::uniform::SampleUniform;
 use rand::{Rng, SeedableRng};
 use rand_xoshiro::Xoshiro256StarStar;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 
-use std::hash::Hash;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::ops::AddAssign;
 
-use super::OsrankError;
+use super::{Normalised, OsrankError, OsrankOptions, TeleportMode};
 
+/// The state the incremental algorithm carries over from one run to the next.
+///
+/// It stores the `RandomWalks` computed by the last run, alongside the set
+/// of nodes which are the source of an edge added or removed since then and
+/// the number of runs performed so far. It also stores a digest of the
+/// outgoing edges of every node, as they were during the last run, so that
+/// the changes to the graph can be detected rather than recorded by hand.
+#[derive(Debug)]
+pub struct IncrementalState<I>
+where
+    I: Eq + Hash + Sync + Send,
+{
+    walks: Option<RandomWalks<I>>,
+    dirty_nodes: HashSet<I>,
+    generation: u64,
+    edge_digests: HashMap<I, u64>,
+}
+
+impl<I> Default for IncrementalState<I>
+where
+    I: Eq + Hash + Sync + Send,
+{
+    fn default() -> Self {
+        IncrementalState {
+            walks: None,
+            dirty_nodes: HashSet::default(),
+            generation: 0,
+            edge_digests: HashMap::default(),
+        }
+    }
+}
+
+impl<I> IncrementalState<I>
+where
+    I: Clone + Eq + Hash + Sync + Send,
+{
+    /// Creates a new, empty `IncrementalState`. The first run of the
+    /// algorithm using this state will walk the entire graph.
+    pub fn new() -> Self {
+        IncrementalState::default()
+    }
+
+    /// Records that a new edge going out of `source` has been added to the
+    /// graph since the last run.
+    pub fn edge_added(&mut self, source: I) {
+        self.dirty_nodes.insert(source);
+    }
+
+    /// Records that an edge going out of `source` has been removed from the
+    /// graph since the last run.
+    pub fn edge_removed(&mut self, source: I) {
+        self.dirty_nodes.insert(source);
+    }
+
+    /// Records as changed every node of the input network whose outgoing
+    /// edges (or their weights) differ from the ones the last run walked on,
+    /// which is what callers re-importing the whole graph every time need.
+    /// It returns the number of nodes found to have changed.
+    ///
+    /// The nodes which have been removed since need not be recorded, as the
+    /// walks visiting them are re-simulated anyway.
+    pub fn detect_changes<G>(&mut self, network: &G) -> usize
+    where
+        G: Graph,
+        G::Node: GraphObject<Id = I, Data = ArtifactType>,
+        G::Weight: Clone + Into<f64>,
+    {
+        if self.walks.is_none() {
+            return 0;
+        }
+
+        let mut changed = 0;
+        for node in network.nodes() {
+            if self.edge_digests.get(node.id()) != Some(&edges_digest(network, node.id())) {
+                self.dirty_nodes.insert(node.id().clone());
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Returns the walks computed by the last run, if any.
+    pub fn walks(&self) -> Option<&RandomWalks<I>> {
+        self.walks.as_ref()
+    }
+
+    /// Returns the number of runs performed so far.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Discards the stored walks, so that the next run will walk the entire
+    /// graph again.
+    pub fn reset(&mut self) {
+        self.walks = None;
+        self.dirty_nodes.clear();
+        self.edge_digests.clear();
+    }
+}
+
+/// The serialisable contents of an `IncrementalState` snapshot, where every
+/// walk is stored as its path.
+#[derive(Serialize, Deserialize)]
+pub struct IncrementalStateContents<I> {
+    walks: Option<Vec<Vec<I>>>,
+    dirty_nodes: Vec<I>,
+    generation: u64,
+    edge_digests: Vec<(I, u64)>,
+}
+
+impl<I> Snapshot for IncrementalState<I>
+where
+    I: Clone + Eq + Hash + Sync + Send + Serialize + DeserializeOwned,
+{
+    type Contents = IncrementalStateContents<I>;
+
+    const KIND: SnapshotKind = SnapshotKind::IncrementalState;
+
+    fn to_contents(&self) -> Self::Contents {
+        IncrementalStateContents {
+            walks: self
+                .walks
+                .as_ref()
+                .map(|walks| walks.iter().map(|walk| walk.path().to_vec()).collect()),
+            dirty_nodes: self.dirty_nodes.iter().cloned().collect(),
+            generation: self.generation,
+            edge_digests: self
+                .edge_digests
+                .iter()
+                .map(|(node_id, digest)| (node_id.clone(), *digest))
+                .collect(),
+        }
+    }
+
+    fn from_contents(contents: Self::Contents) -> Result<Self, SnapshotError> {
+        let walks = match contents.walks {
+            None => None,
+            Some(paths) => {
+                let mut walks = RandomWalks::new();
+                for path in paths {
+                    let mut segments = path.into_iter();
+                    let mut walk = match segments.next() {
+                        None => {
+                            return Err(SnapshotError::InvalidContents(String::from(
+                                "a walk has no source",
+                            )))
+                        }
+                        Some(source) => RandomWalk::new(source),
+                    };
+                    for segment in segments {
+                        walk.add_next(segment);
+                    }
+                    walks.add_walk(walk);
+                }
+                Some(walks)
+            }
+        };
+
+        Ok(IncrementalState {
+            walks,
+            dirty_nodes: contents.dirty_nodes.into_iter().collect(),
+            generation: contents.generation,
+            edge_digests: contents.edge_digests.into_iter().collect(),
+        })
+    }
+}
+
+/// Hashes the targets and the weights of the outgoing edges of the input
+/// node, as well as whether it's a project, which decides its damping factor.
+/// The digests of the edges are summed up, so that their order doesn't
+/// matter.
+fn edges_digest<G>(network: &G, node_id: &Id<G::Node>) -> u64
+where
+    G: Graph,
+    Id<G::Node>: Hash,
+    G::Node: GraphObject<Data = ArtifactType>,
+    G::Weight: Clone + Into<f64>,
+{
+    let is_project = match network.get_node(node_id).map(|node| node.data()) {
+        Some(ArtifactType::Project { .. }) => 1,
+        _ => 0,
+    };
+
+    network
+        .edges_directed(node_id, Direction::Outgoing)
+        .iter()
+        .filter_map(|edge_ref| {
+            network.get_edge(edge_ref.id).map(|edge| {
+                let mut hasher = FnvHasher::default();
+                edge_ref.to.hash(&mut hasher);
+                edge.weight().clone().into().to_bits().hash(&mut hasher);
+                hasher.finish()
+            })
+        })
+        .fold(is_project, u64::wrapping_add)
+}
+
+/// Returns the length of the prefix of the input walk which is still valid
+/// for the input network, or `None` if the walk is valid as a whole.
+///
+/// The prefix of a walk up to (and including) its first visit to a node
+/// whose outgoing edges have changed took the same steps with the same
+/// probabilities it would take on the new network, so it's only the rest of
+/// the walk which has to be re-simulated. A walk reaching a node which isn't
+/// part of the network anymore is cut right before it, as the node it came
+/// from must have lost an edge.
+fn valid_prefix_len<G>(
+    walk: &RandomWalk<Id<G::Node>>,
+    network: &G,
+    dirty_nodes: &HashSet<Id<G::Node>>,
+) -> Option<usize>
+where
+    G: Graph,
+    Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+{
+    walk.path().iter().enumerate().find_map(|(ix, node_id)| {
+        if network.get_node(node_id).is_none() {
+            Some(ix)
+        } else if dirty_nodes.contains(node_id) {
+            Some(ix + 1)
+        } else {
+            None
+        }
+    })
+}
+
+/// Brings the walks stored in the `IncrementalState` up-to-date with the
+/// input network `G`, following the scheme of Bahmani et al.
+///
+/// Every stored walk which visits a node recorded as changed is cut right
+/// after its first visit to it, and re-simulated from there on (see
+/// `valid_prefix_len`), whereas all the others are kept as they are. Walks
+/// starting from nodes which are not part of the network anymore are
+/// dropped, and so are the ones exceeding `R` for their source, in case `R`
+/// shrank since the last run. Nodes with fewer than `R` walks (for example
+/// because they have been added since the last run) get their missing walks
+/// simulated as well. The `AliasTables`, if requested, are built only when
+/// there are walks to simulate.
+fn update_walks<L, G, RNG>(
+    network: &G,
+    options: OsrankOptions,
+    ledger_view: &L,
+    rng: &RNG,
+    state: &mut IncrementalState<Id<G::Node>>,
+) -> Result<(), OsrankError>
+where
+    L: LedgerView + Send + Sync,
+    G: GraphExtras + Send + Sync,
+    Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
+    RNG: Rng + SeedableRng + Clone + Send + Sync,
+    <G as Graph>::Weight:
+        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform + Into<f64>,
+{
+    let walks_num = *ledger_view.get_random_walks_num();
+    let dirty_nodes = std::mem::replace(&mut state.dirty_nodes, HashSet::default());
+    let mut walks = state.walks.take().unwrap_or_else(RandomWalks::new);
+
+    // Like in `osrank_naive`, a node gets `R` walks for every time it occurs
+    // in the network.
+    let mut quotas: HashMap<&Id<G::Node>, R> = HashMap::default();
+    for node in network.nodes() {
+        *quotas.entry(node.id()).or_insert(0) += walks_num;
+    }
+
+    let mut walks_per_node: HashMap<Id<G::Node>, R> = HashMap::default();
+    let dropped = walks.retain(|walk| match quotas.get(walk.source()) {
+        None => false,
+        Some(quota) => {
+            let existing = walks_per_node.entry(walk.source().clone()).or_insert(0);
+            *existing += 1;
+            *existing <= *quota
+        }
+    });
+
+    let stale_walks: Vec<RandomWalk<Id<G::Node>>> = walks
+        .retain(|walk| valid_prefix_len(walk, network, &dirty_nodes).is_none())
+        .into_iter()
+        .map(|mut walk| {
+            if let Some(len) = valid_prefix_len(&walk, network, &dirty_nodes) {
+                walk.truncate(len);
+            }
+            walk
+        })
+        .collect();
+
+    debug!(
+        "Dropped {} walks and resuming {} stale ones out of {}.",
+        dropped.len(),
+        stale_walks.len(),
+        dropped.len() + stale_walks.len() + walks.len()
+    );
+
+    let missing_walks: Vec<(&Id<G::Node>, R)> = quotas
+        .into_iter()
+        .filter_map(|(node_id, quota)| {
+            let existing = walks_per_node.get(node_id).cloned().unwrap_or(0);
+            if existing < quota {
+                Some((node_id, quota - existing))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    if !stale_walks.is_empty() || !missing_walks.is_empty() {
+        // Each run derives its own RNGs, otherwise the re-simulated walks of
+        // a node would replay the very same stream used by its previous walks.
+        let seed: u64 = rng.clone().gen();
+        let tables = alias_tables_for(network, options);
+
+        if !stale_walks.is_empty() {
+            let resume_rng: RNG = derive_rng(seed, &(state.generation, "resume"));
+            walks.append(resume_walks(
+                stale_walks,
+                network,
+                ledger_view,
+                &resume_rng,
+                tables.as_ref(),
+            )?);
+        }
+
+        if !missing_walks.is_empty() {
+            let run_rng: RNG = derive_rng(seed, &state.generation);
+            walks.append(walks_from(
+                missing_walks,
+                network,
+                ledger_view,
+                &run_rng,
+                tables.as_ref(),
+            )?);
+        }
+    }
+
+    state.walks = Some(walks);
+    state.generation += 1;
+    Ok(())
+}
+
+/// Incremental version of the `osrank` algorithm.
+///
+/// Given a full network `G`, an optional `SeedSet` and the `IncrementalState`
+/// from the previous run, it re-simulates only the random walks affected by
+/// the changes recorded in the state, and then annotates the network with
+/// the new `Osrank`s. If the state is empty, it walks the entire network,
+/// like `osrank_naive` would.
+///
+/// When a `SeedSet` is given, only the second phase is incremental: the
+/// walks from the seeds which decide the trusted nodes (the first phase) are
+/// performed from scratch on every call, as the seeds might change between
+/// runs. They amount to `R` walks per seed, rather than per node.
+///
+/// Only the `Uniform` `TeleportMode` without the adaptive mode is supported;
+/// any other `OsrankOptions` yield an `OsrankError::UnsupportedOptions`.
+#[allow(clippy::too_many_arguments)]
 pub fn osrank_incremental<G, A>(
-    _seed_set: Option<&SeedSet<Id<G::Node>>>,
-    _network: &G,
-    _annotator: &mut A,
-    _ledger_view: &(impl LedgerView + Send + Sync),
-    _rng: &(impl Rng + SeedableRng + Clone + Send + Sync),
-    _to_annotation: &dyn Fn(&G::Node, Osrank) -> A::Annotation,
+    seed_set: Option<&SeedSet<Id<G::Node>>>,
+    options: OsrankOptions,
+    network: &G,
+    annotator: &mut A,
+    ledger_view: &(impl LedgerView + Send + Sync),
+    rng: &(impl Rng + SeedableRng + Clone + Send + Sync),
+    to_annotation: &dyn Fn(&G::Node, RankEstimate) -> A::Annotation,
+    state: &mut IncrementalState<Id<G::Node>>,
 ) -> Result<(), OsrankError>
 where
     G: GraphExtras + Clone + Send + Sync,
     A: GraphAnnotator,
     Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
     <G as Graph>::Weight:
-        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform,
+        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform + Into<f64>,
 {
-    unimplemented!("The incremental algorithm is not implemented yet.");
+    if options.teleport != TeleportMode::Uniform {
+        return Err(OsrankError::UnsupportedOptions(String::from(
+            "the incremental algorithm only supports the uniform teleport mode",
+        )));
+    }
+
+    if options.adaptive.is_some() {
+        return Err(OsrankError::UnsupportedOptions(String::from(
+            "the incremental algorithm doesn't support the adaptive mode",
+        )));
+    }
+
+    match seed_set {
+        Some(_) => {
+            // Phase1, rank the network and produce a NetworkView. The set of
+            // trusted nodes might change between runs, so this is always
+            // computed from scratch, but it only walks from the seeds.
+            let phase1 = random_walk(
+                seed_set,
+                network,
+                ledger_view,
+                rng,
+                alias_tables_for(network, options).as_ref(),
+            )?;
+
+            // Phase2, update the walks only on the NetworkView.
+            update_walks(&phase1.network_view, options, ledger_view, rng, state)?;
+        }
+        None => update_walks(network, options, ledger_view, rng, state)?,
+    }
+
+    state.edge_digests = network
+        .nodes()
+        .map(|node| (node.id().clone(), edges_digest(network, node.id())))
+        .collect();
+
+    match state.walks() {
+        None => Ok(()),
+        Some(walks) => rank_network(
+            walks,
+            network,
+            ledger_view,
+            options.normalise,
+            annotator,
+            to_annotation,
+        ),
+    }
 }
 
 pub struct OsrankIncrementalAlgorithm<'a, G: 'a, L, A: 'a> {
@@ -65,32 +483,86 @@ pub struct OsrankIncrementalMockContext<'a, A, G = MockNetwork>
 where
     G: Graph,
     A: GraphAnnotator,
+    Id<G::Node>: Eq + Hash + Send + Sync,
 {
     /// The optional `SeetSet` to use.
     pub seed_set: Option<&'a SeedSet<Id<G::Node>>>,
+    /// The `OsrankOptions`. Only the `Uniform` `TeleportMode` without the
+    /// adaptive mode is supported.
+    pub options: OsrankOptions,
     /// The `LedgerView` for this context, i.e. a `MockLedger`.
     pub ledger_view: MockLedger,
     /// The `to_annotation` function is a "getter" function that given a
-    /// generic `Node` "knows" how to extract an annotation out of an `Osrank`.
-    /// This function is necessary to bridge the gap between the algorithm being
-    /// written in a totally generic way and the need to convert from a
-    /// fraction-like `Osrank` into an `A::Annotation`.
-    pub to_annotation: &'a (dyn Fn(&G::Node, Osrank) -> A::Annotation),
+    /// generic `Node` "knows" how to extract an annotation out of a
+    /// `RankEstimate`. This function is necessary to bridge the gap between
+    /// the algorithm being written in a totally generic way and the need to
+    /// convert from a fraction-like `Osrank` (and its standard error) into an
+    /// `A::Annotation`.
+    pub to_annotation: &'a (dyn Fn(&G::Node, RankEstimate) -> A::Annotation),
+    /// The walks from the previous run, alongside the changes to the graph
+    /// which happened since. It gets updated by every `execute`.
+    pub state: IncrementalState<Id<G::Node>>,
 }
 
 impl<'a> Default for OsrankIncrementalMockContext<'a, MockAnnotator<MockNetwork>, MockNetwork> {
     fn default() -> Self {
         OsrankIncrementalMockContext {
             seed_set: None,
+            options: OsrankOptions::default(),
             ledger_view: MockLedger::default(),
             to_annotation: &mock_network_to_annotation,
+            state: IncrementalState::new(),
+        }
+    }
+}
+
+impl<'a> Default
+    for OsrankIncrementalMockContext<
+        'a,
+        MockAnnotator<Normalised<MockNetwork>>,
+        Normalised<MockNetwork>,
+    >
+{
+    fn default() -> Self {
+        OsrankIncrementalMockContext {
+            seed_set: None,
+            options: OsrankOptions::default(),
+            ledger_view: MockLedger::default(),
+            to_annotation: &mock_network_to_annotation,
+            state: IncrementalState::new(),
+        }
+    }
+}
+
+impl<'a> Default
+    for OsrankIncrementalMockContext<
+        'a,
+        MockEstimateAnnotator<Normalised<MockNetwork>>,
+        Normalised<MockNetwork>,
+    >
+{
+    fn default() -> Self {
+        OsrankIncrementalMockContext {
+            seed_set: None,
+            options: OsrankOptions::default(),
+            ledger_view: MockLedger::default(),
+            to_annotation: &mock_network_to_estimate_annotation,
+            state: IncrementalState::new(),
         }
     }
 }
 
-fn mock_network_to_annotation(node: &Artifact<String>, rank: Osrank) -> (String, Osrank) {
+fn mock_network_to_annotation(node: &Artifact<String>, estimate: RankEstimate) -> (String, Osrank) {
+    let artifact_id = node.id().clone();
+    (artifact_id, estimate.rank)
+}
+
+fn mock_network_to_estimate_annotation(
+    node: &Artifact<String>,
+    estimate: RankEstimate,
+) -> (String, RankEstimate) {
     let artifact_id = node.id().clone();
-    (artifact_id, rank)
+    (artifact_id, estimate)
 }
 
 /// A *mock* implementation for the `OsrankIncrementalAlgorithm`, using the `Mock`
@@ -115,8 +587,9 @@ where
     G: GraphExtras + Clone + Send + Sync,
     L: LedgerView + Send + Sync,
     Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
     <G as Graph>::Weight:
-        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform,
+        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform + Into<f64>,
     OsrankIncrementalMockContext<'a, A, G>: Default,
     A: GraphAnnotator,
 {
@@ -136,11 +609,369 @@ where
         let rng = <Xoshiro256StarStar as SeedableRng>::from_seed(initial_seed);
         osrank_incremental(
             ctx.seed_set,
+            ctx.options,
             graph,
             annotator,
             &ctx.ledger_view,
             &rng,
             &ctx.to_annotation,
+            &mut ctx.state,
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    extern crate oscoin_graph_api;
+    extern crate quickcheck;
+
+    use super::*;
+    use crate::protocol_traits::ledger::MockLedger;
+    use crate::types::mock::{Mock, MockAnnotator, MockNetwork};
+    use crate::types::network::{ArtifactType, DependencyType, Network};
+    use crate::types::snapshot::{load_snapshot, save_snapshot};
+    use crate::types::Weight;
+    use fraction::ToPrimitive;
+    use num_traits::Zero;
+    use oscoin_graph_api::{GraphAlgorithm, GraphWriter};
+    use quickcheck::{quickcheck, TestResult};
+
+    type MockIncrementalAlgorithm<'a> =
+        Mock<OsrankIncrementalAlgorithm<'a, MockNetwork, MockLedger, MockAnnotator<MockNetwork>>>;
+
+    fn network_fixture() -> MockNetwork {
+        let mut network = Network::default();
+        for node in &["p1", "p2", "p3"] {
+            network.add_node(
+                node.to_string(),
+                ArtifactType::Project {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+
+        for node in &["a1", "a2", "a3", "isle"] {
+            network.add_node(
+                node.to_string(),
+                ArtifactType::Account {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+
+        let edges = [
+            ("p1", "a1", Weight::new(3, 7)),
+            ("a1", "p1", Weight::new(1, 1)),
+            ("p1", "p2", Weight::new(4, 7)),
+            ("p2", "a2", Weight::new(1, 1)),
+            ("a2", "p2", Weight::new(1, 3)),
+            ("a2", "p3", Weight::new(2, 3)),
+            ("p3", "a2", Weight::new(11, 28)),
+            ("p3", "a3", Weight::new(1, 28)),
+            ("p3", "p1", Weight::new(2, 7)),
+            ("p3", "p2", Weight::new(2, 7)),
+            ("a3", "p3", Weight::new(1, 1)),
+        ];
+
+        for (ix, edge) in edges.iter().enumerate() {
+            network.add_edge(
+                ix,
+                &edge.0.to_string(),
+                &edge.1.to_string(),
+                edge.2.as_f64().unwrap(),
+                DependencyType::Influence(edge.2.as_f64().unwrap()),
+            )
+        }
+
+        network
+    }
+
+    // Sums the visits for the input node, excluding the walks originating
+    // from `excluded_source`.
+    fn visits_excluding(walks: &RandomWalks<String>, node: &str, excluded_source: &str) -> usize {
+        walks
+            .iter()
+            .filter(|w| w.source() != excluded_source)
+            .map(|w| w.count_visits(&node.to_string()))
+            .sum()
+    }
+
+    #[test]
+    fn first_run_walks_every_node() {
+        let network = network_fixture();
+        let algo: MockIncrementalAlgorithm = Mock {
+            unmock: OsrankIncrementalAlgorithm::default(),
+        };
+        let mut ctx = OsrankIncrementalMockContext::default();
+        let mut annotator: MockAnnotator<MockNetwork> = Default::default();
+
+        assert_eq!(
+            algo.execute(&mut ctx, &network, &mut annotator, [0; 32]),
+            Ok(())
+        );
+
+        let walks_num = *ctx.ledger_view.get_random_walks_num() as usize;
+        assert_eq!(
+            ctx.state.walks().map(|w| w.len()),
+            Some(network.node_count() * walks_num)
+        );
+        assert_eq!(annotator.annotator.len(), network.node_count());
+    }
+
+    #[test]
+    // "isle" is an isolated node, so no walk but the ones originating from it
+    // can pass through it. Adding an edge out of "isle" must re-simulate only
+    // those walks.
+    fn edge_change_resimulates_only_stale_walks() {
+        let mut network = network_fixture();
+        let algo: MockIncrementalAlgorithm = Mock {
+            unmock: OsrankIncrementalAlgorithm::default(),
+        };
+        let mut ctx = OsrankIncrementalMockContext::default();
+        let mut annotator: MockAnnotator<MockNetwork> = Default::default();
+
+        assert!(algo
+            .execute(&mut ctx, &network, &mut annotator, [0; 32])
+            .is_ok());
+
+        let nodes = ["p1", "p2", "p3", "a1", "a2", "a3"];
+        let before = nodes
+            .iter()
+            .map(|n| visits_excluding(ctx.state.walks().unwrap(), n, "isle"))
+            .collect::<Vec<usize>>();
+
+        network.add_edge(
+            11,
+            &"isle".to_string(),
+            &"p1".to_string(),
+            1.0,
+            DependencyType::Influence(1.0),
+        );
+        ctx.state.edge_added("isle".to_string());
+
+        assert!(algo
+            .execute(&mut ctx, &network, &mut annotator, [1; 32])
+            .is_ok());
+
+        let after = nodes
+            .iter()
+            .map(|n| visits_excluding(ctx.state.walks().unwrap(), n, "isle"))
+            .collect::<Vec<usize>>();
+
+        let walks_num = *ctx.ledger_view.get_random_walks_num() as usize;
+        assert_eq!(before, after);
+        assert_eq!(
+            ctx.state.walks().map(|w| w.len()),
+            Some(network.node_count() * walks_num)
+        );
+        assert_eq!(
+            ctx.state
+                .walks()
+                .map(|w| w.count_walks_from(&"isle".to_string())),
+            Some(walks_num)
+        );
+    }
+
+    #[test]
+    fn shrinking_r_trims_the_walks_of_every_node() {
+        let network = network_fixture();
+        let algo: MockIncrementalAlgorithm = Mock {
+            unmock: OsrankIncrementalAlgorithm::default(),
+        };
+        let mut ctx = OsrankIncrementalMockContext::default();
+        let mut annotator: MockAnnotator<MockNetwork> = Default::default();
+
+        ctx.ledger_view.set_random_walks_num(5);
+        assert!(algo
+            .execute(&mut ctx, &network, &mut annotator, [0; 32])
+            .is_ok());
+
+        ctx.ledger_view.set_random_walks_num(2);
+        assert!(algo
+            .execute(&mut ctx, &network, &mut annotator, [0; 32])
+            .is_ok());
+
+        let walks = ctx.state.walks().unwrap();
+        assert_eq!(walks.len(), network.node_count() * 2);
+        for node in network.nodes() {
+            assert_eq!(walks.count_walks_from(node.id()), 2);
+        }
+    }
+
+    // A normalised network of three nodes, where the outgoing edges of "n1"
+    // are given as input.
+    fn three_nodes_network(n1_edges: &[(&str, f64)]) -> Normalised<MockNetwork> {
+        let mut network = Normalised::new(Network::default());
+
+        for node in &["n0", "n1", "n2"] {
+            network.add_node(
+                node.to_string(),
+                ArtifactType::Project {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+
+        let edges = [("n0", "n0", 0.5), ("n0", "n1", 0.5), ("n2", "n2", 1.0)]
+            .iter()
+            .cloned()
+            .chain(n1_edges.iter().map(|(to, weight)| ("n1", *to, *weight)));
+
+        for (ix, edge) in edges.enumerate() {
+            network.add_edge(
+                ix,
+                &edge.0.to_string(),
+                &edge.1.to_string(),
+                edge.2,
+                DependencyType::Influence(edge.2),
+            )
+        }
+
+        network
+    }
+
+    // Test that, after the outgoing edges of a node change, the updated
+    // walks still converge to the exact PageRank of the new network. Walks
+    // from "n0" visit "n1" about half of the times: re-simulating them
+    // from scratch, rather than from their first visit to "n1", would
+    // underestimate the rank "n1" gets out of them.
+    #[test]
+    fn updated_walks_agree_with_exact_pagerank() {
+        let algo: Mock<
+            OsrankIncrementalAlgorithm<
+                Normalised<MockNetwork>,
+                MockLedger,
+                MockAnnotator<Normalised<MockNetwork>>,
+            >,
+        > = Mock {
+            unmock: OsrankIncrementalAlgorithm::default(),
+        };
+        let mut ctx = OsrankIncrementalMockContext {
+            options: OsrankOptions {
+                normalise: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        ctx.ledger_view.set_random_walks_num(5_000);
+
+        let before = three_nodes_network(&[("n0", 0.5), ("n2", 0.5)]);
+        let mut annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+        assert_eq!(
+            algo.execute(&mut ctx, &before, &mut annotator, [0; 32]),
+            Ok(())
+        );
+
+        let after = three_nodes_network(&[("n1", 1.0)]);
+        assert_eq!(ctx.state.detect_changes(&after), 1);
+
+        let mut annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+        assert_eq!(
+            algo.execute(&mut ctx, &after, &mut annotator, [0; 32]),
+            Ok(())
+        );
+        assert_eq!(ctx.state.detect_changes(&after), 0);
+
+        let mut exact: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+        assert_eq!(
+            crate::algorithm::pagerank::pagerank_exact(
+                &after,
+                &mut exact,
+                &ctx.ledger_view,
+                1e-10,
+                1_000,
+                &|node, estimate| (node.id().clone(), estimate.rank),
+            ),
+            Ok(())
+        );
+
+        for (id, rank) in &annotator.annotator {
+            let expected = exact.annotator[id].to_f64().unwrap();
+            let actual = rank.to_f64().unwrap();
+            assert!(
+                (actual - expected).abs() < 0.01,
+                "{}: {} (expected {})",
+                id,
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn state_snapshots_roundtrip() {
+        let network = network_fixture();
+        let algo: MockIncrementalAlgorithm = Mock {
+            unmock: OsrankIncrementalAlgorithm::default(),
+        };
+        let mut ctx = OsrankIncrementalMockContext::default();
+        let mut annotator1: MockAnnotator<MockNetwork> = Default::default();
+        let mut annotator2: MockAnnotator<MockNetwork> = Default::default();
+
+        assert!(algo
+            .execute(&mut ctx, &network, &mut annotator1, [0; 32])
+            .is_ok());
+        ctx.state.edge_added("a1".to_string());
+
+        let mut bytes = Vec::new();
+        save_snapshot(&ctx.state, &mut bytes).unwrap();
+        let mut loaded = OsrankIncrementalMockContext {
+            state: load_snapshot(&bytes[..]).unwrap(),
+            ..Default::default()
+        };
+
+        let paths_of = |state: &IncrementalState<String>| {
+            let mut paths: Vec<Vec<String>> = state
+                .walks()
+                .unwrap()
+                .iter()
+                .map(|walk| walk.path().to_vec())
+                .collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(paths_of(&loaded.state), paths_of(&ctx.state));
+        assert_eq!(loaded.state.generation(), 1);
+        assert_eq!(loaded.state.detect_changes(&network), 0);
+
+        assert!(algo
+            .execute(&mut ctx, &network, &mut annotator1, [0; 32])
+            .is_ok());
+        assert!(algo
+            .execute(&mut loaded, &network, &mut annotator2, [0; 32])
+            .is_ok());
+        assert_eq!(annotator1.annotator, annotator2.annotator);
+    }
+
+    // Test that running the incremental algorithm twice, without any change
+    // in between, yields exactly the same ranks.
+    fn prop_incremental_without_changes_is_stable(graph: MockNetwork) -> TestResult {
+        if graph.is_empty() {
+            return TestResult::discard();
+        }
+
+        let algo: MockIncrementalAlgorithm = Mock {
+            unmock: OsrankIncrementalAlgorithm::default(),
+        };
+        let mut ctx = OsrankIncrementalMockContext::default();
+        let mut annotator1: MockAnnotator<MockNetwork> = Default::default();
+        let mut annotator2: MockAnnotator<MockNetwork> = Default::default();
+
+        assert_eq!(
+            algo.execute(&mut ctx, &graph, &mut annotator1, [0; 32]),
+            Ok(())
+        );
+        assert_eq!(
+            algo.execute(&mut ctx, &graph, &mut annotator2, [1; 32]),
+            Ok(())
+        );
+
+        TestResult::from_bool(annotator1.annotator == annotator2.annotator)
+    }
+
+    #[test]
+    fn incremental_without_changes_is_stable() {
+        quickcheck(prop_incremental_without_changes_is_stable as fn(MockNetwork) -> TestResult);
+    }
+}
@@ -2,12 +2,26 @@
 pub mod incremental;
 /// Naive Osrank algorithm.
 pub mod naive;
+/// Normalisation of networks with typed edges.
+pub mod normalisation;
+/// Exact PageRank, via sparse power iteration.
+pub mod pagerank;
 
 use crate::protocol_traits::graph::GraphExtras;
+use crate::types::network::Network;
+use crate::types::snapshot::{Snapshot, SnapshotError, SnapshotKind};
+use crate::types::MaxWalkLength;
 use oscoin_graph_api::{
     Data, Direction, EdgeRefs, Edges, Graph, GraphDataWriter, GraphWriter, Id, Nodes, NodesMut,
 };
 use quickcheck::{Arbitrary, Gen};
+use rand::distributions::uniform::SampleUniform;
+use rand::distributions::WeightedError;
+use rand::Rng;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::AddAssign;
+use std::path::Path;
 
 /// Shared types between algorithms.
 
@@ -35,6 +49,19 @@ where
     }
 }
 
+impl<NodeId, EdgeId, W> Normalised<Network<NodeId, EdgeId, W>>
+where
+    NodeId: Clone + Eq + Hash + fmt::Display,
+    EdgeId: Clone + Eq + Hash + fmt::Display,
+    W: fmt::Display + Clone,
+{
+    /// Debug-only function to render a normalised Network into a Graphiz dot
+    /// file.
+    pub fn to_graphviz_dot(&self, output_path: &Path) -> Result<(), Box<std::io::Error>> {
+        self.normalised_graph.to_graphviz_dot(output_path)
+    }
+}
+
 /// Conceptually the companion for the `Normalised` newtype, this trait ensure
 /// that certain `GraphAlgorithm` implementation can be given only in terms
 /// of normalised graphs. This is the case for the "naive" algorithm, which
@@ -179,6 +206,106 @@ where
             normalised_graph: self.normalised_graph.subgraph_by_nodes(sub_nodes),
         }
     }
+
+    fn choose_outgoing<R>(
+        &self,
+        node_id: &Id<Self::Node>,
+        rng: &mut R,
+    ) -> Result<Option<&Id<Self::Node>>, WeightedError>
+    where
+        R: Rng + ?Sized,
+        Self::Weight:
+            Default + Clone + PartialOrd + for<'x> AddAssign<&'x Self::Weight> + SampleUniform,
+    {
+        self.normalised_graph.choose_outgoing(node_id, rng)
+    }
+}
+
+/// A `Normalised` graph is snapshotted like the graph it wraps, but gets
+/// recorded as a different kind, so that a raw network can never be loaded
+/// back as a normalised one (and viceversa).
+impl<G> Snapshot for Normalised<G>
+where
+    G: Graph + Snapshot,
+{
+    type Contents = G::Contents;
+
+    const KIND: SnapshotKind = SnapshotKind::Normalised;
+
+    fn to_contents(&self) -> Self::Contents {
+        self.normalised_graph.to_contents()
+    }
+
+    fn from_contents(contents: Self::Contents) -> Result<Self, SnapshotError> {
+        G::from_contents(contents).map(Normalised::new)
+    }
+}
+
+/// Where the random walks (re)start from, i.e. where a random surfer
+/// _teleports_ to once it stops following the edges of the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeleportMode {
+    /// Walks start from every node of the network, after the optional
+    /// pruning phase driven by the `SeedSet`.
+    Uniform,
+    /// Walks start only from the nodes of the `SeedSet`, proportionally to
+    /// their trust score. This computes a personalized Osrank, where all the
+    /// rank flows from the trusted seeds.
+    Personalized,
+}
+
+impl Default for TeleportMode {
+    fn default() -> Self {
+        TeleportMode::Uniform
+    }
+}
+
+/// Options which tweak how the Osrank is computed and reported.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct OsrankOptions {
+    /// Where the random walks (re)start from.
+    pub teleport: TeleportMode,
+    /// Whether the ranks should be scaled so that they sum up to exactly 1.
+    pub normalise: bool,
+    /// If set, the walks are performed in rounds until the ranks converge,
+    /// rather than performing exactly `R` walks from each node.
+    pub adaptive: Option<AdaptiveWalks>,
+    /// Whether the walks should pick the next node out of precomputed
+    /// `AliasTables`, which takes constant time rather than time linear in
+    /// the out-degree of the current node. The tables are built once per
+    /// run, which pays off for large networks with high-degree nodes.
+    pub alias_tables: bool,
+}
+
+/// The configuration for the adaptive mode, where the walks are performed in
+/// rounds, each of which performs `R` walks from each starting node. After
+/// each round the new ranks get compared with the ones from the previous
+/// round, and the walks stop once they are close enough, or once the next
+/// round would exceed the walk budget.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveWalks {
+    /// The walks stop once the L1 distance between the ranks of two
+    /// consecutive rounds falls below this threshold.
+    pub tolerance: f64,
+    /// If set, the walks also require the set of the `k` highest-ranked nodes
+    /// to be the same in two consecutive rounds.
+    pub top_k: Option<usize>,
+    /// The maximum number of walks to perform, across all rounds.
+    pub max_walks: usize,
+}
+
+/// How the adaptive mode went, i.e. how many rounds it needed and whether
+/// the ranks converged before the walk budget ran out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Convergence {
+    /// The number of rounds performed.
+    pub rounds: usize,
+    /// The number of walks performed, across all rounds.
+    pub walks: usize,
+    /// The L1 distance between the ranks of the last two rounds.
+    pub l1_distance: f64,
+    /// Whether the ranks converged within the walk budget.
+    pub converged: bool,
 }
 
 #[derive(Debug, Display, PartialEq, Eq)]
@@ -188,6 +315,17 @@ pub enum OsrankError {
     /// algorithm.
     UnknownError,
     RngFailedToSplit(String),
+    /// A random walk was cut off after reaching the maximum walk length
+    /// allowed by the `LedgerView`, which is carried by this variant.
+    WalkLengthExceeded(MaxWalkLength),
+    /// The exact PageRank didn't converge within the given number of
+    /// iterations, which is carried by this variant.
+    PageRankDidNotConverge(usize),
+    /// The `SeedSet` is missing or can't be used, for example because all
+    /// the trust scores are zero.
+    InvalidSeedSet(String),
+    /// The algorithm doesn't support the requested `OsrankOptions`.
+    UnsupportedOptions(String),
 }
 
 impl From<rand::Error> for OsrankError {
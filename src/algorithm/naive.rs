@@ -3,6 +3,7 @@
 
 /// Implementation of a naive (but correct) version of the Osrank algorithm,
 /// which walks the entire graph every time.
+extern crate fnv;
 extern crate ndarray;
 extern crate oscoin_graph_api;
 extern crate petgraph;
@@ -12,26 +13,33 @@ extern crate sprs;
 
 use crate::protocol_traits::graph::GraphExtras;
 use crate::protocol_traits::ledger::{LedgerView, MockLedger};
-use crate::types::mock::{Mock, MockAnnotator, MockNetwork};
-use crate::types::network::Artifact;
-use crate::types::walk::{RandomWalk, RandomWalks, SeedSet};
-use crate::types::Osrank;
+use crate::types::alias::AliasTables;
+use crate::types::mock::{Mock, MockAnnotator, MockEstimateAnnotator, MockNetwork};
+use crate::types::network::{Artifact, ArtifactType};
+use crate::types::walk::{RandomWalk, RandomWalks, SeedSet, VisitCounts};
+use crate::types::{
+    approximate_fraction, DampingFactors, MaxWalkLength, Osrank, RankEstimate,
+    MAX_DAMPING_DENOMINATOR, R,
+};
 use core::iter::Iterator;
-use fraction::Fraction;
+use fnv::FnvHasher;
+use fraction::{Fraction, ToPrimitive};
 use num_traits::{One, Zero};
-use oscoin_graph_api::{Direction, Edge, Graph, GraphAlgorithm, GraphAnnotator, GraphObject, Id};
+use oscoin_graph_api::{Graph, GraphAlgorithm, GraphAnnotator, GraphObject, Id};
 use rand::distributions::uniform::SampleUniform;
-use rand::distributions::WeightedError;
-use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
 use rand_xoshiro::Xoshiro256StarStar;
 use rayon::prelude::*;
 
-use std::hash::Hash;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::ops::AddAssign;
 
-use super::{Normalised, NormalisedGraph, OsrankError};
+use super::{
+    AdaptiveWalks, Convergence, Normalised, NormalisedGraph, OsrankError, OsrankOptions,
+    TeleportMode,
+};
 
 #[derive(Debug)]
 /// The output from a random walk.
@@ -42,7 +50,7 @@ pub struct WalkResult<G, I>
 where
     I: Eq + Hash + Sync + Send,
 {
-    network_view: G,
+    pub(crate) network_view: G,
     pub walks: RandomWalks<I>,
 }
 
@@ -51,59 +59,69 @@ fn walks<'a, L, G: 'a, RNG>(
     network: &G,
     ledger_view: &L,
     rng: &RNG,
+    sampler: Option<&AliasTables<Id<G::Node>>>,
 ) -> Result<RandomWalks<Id<G::Node>>, OsrankError>
 where
     L: LedgerView + Send + Sync,
     G: GraphExtras + Send + Sync,
     Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
     RNG: Rng + SeedableRng + Clone + Send + Sync,
     <G as Graph>::Weight:
         Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform,
 {
+    let walks_num = *ledger_view.get_random_walks_num();
+    walks_from(
+        starting_nodes.into_par_iter().map(move |i| (i, walks_num)),
+        network,
+        ledger_view,
+        rng,
+        sampler,
+    )
+}
+
+/// Like `walks`, but it allows the caller to specify how many walks should
+/// be performed from each starting node, rather than using the `R` from the
+/// `LedgerView`. This is what the incremental algorithm uses to re-simulate
+/// only a subset of the walks.
+///
+/// If some `AliasTables` are given, they must have been built out of the
+/// input network, and are used to pick the next node of every walk.
+pub(crate) fn walks_from<'a, L, G: 'a, RNG>(
+    starting_nodes: impl IntoParallelIterator<Item = (&'a Id<G::Node>, R)>,
+    network: &G,
+    ledger_view: &L,
+    rng: &RNG,
+    sampler: Option<&AliasTables<Id<G::Node>>>,
+) -> Result<RandomWalks<Id<G::Node>>, OsrankError>
+where
+    L: LedgerView + Send + Sync,
+    G: GraphExtras + Send + Sync,
+    Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
+    RNG: Rng + SeedableRng + Clone + Send + Sync,
+    <G as Graph>::Weight:
+        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform,
+{
+    // Drawn once, so that the stream of every starting node depends only on
+    // the master RNG and on the node itself, and not on the order (or the
+    // thread) in which the nodes are walked.
+    let master_seed: u64 = rng.clone().gen();
+
     let res = starting_nodes
         .into_par_iter()
-        .map(move |i| {
+        .map(move |(i, walks_num)| {
             let mut thread_walks = RandomWalks::new();
-
-            // ATTENTION: The accuracy (or lack thereof) of the final ranks
-            // depends on the goodness of the input RNG. In particular, using
-            // a poor RNG for testing might result in an osrank which is > 1.0.
-            // An example of this is the `XorShiftRng`. Quoting the documentation
-            // for `from_rng`:
-            //
-            // "The master PRNG should be at least as high quality as the child PRNGs.
-            // When seeding non-cryptographic child PRNGs, we recommend using a
-            // different algorithm for the master PRNG (ideally a CSPRNG) to avoid
-            // correlations between the child PRNGs. If this is not possible (e.g. forking using
-            // small non-crypto PRNGs) ensure that your PRNG has a good mixing function on the
-            // output or consider use of a hash function with from_seed.
-            // Note that seeding XorShiftRng from another XorShiftRng provides an extreme example
-            // of what can go wrong: the new PRNG will be a clone of the parent."
-
-            let mut thread_rng: RNG = SeedableRng::from_rng(rng.clone())?;
-
-            for _ in 0..(*ledger_view.get_random_walks_num()) {
-                let mut walk = RandomWalk::new(i.clone());
-                let mut current_node = i;
-                // TODO distinguish account/project
-                // TODO Should there be a safeguard so this doesn't run forever?
-                while thread_rng.gen::<f64>() < ledger_view.get_damping_factors().project {
-                    let neighbors = network.edges_directed(&current_node, Direction::Outgoing);
-                    match neighbors.choose_weighted(&mut thread_rng, |item| {
-                        network
-                            .get_edge(&item.id)
-                            .and_then(|m| Some(m.weight()))
-                            .unwrap()
-                    }) {
-                        Ok(next_edge) => {
-                            walk.add_next(next_edge.to.clone());
-                            current_node = next_edge.to;
-                        }
-                        Err(WeightedError::NoItem) => break,
-                        Err(error) => panic!("Problem with the neighbors: {:?}", error),
-                    }
-                }
-                thread_walks.add_walk(walk);
+            let mut thread_rng: RNG = derive_rng(master_seed, i);
+
+            for _ in 0..walks_num {
+                thread_walks.add_walk(walk_from(
+                    i,
+                    network,
+                    ledger_view,
+                    &mut thread_rng,
+                    sampler,
+                )?);
             }
 
             Ok(thread_walks)
@@ -125,31 +143,217 @@ where
     }
 }
 
+/// Resumes the input walks, each from its last segment, over the input
+/// network `G`. This is what the incremental algorithm uses to re-simulate
+/// the walks it cut at a node whose outgoing edges have changed.
+///
+/// The walks of each source share a stream derived from the master RNG and
+/// the source itself, exactly as in `walks_from`, and are resumed in the
+/// order they are given.
+pub(crate) fn resume_walks<L, G, RNG>(
+    stale_walks: Vec<RandomWalk<Id<G::Node>>>,
+    network: &G,
+    ledger_view: &L,
+    rng: &RNG,
+    sampler: Option<&AliasTables<Id<G::Node>>>,
+) -> Result<RandomWalks<Id<G::Node>>, OsrankError>
+where
+    L: LedgerView + Send + Sync,
+    G: GraphExtras + Send + Sync,
+    Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
+    RNG: Rng + SeedableRng + Clone + Send + Sync,
+    <G as Graph>::Weight:
+        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform,
+{
+    let master_seed: u64 = rng.clone().gen();
+
+    let mut walks_per_source = HashMap::new();
+    for walk in stale_walks {
+        walks_per_source
+            .entry(walk.source().clone())
+            .or_insert_with(Vec::new)
+            .push(walk);
+    }
+
+    let res = walks_per_source
+        .into_par_iter()
+        .map(move |(source, source_walks)| {
+            let mut thread_walks = RandomWalks::new();
+            let mut thread_rng: RNG = derive_rng(master_seed, &source);
+
+            for walk in source_walks {
+                thread_walks.add_walk(continue_walk(
+                    walk,
+                    network,
+                    ledger_view,
+                    &mut thread_rng,
+                    sampler,
+                )?);
+            }
+
+            Ok(thread_walks)
+        })
+        .reduce_with(|acc, w2| match (acc, w2) {
+            (Err(e), _) => Err(e),
+            (_, Err(e)) => Err(e),
+            (Ok(mut w1), Ok(w2)) => {
+                w1.append(w2);
+                Ok(w1)
+            }
+        });
+
+    res.unwrap_or_else(|| Ok(RandomWalks::new()))
+}
+
+/// Derives a new, independent RNG from the input `master_seed` and `key`.
+///
+/// The `key` is hashed together with the seed using a hasher whose output is
+/// stable across runs and platforms, which makes the derived RNG fully
+/// reproducible. This is used to give each starting node its own stream.
+pub(crate) fn derive_rng<RNG, K>(master_seed: u64, key: &K) -> RNG
+where
+    RNG: SeedableRng,
+    K: Hash + ?Sized,
+{
+    let mut hasher = FnvHasher::default();
+    master_seed.hash(&mut hasher);
+    key.hash(&mut hasher);
+    RNG::seed_from_u64(hasher.finish())
+}
+
+/// Performs a single random walk over the input network `G`, starting from
+/// the input node.
+///
+/// Returns an `OsrankError::WalkLengthExceeded` if the walk would take more
+/// steps than the maximum walk length specified by the `LedgerView`.
+fn walk_from<L, G, RNG>(
+    starting_node: &Id<G::Node>,
+    network: &G,
+    ledger_view: &L,
+    rng: &mut RNG,
+    sampler: Option<&AliasTables<Id<G::Node>>>,
+) -> Result<RandomWalk<Id<G::Node>>, OsrankError>
+where
+    L: LedgerView,
+    G: GraphExtras,
+    Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
+    RNG: Rng,
+    <G as Graph>::Weight:
+        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform,
+{
+    continue_walk(
+        RandomWalk::new(starting_node.clone()),
+        network,
+        ledger_view,
+        rng,
+        sampler,
+    )
+}
+
+/// Continues the input walk from its last segment, as if it had just reached
+/// it. The steps it already took count towards the maximum walk length.
+fn continue_walk<L, G, RNG>(
+    mut walk: RandomWalk<Id<G::Node>>,
+    network: &G,
+    ledger_view: &L,
+    rng: &mut RNG,
+    sampler: Option<&AliasTables<Id<G::Node>>>,
+) -> Result<RandomWalk<Id<G::Node>>, OsrankError>
+where
+    L: LedgerView,
+    G: GraphExtras,
+    Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
+    RNG: Rng,
+    <G as Graph>::Weight:
+        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform,
+{
+    let damping_factors = ledger_view.get_damping_factors();
+    let max_walk_length = *ledger_view.get_max_walk_length();
+    let mut steps = (walk.path().len() - 1) as MaxWalkLength;
+    let mut current_node = match network.get_node(walk.last()) {
+        Some(node) => node.id(),
+        None => return Ok(walk),
+    };
+    while rng.gen::<f64>() < damping_factor_of(network, current_node, damping_factors) {
+        let next_node = match sampler {
+            Some(tables) => tables.sample(current_node, rng),
+            None => match network.choose_outgoing(current_node, rng) {
+                Ok(next_node) => next_node,
+                Err(error) => panic!("Problem with the neighbors: {:?}", error),
+            },
+        };
+
+        match next_node {
+            Some(next_node) => {
+                if steps >= max_walk_length {
+                    return Err(OsrankError::WalkLengthExceeded(max_walk_length));
+                }
+                steps += 1;
+                walk.add_next(next_node.clone());
+                current_node = next_node;
+            }
+            None => break,
+        }
+    }
+    Ok(walk)
+}
+
+/// Returns the damping factor to use when leaving the input node, which
+/// depends on whether the node is a project or an account. Nodes which are not
+/// part of the network default to the project damping factor.
+fn damping_factor_of<G>(network: &G, node_id: &Id<G::Node>, damping_factors: &DampingFactors) -> f64
+where
+    G: Graph,
+    G::Node: GraphObject<Data = ArtifactType>,
+{
+    network
+        .get_node(node_id)
+        .map(|node| damping_factors.for_artifact(node.data()))
+        .unwrap_or(damping_factors.project)
+}
+
 /// Performs a random walk over the input network `G`.
 ///
 /// If a `SeedSet` is provided, this function will produce a collection of
 /// _trusted nodes_ to be used for subsequent walks, otherwise the entire
 /// network is returned.
+///
+/// If some `AliasTables` are given, they must have been built out of the
+/// input network, and are used to pick the next node of every walk.
 pub fn random_walk<L, G, RNG>(
     seed_set: Option<&SeedSet<Id<G::Node>>>,
     network: &G,
     ledger_view: &L,
     rng: &RNG,
+    sampler: Option<&AliasTables<Id<G::Node>>>,
 ) -> Result<WalkResult<G, <G::Node as GraphObject>::Id>, OsrankError>
 where
     L: LedgerView + Send + Sync,
     G: GraphExtras + Clone + Send + Sync,
     Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
     RNG: Rng + SeedableRng + Clone + Send + Sync,
     <G as Graph>::Weight:
         Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform,
 {
     match seed_set {
         Some(seeds) => {
-            let walks = walks(seeds.seedset_iter().par_bridge(), network, ledger_view, rng)?;
+            let walks = walks(
+                seeds.seedset_iter().par_bridge(),
+                network,
+                ledger_view,
+                rng,
+                sampler,
+            )?;
+            let visits = walks.visit_counts();
             let mut trusted_node_ids: Vec<&Id<G::Node>> = Vec::new();
             for node in network.nodes() {
-                if rank_node::<L, G>(&walks, node.id().clone(), ledger_view) > Fraction::from(*ledger_view.get_tau()) {
+                if rank_node::<L, G>(&visits, node, ledger_view).rank
+                    > Fraction::from(*ledger_view.get_tau())
+                {
                     trusted_node_ids.push(&node.id());
                 }
             }
@@ -167,6 +371,7 @@ where
                 network,
                 ledger_view,
                 rng,
+                sampler,
             )?;
             let res = WalkResult {
                 network_view: whole_network,
@@ -178,109 +383,362 @@ where
     }
 }
 
+/// Performs the random walks for the personalized mode, where walks start
+/// only from the nodes of the `SeedSet` which are part of the network.
+///
+/// The seeds share a budget of `R` walks per seed, and each seed gets a share
+/// proportional to its trust score. As every walk starts from a seed, this is
+/// equivalent to teleporting back to the seeds according to their trust.
+pub fn personalized_walks<L, G, RNG>(
+    seed_set: &SeedSet<Id<G::Node>>,
+    network: &G,
+    ledger_view: &L,
+    rng: &RNG,
+    sampler: Option<&AliasTables<Id<G::Node>>>,
+) -> Result<RandomWalks<Id<G::Node>>, OsrankError>
+where
+    L: LedgerView + Send + Sync,
+    G: GraphExtras + Send + Sync,
+    Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
+    RNG: Rng + SeedableRng + Clone + Send + Sync,
+    <G as Graph>::Weight:
+        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform,
+{
+    let seeds: Vec<(&Id<G::Node>, f64)> = seed_set
+        .trust_iter()
+        .filter(|(node_id, trust)| *trust > 0.0 && network.get_node(node_id).is_some())
+        .collect();
+    let total_trust: f64 = seeds.iter().map(|(_, trust)| trust).sum();
+
+    let budget = f64::from(*ledger_view.get_random_walks_num()) * seeds.len() as f64;
+    let starting_nodes: Vec<(&Id<G::Node>, R)> = seeds
+        .into_iter()
+        .map(|(node_id, trust)| (node_id, (budget * trust / total_trust).round() as R))
+        .filter(|(_, walks_num)| *walks_num > 0)
+        .collect();
+
+    if starting_nodes.is_empty() {
+        return Err(OsrankError::InvalidSeedSet(
+            "No seed with a positive trust score belongs to the network.".to_string(),
+        ));
+    }
+
+    walks_from(starting_nodes, network, ledger_view, rng, sampler)
+}
+
 /// Naive version of the `osrank` algorithm
 ///
 /// Given a full network `G` and an optional `SeedSet`, iterates over each
-/// edge of the network and computes the `Osrank`.
+/// edge of the network and computes the `Osrank`. With the `Personalized`
+/// `TeleportMode` the walks start only from the seeds, which are therefore
+/// mandatory, and no pruning phase takes place.
+///
+/// If the `OsrankOptions` ask for the adaptive mode, the walks used to rank
+/// the network are performed in rounds, and the returned `Convergence`
+/// describes how many of them were needed. If they ask for `alias_tables`,
+/// those are built once for every network the walks are performed on.
 pub fn osrank_naive<G, A>(
     seed_set: Option<&SeedSet<Id<G::Node>>>,
+    options: OsrankOptions,
     network: &G,
     annotator: &mut A,
     ledger_view: &(impl LedgerView + Send + Sync),
     rng: &(impl Rng + SeedableRng + Clone + Send + Sync),
-    to_annotation: &dyn Fn(&G::Node, Osrank) -> A::Annotation,
-) -> Result<(), OsrankError>
+    to_annotation: &dyn Fn(&G::Node, RankEstimate) -> A::Annotation,
+) -> Result<Option<Convergence>, OsrankError>
 where
     G: GraphExtras + Clone + Send + Sync + NormalisedGraph,
     A: GraphAnnotator,
     Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
     <G as Graph>::Weight:
-        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform,
+        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform + Into<f64>,
 {
+    let tables = alias_tables_for(network, options);
+
+    if options.teleport == TeleportMode::Personalized {
+        return match seed_set {
+            Some(seeds) => {
+                let (walks, convergence) =
+                    walk_in_rounds(network, ledger_view, rng, options.adaptive, |rng| {
+                        personalized_walks(seeds, network, ledger_view, rng, tables.as_ref())
+                    })?;
+                rank_network(
+                    &walks,
+                    network,
+                    ledger_view,
+                    options.normalise,
+                    annotator,
+                    to_annotation,
+                )?;
+                Ok(convergence)
+            }
+            None => Err(OsrankError::InvalidSeedSet(
+                "The personalized mode requires a seed set.".to_string(),
+            )),
+        };
+    }
+
     match seed_set {
         Some(_) => {
             // Phase1, rank the network and produce a NetworkView.
-            let phase1 = random_walk(seed_set, &*network, ledger_view, rng)?;
+            let phase1 = random_walk(seed_set, &*network, ledger_view, rng, tables.as_ref())?;
+            let view_tables = alias_tables_for(&phase1.network_view, options);
 
             // Phase2, compute the osrank only on the NetworkView
-            let phase2 = random_walk(None, &phase1.network_view, ledger_view, rng)?;
+            let (phase2, convergence) =
+                walk_in_rounds(&*network, ledger_view, rng, options.adaptive, |rng| {
+                    random_walk(
+                        None,
+                        &phase1.network_view,
+                        ledger_view,
+                        rng,
+                        view_tables.as_ref(),
+                    )
+                    .map(|w| w.walks)
+                })?;
 
             rank_network(
-                &phase2.walks,
+                &phase2,
                 &*network,
                 ledger_view,
+                options.normalise,
                 annotator,
                 to_annotation,
-            )
+            )?;
+            Ok(convergence)
         }
         None => {
             // Compute osrank on the full NetworkView
-            let create_walks = random_walk(None, &*network, ledger_view, rng)?;
+            let (create_walks, convergence) =
+                walk_in_rounds(&*network, ledger_view, rng, options.adaptive, |rng| {
+                    random_walk(None, &*network, ledger_view, rng, tables.as_ref()).map(|w| w.walks)
+                })?;
             rank_network(
-                &create_walks.walks,
+                &create_walks,
                 &*network,
                 ledger_view,
+                options.normalise,
                 annotator,
                 to_annotation,
-            )
+            )?;
+            Ok(convergence)
+        }
+    }
+}
+
+/// Builds the `AliasTables` for the input network, if the `OsrankOptions`
+/// ask for them.
+pub(crate) fn alias_tables_for<G>(
+    network: &G,
+    options: OsrankOptions,
+) -> Option<AliasTables<Id<G::Node>>>
+where
+    G: Graph,
+    Id<G::Node>: Clone + Eq + Hash,
+    G::Weight: Into<f64>,
+{
+    if options.alias_tables {
+        Some(AliasTables::new(network))
+    } else {
+        None
+    }
+}
+
+/// The walks performed by `walk_in_rounds`, alongside the `Convergence` of
+/// the adaptive mode, if requested.
+type WalkRounds<I> = (RandomWalks<I>, Option<Convergence>);
+
+/// Performs the walks produced by `walk_round` once or, if `AdaptiveWalks`
+/// are requested, in rounds, until the ranks of the nodes of the input
+/// network converge or the walk budget runs out. The first round uses the
+/// input RNG, while every subsequent one gets its own, derived from it.
+fn walk_in_rounds<L, G, RNG, F>(
+    network: &G,
+    ledger_view: &L,
+    rng: &RNG,
+    adaptive: Option<AdaptiveWalks>,
+    walk_round: F,
+) -> Result<WalkRounds<Id<G::Node>>, OsrankError>
+where
+    L: LedgerView,
+    G: GraphExtras,
+    Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
+    RNG: Rng + SeedableRng + Clone,
+    F: Fn(&RNG) -> Result<RandomWalks<Id<G::Node>>, OsrankError>,
+{
+    let mut walks = walk_round(rng)?;
+
+    let adaptive = match adaptive {
+        None => return Ok((walks, None)),
+        Some(adaptive) => adaptive,
+    };
+
+    let master_seed: u64 = rng.clone().gen();
+    let round_size = walks.len();
+    let mut ranks = ranks_of(&walks, network, ledger_view);
+    let mut convergence = Convergence {
+        rounds: 1,
+        walks: round_size,
+        l1_distance: std::f64::INFINITY,
+        converged: false,
+    };
+
+    while round_size > 0 && convergence.walks + round_size <= adaptive.max_walks {
+        let round_rng: RNG = derive_rng(master_seed, &(convergence.rounds as u64));
+        walks.append(walk_round(&round_rng)?);
+
+        let new_ranks = ranks_of(&walks, network, ledger_view);
+        let top_k_is_stable = adaptive
+            .top_k
+            .map_or(true, |k| top_k_of(&ranks, k) == top_k_of(&new_ranks, k));
+
+        convergence.rounds += 1;
+        convergence.walks = walks.len();
+        convergence.l1_distance = ranks
+            .iter()
+            .zip(new_ranks.iter())
+            .map(|(old, new)| (old - new).abs())
+            .sum();
+        ranks = new_ranks;
+
+        debug!(
+            "Round {}: {} walks, L1 distance {}.",
+            convergence.rounds, convergence.walks, convergence.l1_distance
+        );
+
+        if convergence.l1_distance < adaptive.tolerance && top_k_is_stable {
+            convergence.converged = true;
+            break;
         }
     }
+
+    Ok((walks, Some(convergence)))
+}
+
+/// Returns the (unnormalised) rank of every node of the input network, in
+/// the order they are yielded by `network.nodes()`.
+fn ranks_of<L, G>(walks: &RandomWalks<Id<G::Node>>, network: &G, ledger_view: &L) -> Vec<f64>
+where
+    L: LedgerView,
+    G: GraphExtras,
+    Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
+{
+    let visits = walks.visit_counts();
+    network
+        .nodes()
+        .map(|node| {
+            rank_node::<L, G>(&visits, node, ledger_view)
+                .rank
+                .to_f64()
+                .unwrap_or(0.0)
+        })
+        .collect()
+}
+
+/// Returns the (sorted) indices of the `k` highest ranks.
+fn top_k_of(ranks: &[f64], k: usize) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..ranks.len()).collect();
+    indices.sort_by(|a, b| {
+        ranks[*b]
+            .partial_cmp(&ranks[*a])
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.cmp(b))
+    });
+    indices.truncate(k);
+    indices.sort();
+    indices
 }
 
-/// Assigns an `Osrank` to a `Node`.
+/// Assigns an `Osrank` to a `Node`, using the damping factor matching its
+/// `ArtifactType`. The visits are looked up from the input `VisitCounts`,
+/// which needs to be built only once for all the nodes.
+///
+/// As the rank is proportional to the mean number of visits per walk, its
+/// standard error is the one of the visits, scaled by the same factor.
 fn rank_node<L, G>(
-    random_walks: &RandomWalks<Id<G::Node>>,
-    node_id: Id<G::Node>,
+    visits: &VisitCounts<Id<G::Node>>,
+    node: &G::Node,
     ledger_view: &L,
-) -> Osrank
+) -> RankEstimate
 where
     L: LedgerView,
     G: GraphExtras,
     <G::Node as GraphObject>::Id: Eq + Clone + Hash + Sync + Send,
+    G::Node: GraphObject<Data = ArtifactType>,
 {
-    let total_walks = random_walks.len();
-    let node_visits = random_walks.count_visits(&node_id);
+    let total_walks = visits.total_walks();
+    let node_visits = visits.count_visits(node.id());
 
     // Avoids division by 0
     if total_walks == 0 {
-        Osrank::zero()
+        RankEstimate {
+            visits: Some(0),
+            ..RankEstimate::exact(Osrank::zero())
+        }
     } else {
+        let damping = ledger_view.get_damping_factors().for_artifact(node.data());
+
         // We don't use Fraction::from(f64), because that generates some
         // big numbers for the numer & denom, which eventually cause overflow.
-        // What we do instead, is to exploit the fact we have a probability
-        // distribution between 0.0 and 1.0 and we use a simple formula to
-        // convert a percent into a fraction.
-        let percent_f64 = (1.0 - ledger_view.get_damping_factors().project) * 100.0;
-        let rank = Fraction::new(percent_f64.round() as u64, 100u64)
-            * Osrank::new(node_visits as u32, total_walks as u32);
-
-        if rank > Osrank::one() {
-            Osrank::one()
-        } else {
-            rank
+        // What we do instead, is to convert the damping factor into the
+        // closest fraction with a small denominator, which is exact for
+        // any damping factor with up to six decimal digits.
+        let damping_factor = approximate_fraction(damping, MAX_DAMPING_DENOMINATOR);
+
+        RankEstimate {
+            rank: (Osrank::one() - damping_factor)
+                * Osrank::new(node_visits as u64, total_walks as u64),
+            std_error: (1.0 - damping) * visits.std_error_of_visits(node.id()),
+            visits: Some(node_visits),
         }
     }
 }
 
 /// Assigns an `Osrank` to a network `G`.
+///
+/// If `normalise` is `true`, the ranks are scaled so that they sum up to
+/// exactly 1, i.e. so that they form a probability distribution over the
+/// nodes of the network. The standard errors are scaled accordingly.
 pub fn rank_network<'a, L, G: 'a, A>(
     random_walks: &RandomWalks<Id<G::Node>>,
     network_view: &'a G,
     ledger_view: &L,
+    normalise: bool,
     annotator: &mut A,
-    to_annotation: &dyn Fn(&G::Node, Osrank) -> A::Annotation,
+    to_annotation: &dyn Fn(&G::Node, RankEstimate) -> A::Annotation,
 ) -> Result<(), OsrankError>
 where
     L: LedgerView,
     G: GraphExtras,
     A: GraphAnnotator,
     <G::Node as GraphObject>::Id: Eq + Clone + Hash + Sync + Send,
+    G::Node: GraphObject<Data = ArtifactType>,
 {
-    for node in network_view.nodes() {
-        annotator.annotate_graph(to_annotation(
-            &node,
-            rank_node::<L, G>(&random_walks, node.id().clone(), ledger_view),
-        ))
+    let visits = random_walks.visit_counts();
+    let estimates: Vec<(&G::Node, RankEstimate)> = network_view
+        .nodes()
+        .map(|node| (node, rank_node::<L, G>(&visits, node, ledger_view)))
+        .collect();
+
+    let total = estimates
+        .iter()
+        .fold(Osrank::zero(), |acc, (_, estimate)| acc + estimate.rank);
+
+    for (node, estimate) in estimates {
+        if normalise && !total.is_zero() {
+            let normalised = RankEstimate {
+                rank: estimate.rank / total,
+                std_error: estimate.std_error / total.to_f64().unwrap_or(1.0),
+                visits: estimate.visits,
+            };
+            annotator.annotate_graph(to_annotation(node, normalised))
+        } else {
+            annotator.annotate_graph(to_annotation(node, estimate))
+        }
     }
     Ok(())
 }
@@ -326,14 +784,18 @@ where
 {
     /// The optional `SeetSet` to use.
     pub seed_set: Option<&'a SeedSet<Id<G::Node>>>,
+    /// The `OsrankOptions`, i.e. whether walks should start from every node
+    /// or only from the seeds, and whether the ranks should be normalised.
+    pub options: OsrankOptions,
     /// The `LedgerView` for this context, i.e. a `MockLedger`.
     pub ledger_view: MockLedger,
     /// The `to_annotation` function is a "getter" function that given a
-    /// generic `Node` "knows" how to extract an annotation out of an `Osrank`.
-    /// This function is necessary to bridge the gap between the algorithm being
-    /// written in a totally generic way and the need to convert from a
-    /// fraction-like `Osrank` into an `A::Annotation`.
-    pub to_annotation: &'a (dyn Fn(&G::Node, Osrank) -> A::Annotation),
+    /// generic `Node` "knows" how to extract an annotation out of a
+    /// `RankEstimate`. This function is necessary to bridge the gap between
+    /// the algorithm being written in a totally generic way and the need to
+    /// convert from a fraction-like `Osrank` (and its standard error) into an
+    /// `A::Annotation`.
+    pub to_annotation: &'a (dyn Fn(&G::Node, RankEstimate) -> A::Annotation),
 }
 
 impl<'a> Default
@@ -342,15 +804,41 @@ impl<'a> Default
     fn default() -> Self {
         OsrankNaiveMockContext {
             seed_set: None,
+            options: OsrankOptions::default(),
             ledger_view: MockLedger::default(),
             to_annotation: &mock_network_to_annotation,
         }
     }
 }
 
-fn mock_network_to_annotation(node: &Artifact<String>, rank: Osrank) -> (String, Osrank) {
+impl<'a> Default
+    for OsrankNaiveMockContext<
+        'a,
+        MockEstimateAnnotator<Normalised<MockNetwork>>,
+        Normalised<MockNetwork>,
+    >
+{
+    fn default() -> Self {
+        OsrankNaiveMockContext {
+            seed_set: None,
+            options: OsrankOptions::default(),
+            ledger_view: MockLedger::default(),
+            to_annotation: &mock_network_to_estimate_annotation,
+        }
+    }
+}
+
+fn mock_network_to_annotation(node: &Artifact<String>, estimate: RankEstimate) -> (String, Osrank) {
+    let artifact_id = node.id().clone();
+    (artifact_id, estimate.rank)
+}
+
+fn mock_network_to_estimate_annotation(
+    node: &Artifact<String>,
+    estimate: RankEstimate,
+) -> (String, RankEstimate) {
     let artifact_id = node.id().clone();
-    (artifact_id, rank)
+    (artifact_id, estimate)
 }
 
 /// A *mock* implementation for the `OsrankNaiveAlgorithm`, using the `Mock`
@@ -375,12 +863,13 @@ where
     G: GraphExtras + Clone + Send + Sync + NormalisedGraph,
     L: LedgerView + Send + Sync,
     Id<G::Node>: Clone + Eq + Hash + Send + Sync,
+    G::Node: GraphObject<Data = ArtifactType>,
     <G as Graph>::Weight:
-        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform,
+        Default + Clone + PartialOrd + for<'x> AddAssign<&'x G::Weight> + SampleUniform + Into<f64>,
     OsrankNaiveMockContext<'a, A, G>: Default,
     A: GraphAnnotator,
 {
-    type Output = ();
+    type Output = Option<Convergence>;
     type Context = OsrankNaiveMockContext<'a, A, G>;
     type Error = OsrankError;
     type RngSeed = [u8; 32];
@@ -396,6 +885,7 @@ where
         let rng = <Xoshiro256StarStar as SeedableRng>::from_seed(initial_seed);
         osrank_naive(
             ctx.seed_set,
+            ctx.options,
             graph,
             annotator,
             &ctx.ledger_view,
@@ -450,7 +940,7 @@ mod tests {
 
         assert_eq!(
             algo.execute(&mut ctx, &graph, &mut annotator, initial_seed),
-            Ok(())
+            Ok(None)
         );
 
         let rank_f64 = annotator
@@ -463,9 +953,21 @@ mod tests {
             .to_f64()
             .unwrap();
 
+        // Each walk contributes `1 - d` to the sum of the ranks for every node
+        // it visits, where `d` is the damping factor of the node's type. The
+        // sum is at most 1.0 on average (less when some walks end at a
+        // dangling node), and the contribution of a single walk has a
+        // variance of at most the largest of the per-type damping factors.
+        // Each node gets an independent stream, hence we allow the sum to
+        // exceed 1.0 by 5 standard deviations of the mean over all the walks.
+        let factors = ctx.ledger_view.get_damping_factors();
+        let total_walks =
+            graph.node_count() as f64 * f64::from(*ctx.ledger_view.get_random_walks_num());
+        let tolerance = 5.0 * (factors.project.max(factors.account) / total_walks).sqrt();
+
         // FIXME(adn) This is a fairly weak check, but so far it's the best
         // we can shoot for.
-        TestResult::from_bool(rank_f64 > 0.0 && rank_f64 <= 1.0)
+        TestResult::from_bool(rank_f64 > 0.0 && rank_f64 <= 1.0 + tolerance)
     }
 
     #[test]
@@ -586,6 +1088,392 @@ mod tests {
         assert!(annotator1_ranks.len() > annotator2_ranks.len());
     }
 
+    // Test that walks leaving an account use the account damping factor,
+    // while walks leaving a project use the project one.
+    #[test]
+    fn walks_use_damping_factor_of_current_node() {
+        let mut network = MockNetwork::default();
+
+        network.add_node(
+            "p1".to_string(),
+            ArtifactType::Project {
+                osrank: Zero::zero(),
+            },
+        );
+        network.add_node(
+            "a1".to_string(),
+            ArtifactType::Account {
+                osrank: Zero::zero(),
+            },
+        );
+        network.add_edge(
+            0,
+            &"p1".to_string(),
+            &"a1".to_string(),
+            1.0,
+            DependencyType::Influence(1.0),
+        );
+        network.add_edge(
+            1,
+            &"a1".to_string(),
+            &"p1".to_string(),
+            1.0,
+            DependencyType::Influence(1.0),
+        );
+
+        let mut ledger = MockLedger::default();
+        ledger.set_damping_factors(DampingFactors {
+            project: 1.0,
+            account: 0.0,
+        });
+
+        let mut rng = Xoshiro256StarStar::from_seed([0; 32]);
+
+        // A walk starting from a project always steps into the account and
+        // immediately stops there.
+        let from_project = walk_from(&"p1".to_string(), &network, &ledger, &mut rng, None).unwrap();
+        assert_eq!(from_project.count_visits(&"p1".to_string()), 1);
+        assert_eq!(from_project.count_visits(&"a1".to_string()), 1);
+
+        // A walk starting from an account never leaves it.
+        let from_account = walk_from(&"a1".to_string(), &network, &ledger, &mut rng, None).unwrap();
+        assert_eq!(from_account.count_visits(&"a1".to_string()), 1);
+        assert_eq!(from_account.count_visits(&"p1".to_string()), 0);
+    }
+
+    // Test that a walk trapped into a self-loop with a damping factor of 1.0
+    // is cut off, rather than running forever.
+    #[test]
+    fn osrank_stops_runaway_walks() {
+        let mut network = Normalised::new(Network::default());
+
+        network.add_node(
+            "p1".to_string(),
+            ArtifactType::Project {
+                osrank: Zero::zero(),
+            },
+        );
+        network.add_edge(
+            0,
+            &"p1".to_string(),
+            &"p1".to_string(),
+            1.0,
+            DependencyType::Influence(1.0),
+        );
+
+        let algo: Mock<
+            OsrankNaiveAlgorithm<
+                Normalised<MockNetwork>,
+                MockLedger,
+                MockAnnotator<Normalised<MockNetwork>>,
+            >,
+        > = Mock {
+            unmock: OsrankNaiveAlgorithm::default(),
+        };
+        let mut ctx = OsrankNaiveMockContext::default();
+        ctx.ledger_view.set_damping_factors(DampingFactors {
+            project: 1.0,
+            account: 1.0,
+        });
+        ctx.ledger_view.set_max_walk_length(50);
+        let mut annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+
+        assert_eq!(
+            algo.execute(&mut ctx, &network, &mut annotator, [0; 32]),
+            Err(OsrankError::WalkLengthExceeded(50))
+        );
+    }
+
+    // Test that in personalized mode all the rank flows from the trusted
+    // seeds, so that nodes unreachable from them get no rank at all.
+    #[test]
+    fn osrank_personalized_walks_only_from_seeds() {
+        let mut network = Normalised::new(Network::default());
+
+        for node in &["p1", "p2"] {
+            network.add_node(
+                node.to_string(),
+                ArtifactType::Project {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+
+        for node in &["a1", "a2"] {
+            network.add_node(
+                node.to_string(),
+                ArtifactType::Account {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+
+        let edges = [("p1", "a1"), ("a1", "p1"), ("p2", "a2"), ("a2", "p2")];
+
+        for (ix, edge) in edges.iter().enumerate() {
+            network.add_edge(
+                ix,
+                &edge.0.to_string(),
+                &edge.1.to_string(),
+                1.0,
+                DependencyType::Influence(1.0),
+            )
+        }
+
+        let algo: Mock<
+            OsrankNaiveAlgorithm<
+                Normalised<MockNetwork>,
+                MockLedger,
+                MockAnnotator<Normalised<MockNetwork>>,
+            >,
+        > = Mock {
+            unmock: OsrankNaiveAlgorithm::default(),
+        };
+
+        // The personalized mode can't work without seeds.
+        let mut ctx = OsrankNaiveMockContext {
+            options: OsrankOptions {
+                teleport: TeleportMode::Personalized,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+        assert!(
+            match algo.execute(&mut ctx, &network, &mut annotator, [0; 32]) {
+                Err(OsrankError::InvalidSeedSet(_)) => true,
+                _ => false,
+            }
+        );
+
+        let seed_set =
+            SeedSet::from_trusted(vec![("p1".to_string(), 1.0), ("p2".to_string(), 0.0)]);
+        ctx.seed_set = Some(&seed_set);
+        assert_eq!(
+            algo.execute(&mut ctx, &network, &mut annotator, [0; 32]),
+            Ok(None)
+        );
+
+        let rank_of = |id: &str| annotator.annotator[&id.to_string()];
+        assert!(rank_of("p1") > Osrank::zero());
+        assert!(rank_of("a1") > Osrank::zero());
+        assert_eq!(rank_of("p2"), Osrank::zero());
+        assert_eq!(rank_of("a2"), Osrank::zero());
+    }
+
+    // A small network without dangling nodes, whose exact PageRank is known.
+    fn three_nodes_network() -> Normalised<MockNetwork> {
+        let mut network = Normalised::new(Network::default());
+
+        for node in &["n0", "n1", "n2"] {
+            network.add_node(
+                node.to_string(),
+                ArtifactType::Project {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+
+        let edges = [
+            ("n0", "n0", 0.5),
+            ("n0", "n1", 0.5),
+            ("n1", "n0", 0.5),
+            ("n1", "n2", 0.5),
+            ("n2", "n2", 1.0),
+        ];
+
+        for (ix, edge) in edges.iter().enumerate() {
+            network.add_edge(
+                ix,
+                &edge.0.to_string(),
+                &edge.1.to_string(),
+                edge.2,
+                DependencyType::Influence(edge.2),
+            )
+        }
+
+        network
+    }
+
+    // Test that the normalised Monte Carlo ranks form an exact probability
+    // distribution, and that they converge to the exact PageRank.
+    #[test]
+    fn osrank_normalised_agrees_with_exact_pagerank() {
+        assert_normalised_agrees_with_exact_pagerank(false)
+    }
+
+    // Like `osrank_normalised_agrees_with_exact_pagerank`, but sampling the
+    // walks out of the alias tables.
+    #[test]
+    fn osrank_with_alias_tables_agrees_with_exact_pagerank() {
+        assert_normalised_agrees_with_exact_pagerank(true)
+    }
+
+    fn assert_normalised_agrees_with_exact_pagerank(alias_tables: bool) {
+        let network = three_nodes_network();
+
+        let algo: Mock<
+            OsrankNaiveAlgorithm<
+                Normalised<MockNetwork>,
+                MockLedger,
+                MockAnnotator<Normalised<MockNetwork>>,
+            >,
+        > = Mock {
+            unmock: OsrankNaiveAlgorithm::default(),
+        };
+        let mut ctx = OsrankNaiveMockContext {
+            options: OsrankOptions {
+                normalise: true,
+                alias_tables,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        ctx.ledger_view.set_random_walks_num(2_000);
+
+        let mut annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+        assert_eq!(
+            algo.execute(&mut ctx, &network, &mut annotator, [0; 32]),
+            Ok(None)
+        );
+
+        let total = annotator
+            .annotator
+            .values()
+            .fold(Osrank::zero(), |acc, rank| acc + *rank);
+        assert_eq!(total, Osrank::one());
+
+        let mut exact: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+        assert_eq!(
+            crate::algorithm::pagerank::pagerank_exact(
+                &network,
+                &mut exact,
+                &ctx.ledger_view,
+                1e-10,
+                1_000,
+                &|node, estimate| (node.id().clone(), estimate.rank),
+            ),
+            Ok(())
+        );
+
+        for (id, rank) in &annotator.annotator {
+            let expected = exact.annotator[id].to_f64().unwrap();
+            let actual = rank.to_f64().unwrap();
+            assert!(
+                (actual - expected).abs() < 0.03,
+                "{}: {} (expected {})",
+                id,
+                actual,
+                expected
+            );
+        }
+    }
+
+    // Test that the standard errors shrink as the number of walks grows, and
+    // that the exact PageRank falls within the confidence intervals.
+    #[test]
+    fn osrank_std_errors_shrink_with_more_walks() {
+        let network = three_nodes_network();
+
+        let algo: Mock<
+            OsrankNaiveAlgorithm<
+                Normalised<MockNetwork>,
+                MockLedger,
+                MockEstimateAnnotator<Normalised<MockNetwork>>,
+            >,
+        > = Mock {
+            unmock: OsrankNaiveAlgorithm::default(),
+        };
+
+        let run = |walks_num: R| {
+            let mut ctx = OsrankNaiveMockContext::default();
+            ctx.ledger_view.set_random_walks_num(walks_num);
+            let mut annotator: MockEstimateAnnotator<Normalised<MockNetwork>> =
+                Default::default();
+            assert_eq!(
+                algo.execute(&mut ctx, &network, &mut annotator, [0; 32]),
+                Ok(None)
+            );
+            annotator.annotator
+        };
+
+        let few = run(100);
+        let many = run(1_600);
+
+        let exact = [
+            ("n0", 0.18066561014263083),
+            ("n1", 0.1267828843106181),
+            ("n2", 0.6925515055467515),
+        ];
+
+        for (id, expected) in exact.iter() {
+            let (few, many) = (few[*id], many[*id]);
+            assert!(few.std_error > 0.0);
+            assert!(many.std_error < few.std_error / 2.0, "{}", id);
+
+            let (lower, upper) = many.interval(4.0);
+            assert!(
+                lower <= *expected && *expected <= upper,
+                "{}: {} not in [{}, {}]",
+                id,
+                expected,
+                lower,
+                upper
+            );
+        }
+    }
+
+    // Test that the adaptive mode stops as soon as the ranks converge, and
+    // that it never exceeds the walk budget otherwise.
+    #[test]
+    fn osrank_adaptive_walks_stop_on_convergence_or_budget() {
+        let network = three_nodes_network();
+
+        let algo: Mock<
+            OsrankNaiveAlgorithm<
+                Normalised<MockNetwork>,
+                MockLedger,
+                MockAnnotator<Normalised<MockNetwork>>,
+            >,
+        > = Mock {
+            unmock: OsrankNaiveAlgorithm::default(),
+        };
+
+        let run = |adaptive: AdaptiveWalks| {
+            let mut ctx = OsrankNaiveMockContext {
+                options: OsrankOptions {
+                    adaptive: Some(adaptive),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            ctx.ledger_view.set_random_walks_num(100);
+            let mut annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+            algo.execute(&mut ctx, &network, &mut annotator, [0; 32])
+                .unwrap()
+                .unwrap()
+        };
+
+        let converged = run(AdaptiveWalks {
+            tolerance: 0.05,
+            top_k: Some(1),
+            max_walks: 1_000_000,
+        });
+        assert!(converged.converged);
+        assert!(converged.rounds >= 2);
+        assert!(converged.l1_distance < 0.05);
+        assert_eq!(converged.walks, converged.rounds * 300);
+
+        let exhausted = run(AdaptiveWalks {
+            tolerance: 0.0,
+            top_k: None,
+            max_walks: 1_000,
+        });
+        assert!(!exhausted.converged);
+        assert_eq!(exhausted.rounds, 3);
+        assert_eq!(exhausted.walks, 900);
+    }
+
     // Test that given the same initial seed, two osrank algorithms yields
     // exactly the same result.
     fn prop_osrank_is_deterministic(
@@ -642,6 +1530,61 @@ mod tests {
         );
     }
 
+    // Test that the ranks don't depend on the number of threads used to
+    // perform the random walks.
+    fn prop_osrank_is_independent_of_thread_count(
+        graph: Normalised<MockNetwork>,
+        threads: u8,
+    ) -> TestResult {
+        if graph.normalised_graph.is_empty() {
+            return TestResult::discard();
+        }
+
+        // Keeps the thread pools reasonably small, from 2 to 8 threads.
+        let threads = (threads % 7) as usize + 2;
+
+        let algo: Mock<
+            OsrankNaiveAlgorithm<
+                Normalised<MockNetwork>,
+                MockLedger,
+                MockAnnotator<Normalised<MockNetwork>>,
+            >,
+        > = Mock {
+            unmock: OsrankNaiveAlgorithm::default(),
+        };
+
+        let run_with_threads = |num_threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .unwrap();
+            let mut annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+
+            let result = pool.install(|| {
+                let mut ctx = OsrankNaiveMockContext::default();
+                algo.execute(&mut ctx, &graph, &mut annotator, [0; 32])
+            });
+            assert_eq!(result, Ok(None));
+
+            let mut ranks = annotator
+                .annotator
+                .into_iter()
+                .collect::<Vec<(String, Osrank)>>();
+            ranks.sort_by(|a, b| a.0.cmp(&b.0)); // Compare by Ids.
+            ranks
+        };
+
+        TestResult::from_bool(run_with_threads(1) == run_with_threads(threads))
+    }
+
+    #[test]
+    fn osrank_is_independent_of_thread_count() {
+        quickcheck(
+            prop_osrank_is_independent_of_thread_count
+                as fn(Normalised<MockNetwork>, u8) -> TestResult,
+        );
+    }
+
     #[test]
     fn everything_ok() {
         // build the example network
@@ -712,7 +1655,7 @@ mod tests {
 
         assert_eq!(
             algo.execute(&mut ctx, &network, &mut annotator, initial_seed),
-            Ok(())
+            Ok(None)
         );
 
         // We need to sort the ranks because the order of the elements returned
@@ -733,13 +1676,13 @@ mod tests {
         assert_eq!(
             expected,
             vec![
-                "id: a1 osrank: 0.0725",
-                "id: a2 osrank: 0.24",
-                "id: a3 osrank: 0.05",
+                "id: a1 osrank: 0.055",
+                "id: a2 osrank: 0.2475",
+                "id: a3 osrank: 0.09",
                 "id: isle osrank: 0",
-                "id: p1 osrank: 0.12",
-                "id: p2 osrank: 0.205",
-                "id: p3 osrank: 0.1675",
+                "id: p1 osrank: 0.0975",
+                "id: p2 osrank: 0.21",
+                "id: p3 osrank: 0.215",
             ]
         );
     }
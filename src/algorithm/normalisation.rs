@@ -0,0 +1,410 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
+/// Normalisation of a network with typed edges (i.e. contributions,
+/// maintenance and dependencies) into a `Normalised` one, which can be fed
+/// to the algorithms.
+extern crate oscoin_graph_api;
+
+use crate::types::network::{Artifact, ArtifactType, Dependency, DependencyType};
+use crate::types::HyperParams;
+use oscoin_graph_api::{Direction, Graph, GraphObject, GraphWriter};
+
+use std::collections::HashMap;
+
+use super::Normalised;
+
+/// Errors that the normalisation might throw.
+#[derive(Debug, Display, PartialEq, Eq)]
+pub enum NormalisationError {
+    /// The input network contains an `Influence` edge, which means it has
+    /// already been (at least partially) normalised. Carries the edge id.
+    AlreadyNormalised(usize),
+    /// An edge has a negative or non-finite weight. Carries the edge id.
+    InvalidWeight(usize),
+}
+
+/// Normalises the input network `G`, whose edges are `Contrib`,
+/// `ContribPrime`, `Maintain`, `MaintainPrime` or `Depend` ones, into a
+/// `Normalised` network whose edges are all `Influence` ones.
+///
+/// For every node, the weights of the outgoing edges of each type are first
+/// scaled so that they sum up to the factor the `HyperParams` assign to that
+/// type. The only exception are the `MaintainPrime` edges, whose weight gets
+/// multiplied by the factor and by the share of the `ContribPrime` weight of
+/// the node going to the same target, so that maintaining a project one
+/// never contributed to counts for nothing. Parallel edges are then
+/// collapsed into a single one, and the weights get scaled again so that the
+/// outgoing weights of every node sum up to 1. Nodes without outgoing edges
+/// stay without.
+///
+/// This mirrors what `adjacency::new_network_matrix` does on the adjacency
+/// matrices, but works on any network, without going through CSV files.
+pub fn normalise_network<G, W, N>(
+    network: &G,
+    hyperparams: &HyperParams,
+) -> Result<Normalised<N>, NormalisationError>
+where
+    W: Clone + Into<f64>,
+    G: Graph<
+        Node = Artifact<String>,
+        Edge = Dependency<usize, W>,
+        Weight = W,
+        NodeData = ArtifactType,
+        EdgeData = DependencyType<W>,
+    >,
+    N: Graph<
+            Node = Artifact<String>,
+            Edge = Dependency<usize, f64>,
+            Weight = f64,
+            NodeData = ArtifactType,
+            EdgeData = DependencyType<f64>,
+        > + GraphWriter,
+{
+    let mut normalised = N::default();
+
+    for node in network.nodes() {
+        normalised.add_node(node.id().clone(), node.data().clone());
+    }
+
+    let mut current_edge_id = 0;
+
+    for node in network.nodes() {
+        // The typed (and weighted) outgoing edges, alongside the total weight
+        // for each of the types.
+        let mut edges: Vec<(&String, &DependencyType<W>, f64)> = Vec::new();
+        let mut totals: HashMap<std::mem::Discriminant<DependencyType<W>>, f64> = HashMap::new();
+
+        // The `ContribPrime` weight going to each target, which scales the
+        // `MaintainPrime` edges to the same target.
+        let mut contributions: HashMap<&String, f64> = HashMap::new();
+        let mut contributions_total = 0.0;
+
+        for edge_ref in network.edges_directed(node.id(), Direction::Outgoing) {
+            let edge = match network.get_edge(edge_ref.id) {
+                None => continue,
+                Some(e) => e,
+            };
+
+            if let DependencyType::Influence(_) = edge.data() {
+                return Err(NormalisationError::AlreadyNormalised(*edge.id()));
+            }
+            let weight: f64 = edge.data().get_weight().clone().into();
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(NormalisationError::InvalidWeight(*edge.id()));
+            }
+
+            *totals
+                .entry(std::mem::discriminant(edge.data()))
+                .or_insert(0.0) += weight;
+            if let DependencyType::ContribPrime(_) = edge.data() {
+                *contributions.entry(edge_ref.to).or_insert(0.0) += weight;
+                contributions_total += weight;
+            }
+            edges.push((edge_ref.to, edge.data(), weight));
+        }
+
+        // Scales the weights by type, collapsing the parallel edges, while
+        // preserving the order in which the targets were first encountered.
+        let mut targets: Vec<(&String, f64)> = Vec::new();
+        let mut target_ix: HashMap<&String, usize> = HashMap::new();
+
+        for (target, dependency_type, weight) in edges {
+            let factor: f64 = match hyperparams.factor_for(dependency_type) {
+                None => continue,
+                Some(f) => f.into(),
+            };
+
+            let (share, total) = match dependency_type {
+                DependencyType::MaintainPrime(_) => (
+                    weight * contributions.get(target).cloned().unwrap_or(0.0),
+                    contributions_total,
+                ),
+                _ => (weight, totals[&std::mem::discriminant(dependency_type)]),
+            };
+
+            if total <= 0.0 {
+                continue;
+            }
+
+            let ix = *target_ix.entry(target).or_insert_with(|| {
+                targets.push((target, 0.0));
+                targets.len() - 1
+            });
+            targets[ix].1 += factor * share / total;
+        }
+
+        let node_total: f64 = targets.iter().map(|(_, w)| w).sum();
+        if node_total <= 0.0 {
+            continue;
+        }
+
+        for (target, weight) in targets {
+            let w = weight / node_total;
+            normalised.add_edge(
+                current_edge_id,
+                node.id(),
+                target,
+                w,
+                DependencyType::Influence(w),
+            );
+            current_edge_id += 1;
+        }
+    }
+
+    Ok(Normalised::new(normalised))
+}
+
+#[cfg(test)]
+mod tests {
+
+    extern crate oscoin_graph_api;
+
+    use super::*;
+    use crate::importers::csv::import_network;
+    use crate::protocol_traits::graph::GraphExtras;
+    use crate::protocol_traits::ledger::MockLedger;
+    use crate::types::mock::MockNetwork;
+    use crate::types::network::Network;
+    use num_traits::Zero;
+    use oscoin_graph_api::{Edge, GraphWriter};
+
+    fn outgoing_weights(network: &Normalised<MockNetwork>, id: &str) -> Vec<(String, f64)> {
+        let mut weights = network
+            .edges_directed(&id.to_string(), Direction::Outgoing)
+            .into_iter()
+            .map(|e| {
+                let edge = network.get_edge(e.id).unwrap();
+                assert_eq!(edge.data(), &DependencyType::Influence(edge.weight()));
+                (e.to.clone(), edge.weight())
+            })
+            .collect::<Vec<_>>();
+        weights.sort_by(|a, b| a.0.cmp(&b.0));
+        weights
+    }
+
+    #[test]
+    fn normalise_network_collapses_and_weights_edges() {
+        let mut network: MockNetwork = Network::default();
+
+        for node in &["p1", "p2"] {
+            network.add_node(
+                node.to_string(),
+                ArtifactType::Project {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+        network.add_node(
+            "a1".to_string(),
+            ArtifactType::Account {
+                osrank: Zero::zero(),
+            },
+        );
+
+        let edges = [
+            ("p1", "p2", DependencyType::Depend(1.0)),
+            ("p1", "a1", DependencyType::Contrib(3.0)),
+            ("p1", "a1", DependencyType::Maintain(1.0)),
+            ("a1", "p1", DependencyType::ContribPrime(3.0)),
+            ("a1", "p2", DependencyType::ContribPrime(1.0)),
+        ];
+
+        for (ix, (from, to, dependency_type)) in edges.iter().enumerate() {
+            network.add_edge(
+                ix,
+                &from.to_string(),
+                &to.to_string(),
+                *dependency_type.get_weight(),
+                dependency_type.clone(),
+            )
+        }
+
+        let normalised: Normalised<MockNetwork> =
+            normalise_network(&network, &HyperParams::default()).unwrap();
+
+        assert_eq!(normalised.nodes().count(), 3);
+
+        // depend: 4/7, contrib + maintain: 1/7 + 2/7, which already sum to 1.
+        let p1 = outgoing_weights(&normalised, "p1");
+        assert_eq!(p1.len(), 2);
+        assert_eq!(p1[0].0, "a1");
+        assert!((p1[0].1 - 3.0 / 7.0).abs() < 1e-12);
+        assert_eq!(p1[1].0, "p2");
+        assert!((p1[1].1 - 4.0 / 7.0).abs() < 1e-12);
+
+        // contrib*: 2/5 split 3:1, then scaled to sum up to 1.
+        let a1 = outgoing_weights(&normalised, "a1");
+        assert_eq!(a1.len(), 2);
+        assert!((a1[0].1 - 0.75).abs() < 1e-12);
+        assert!((a1[1].1 - 0.25).abs() < 1e-12);
+
+        assert!(outgoing_weights(&normalised, "p2").is_empty());
+    }
+
+    // Test that normalising a network gives the same weights as importing it
+    // out of the equivalent CSV files, maintainers included.
+    #[test]
+    fn normalise_network_agrees_with_csv_import() {
+        let projects = [("0", "foo"), ("1", "bar"), ("2", "baz")];
+        let deps = [("0", "1"), ("2", "0"), ("2", "1")];
+        let contribs = [
+            ("0", "github@john", 100),
+            ("1", "github@tom", 30),
+            ("2", "github@tom", 60),
+            ("2", "github@alice", 20),
+        ];
+        let maintainers = [
+            ("0", "github@john"),
+            ("2", "github@alice"),
+            ("2", "github@tom"),
+            ("1", "github@bob"),
+        ];
+
+        let name_of = |id: &str| projects.iter().find(|p| p.0 == id).unwrap().1.to_string();
+        let mut network: MockNetwork = Network::default();
+        let mut edges: Vec<(String, String, DependencyType<f64>)> = Vec::new();
+
+        for (_, name) in &projects {
+            network.add_node(
+                name.to_string(),
+                ArtifactType::Project {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+        for account in &["github@john", "github@tom", "github@alice", "github@bob"] {
+            network.add_node(
+                account.to_string(),
+                ArtifactType::Account {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+        for (from, to) in &deps {
+            edges.push((name_of(from), name_of(to), DependencyType::Depend(1.0)));
+        }
+        for (project, account, contributions) in &contribs {
+            let contributions = f64::from(*contributions);
+            edges.push((
+                name_of(project),
+                account.to_string(),
+                DependencyType::Contrib(contributions),
+            ));
+            edges.push((
+                account.to_string(),
+                name_of(project),
+                DependencyType::ContribPrime(contributions),
+            ));
+        }
+        for (project, account) in &maintainers {
+            edges.push((
+                name_of(project),
+                account.to_string(),
+                DependencyType::Maintain(1.0),
+            ));
+            edges.push((
+                account.to_string(),
+                name_of(project),
+                DependencyType::MaintainPrime(1.0),
+            ));
+        }
+        for (ix, (from, to, dependency_type)) in edges.into_iter().enumerate() {
+            network.add_edge(
+                ix,
+                &from,
+                &to,
+                *dependency_type.get_weight(),
+                dependency_type,
+            )
+        }
+
+        let normalised: Normalised<MockNetwork> =
+            normalise_network(&network, &HyperParams::default()).unwrap();
+
+        let csv_of = |header: &str, rows: Vec<String>| {
+            let mut contents = format!("{}\n", header);
+            for row in rows {
+                contents.push_str(&row);
+                contents.push('\n');
+            }
+            contents
+        };
+        let deps_csv = csv_of(
+            "FROM_ID,TO_ID",
+            deps.iter().map(|(f, t)| format!("{},{}", f, t)).collect(),
+        );
+        let deps_meta_csv = csv_of(
+            "ID,NAME,PLATFORM",
+            projects
+                .iter()
+                .map(|(id, name)| format!("{},{},Cargo", id, name))
+                .collect(),
+        );
+        let contribs_csv = csv_of(
+            "ID,MAINTAINER,REPO,CONTRIBUTIONS,NAME",
+            contribs
+                .iter()
+                .map(|(id, a, c)| format!("{},{},x,{},{}", id, a, c, name_of(id)))
+                .collect(),
+        );
+        let maintainers_csv = csv_of(
+            "ID,MAINTAINER,NAME",
+            maintainers
+                .iter()
+                .map(|(id, a)| format!("{},{},{}", id, a, name_of(id)))
+                .collect(),
+        );
+        let imported: Normalised<MockNetwork> = import_network(
+            csv::Reader::from_reader(deps_csv.as_bytes()),
+            csv::Reader::from_reader(deps_meta_csv.as_bytes()),
+            csv::Reader::from_reader(contribs_csv.as_bytes()),
+            Some(csv::Reader::from_reader(maintainers_csv.as_bytes())),
+            &MockLedger::default(),
+        )
+        .unwrap();
+
+        assert_eq!(imported.node_count(), normalised.node_count());
+        for node in imported.nodes() {
+            let expected = outgoing_weights(&imported, node.id());
+            let actual = outgoing_weights(&normalised, node.id());
+            assert_eq!(
+                actual.iter().map(|(t, _)| t).collect::<Vec<_>>(),
+                expected.iter().map(|(t, _)| t).collect::<Vec<_>>(),
+                "{}",
+                node.id()
+            );
+            for ((target, w1), (_, w2)) in actual.iter().zip(expected.iter()) {
+                assert!((w1 - w2).abs() < 1e-9, "{} -> {}", node.id(), target);
+            }
+        }
+    }
+
+    #[test]
+    fn normalise_network_rejects_influence_edges() {
+        let mut network: MockNetwork = Network::default();
+
+        for node in &["p1", "p2"] {
+            network.add_node(
+                node.to_string(),
+                ArtifactType::Project {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+        network.add_edge(
+            7,
+            &"p1".to_string(),
+            &"p2".to_string(),
+            1.0,
+            DependencyType::Influence(1.0),
+        );
+
+        let result: Result<Normalised<MockNetwork>, _> =
+            normalise_network(&network, &HyperParams::default());
+        assert_eq!(
+            result.map(|_| ()),
+            Err(NormalisationError::AlreadyNormalised(7))
+        );
+    }
+}
@@ -0,0 +1,424 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
+/// Implementation of an exact (i.e. deterministic) PageRank, computed via
+/// sparse power iteration. It's not meant to replace the Monte Carlo
+/// algorithms, but to provide a ground truth to compare them against.
+extern crate oscoin_graph_api;
+extern crate sprs;
+
+use crate::linalg::{normalise_rows_mut, pagerank_sparse, SparseMatrix};
+use crate::protocol_traits::graph::GraphExtras;
+use crate::protocol_traits::ledger::{LedgerView, MockLedger};
+use crate::types::mock::{Mock, MockAnnotator, MockNetwork};
+use crate::types::network::{Artifact, ArtifactType};
+use crate::types::{Osrank, RankEstimate};
+use oscoin_graph_api::{Direction, Edge, Graph, GraphAlgorithm, GraphAnnotator, GraphObject, Id};
+use sprs::TriMat;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use super::{Normalised, NormalisedGraph, OsrankError};
+
+/// The exact ranks of a network with `N` nodes are expressed as fractions of
+/// `N * EXACT_RANK_SCALE`.
+pub const EXACT_RANK_SCALE: u64 = 1_000_000_000;
+
+/// Builds the (row-normalised) transition matrix for the input network `G`,
+/// where the node `i` is the `i`-th node yielded by `network.nodes()`.
+pub fn transition_matrix<G>(network: &G) -> SparseMatrix<f64>
+where
+    G: GraphExtras,
+    Id<G::Node>: Eq + Hash,
+    G::Weight: Into<f64>,
+{
+    let node_indices: HashMap<&Id<G::Node>, usize> = network
+        .nodes()
+        .enumerate()
+        .map(|(ix, node)| (node.id(), ix))
+        .collect();
+
+    let nodes = network.nodes().count();
+    let mut matrix = TriMat::new((nodes, nodes));
+
+    for (from, node) in network.nodes().enumerate() {
+        for edge in network.edges_directed(node.id(), Direction::Outgoing) {
+            if let (Some(to), Some(e)) = (node_indices.get(edge.to), network.get_edge(edge.id)) {
+                matrix.add_triplet(from, *to, e.weight().into());
+            }
+        }
+    }
+
+    let mut transition = matrix.to_csr();
+    normalise_rows_mut(&mut transition);
+    transition
+}
+
+/// Computes the exact PageRank of the input network `G`, using the damping
+/// factors from the `LedgerView` matching the `ArtifactType` of each node,
+/// and annotates the network with the result.
+///
+/// Returns an `OsrankError::PageRankDidNotConverge` if the residual is still
+/// above `tolerance` after `max_iterations`.
+pub fn pagerank_exact<G, A>(
+    network: &G,
+    annotator: &mut A,
+    ledger_view: &impl LedgerView,
+    tolerance: f64,
+    max_iterations: usize,
+    to_annotation: &dyn Fn(&G::Node, RankEstimate) -> A::Annotation,
+) -> Result<(), OsrankError>
+where
+    G: GraphExtras + NormalisedGraph,
+    A: GraphAnnotator,
+    Id<G::Node>: Eq + Hash,
+    G::Node: GraphObject<Data = ArtifactType>,
+    G::Weight: Into<f64>,
+{
+    let transition = transition_matrix(network);
+    let damping_factors: Vec<f64> = network
+        .nodes()
+        .map(|node| ledger_view.get_damping_factors().for_artifact(node.data()))
+        .collect();
+
+    let pagerank = pagerank_sparse(&transition, &damping_factors, tolerance, max_iterations);
+
+    debug!(
+        "PageRank stopped after {} iterations, with a residual of {}.",
+        pagerank.iterations, pagerank.residual
+    );
+
+    if !pagerank.converged(tolerance) {
+        return Err(OsrankError::PageRankDidNotConverge(pagerank.iterations));
+    }
+
+    // We don't use Fraction::from(f64), whose big numer & denom would
+    // eventually cause overflows in the arithmetic on the ranks. All the
+    // ranks are rounded to a multiple of the same `1 / denominator` instead,
+    // which keeps them summable, and keeps about nine significant digits
+    // even for ranks as small as `1 / N`, where `N` is the number of nodes.
+    let denominator = (pagerank.ranks.len() as u64)
+        .saturating_mul(EXACT_RANK_SCALE)
+        .max(1);
+    for (node, rank) in network.nodes().zip(pagerank.ranks.iter()) {
+        let numerator = (rank.max(0.0) * denominator as f64).round() as u64;
+        annotator.annotate_graph(to_annotation(
+            node,
+            RankEstimate::exact(Osrank::new(numerator, denominator)),
+        ))
+    }
+
+    Ok(())
+}
+
+/// This is a marker type used to implement a valid instance for
+/// `GraphAlgorithm`. Refer to the documentation of `OsrankNaiveAlgorithm`
+/// for the rationale behind it.
+pub struct PageRankAlgorithm<'a, G: 'a, L, A: 'a> {
+    graph: PhantomData<G>,
+    ledger: PhantomData<L>,
+    ty: PhantomData<&'a ()>,
+    annotator: PhantomData<A>,
+}
+
+impl<'a, G, L, A> Default for PageRankAlgorithm<'a, G, L, A> {
+    fn default() -> Self {
+        PageRankAlgorithm {
+            graph: PhantomData,
+            ledger: PhantomData,
+            ty: PhantomData,
+            annotator: PhantomData,
+        }
+    }
+}
+
+/// The `Context` that the exact PageRank _mock_ algorithm will need.
+pub struct PageRankMockContext<'a, A, G = Normalised<MockNetwork>>
+where
+    G: Graph,
+    A: GraphAnnotator,
+{
+    /// The `LedgerView` for this context, i.e. a `MockLedger`.
+    pub ledger_view: MockLedger,
+    /// The power iteration stops once the L1 norm of the difference between
+    /// two iterates falls below this threshold.
+    pub tolerance: f64,
+    /// The maximum number of iterations to perform.
+    pub max_iterations: usize,
+    /// The `to_annotation` function is a "getter" function that given a
+    /// generic `Node` "knows" how to extract an annotation out of a
+    /// `RankEstimate`.
+    pub to_annotation: &'a (dyn Fn(&G::Node, RankEstimate) -> A::Annotation),
+}
+
+impl<'a> Default
+    for PageRankMockContext<'a, MockAnnotator<Normalised<MockNetwork>>, Normalised<MockNetwork>>
+{
+    fn default() -> Self {
+        PageRankMockContext {
+            ledger_view: MockLedger::default(),
+            tolerance: 1e-10,
+            max_iterations: 1_000,
+            to_annotation: &mock_network_to_annotation,
+        }
+    }
+}
+
+fn mock_network_to_annotation(node: &Artifact<String>, estimate: RankEstimate) -> (String, Osrank) {
+    let artifact_id = node.id().clone();
+    (artifact_id, estimate.rank)
+}
+
+/// A *mock* implementation for the `PageRankAlgorithm`, using the `Mock`
+/// newtype wrapper. As PageRank is deterministic, the `RngSeed` is `()`.
+impl<'a, G, L, A> GraphAlgorithm<G, A> for Mock<PageRankAlgorithm<'a, G, L, A>>
+where
+    G: GraphExtras + NormalisedGraph,
+    L: LedgerView,
+    Id<G::Node>: Eq + Hash,
+    G::Node: GraphObject<Data = ArtifactType>,
+    G::Weight: Into<f64>,
+    PageRankMockContext<'a, A, G>: Default,
+    A: GraphAnnotator,
+{
+    type Output = ();
+    type Context = PageRankMockContext<'a, A, G>;
+    type Error = OsrankError;
+    type RngSeed = ();
+    type Annotation = <A as GraphAnnotator>::Annotation;
+
+    fn execute(
+        &self,
+        ctx: &mut Self::Context,
+        graph: &G,
+        annotator: &mut A,
+        _initial_seed: Self::RngSeed,
+    ) -> Result<Self::Output, Self::Error> {
+        pagerank_exact(
+            graph,
+            annotator,
+            &ctx.ledger_view,
+            ctx.tolerance,
+            ctx.max_iterations,
+            &ctx.to_annotation,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    extern crate oscoin_graph_api;
+    extern crate quickcheck;
+
+    use super::*;
+    use crate::algorithm::Normalised;
+    use crate::protocol_traits::ledger::MockLedger;
+    use crate::types::mock::{Mock, MockAnnotator, MockNetwork};
+    use crate::types::network::{ArtifactType, DependencyType, Network};
+    use crate::types::DampingFactors;
+    use fraction::ToPrimitive;
+    use num_traits::Zero;
+    use oscoin_graph_api::{GraphAlgorithm, GraphWriter};
+    use quickcheck::{quickcheck, TestResult};
+
+    type MockPageRankAlgorithm<'a> = Mock<
+        PageRankAlgorithm<
+            'a,
+            Normalised<MockNetwork>,
+            MockLedger,
+            MockAnnotator<Normalised<MockNetwork>>,
+        >,
+    >;
+
+    fn run_pagerank(graph: &Normalised<MockNetwork>) -> Result<Vec<(String, f64)>, OsrankError> {
+        let algo: MockPageRankAlgorithm = Mock {
+            unmock: PageRankAlgorithm::default(),
+        };
+        let mut ctx = PageRankMockContext::default();
+        let mut annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+
+        algo.execute(&mut ctx, graph, &mut annotator, ())?;
+
+        let mut ranks = annotator
+            .annotator
+            .into_iter()
+            .map(|(id, rank)| (id, rank.to_f64().unwrap()))
+            .collect::<Vec<_>>();
+        ranks.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(ranks)
+    }
+
+    // Test that the exact PageRank is a probability distribution.
+    fn prop_pagerank_is_probability_distribution(graph: Normalised<MockNetwork>) -> TestResult {
+        if graph.normalised_graph.is_empty() {
+            return TestResult::discard();
+        }
+
+        let ranks = run_pagerank(&graph).unwrap();
+
+        // Nodes sharing the same id would collapse into a single annotation.
+        if ranks.len() != graph.node_count() {
+            return TestResult::discard();
+        }
+
+        let total: f64 = ranks.iter().map(|(_, r)| r).sum();
+
+        TestResult::from_bool(ranks.iter().all(|(_, r)| *r >= 0.0) && (total - 1.0).abs() < 1e-6)
+    }
+
+    #[test]
+    fn pagerank_is_probability_distribution() {
+        quickcheck(
+            prop_pagerank_is_probability_distribution as fn(Normalised<MockNetwork>) -> TestResult,
+        );
+    }
+
+    // Same network as the `pagerank_naive_iterative_f64` test for the
+    // `adjacency_matrix` binary, which uses a dense solver.
+    #[test]
+    fn pagerank_agrees_with_dense_solver() {
+        let mut network = Normalised::new(Network::default());
+
+        for node in &["n0", "n1", "n2"] {
+            network.add_node(
+                node.to_string(),
+                ArtifactType::Project {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+
+        let edges = [
+            ("n0", "n0", 0.5),
+            ("n0", "n1", 0.5),
+            ("n1", "n0", 0.5),
+            ("n1", "n2", 0.5),
+            ("n2", "n2", 1.0),
+        ];
+
+        for (ix, edge) in edges.iter().enumerate() {
+            network.add_edge(
+                ix,
+                &edge.0.to_string(),
+                &edge.1.to_string(),
+                edge.2,
+                DependencyType::Influence(edge.2),
+            )
+        }
+
+        let ranks = run_pagerank(&network).unwrap();
+        let expected = [
+            ("n0", 0.18066561014263083),
+            ("n1", 0.1267828843106181),
+            ("n2", 0.6925515055467515),
+        ];
+
+        for ((id, rank), (expected_id, expected_rank)) in ranks.iter().zip(expected.iter()) {
+            assert_eq!(id, expected_id);
+            assert!((rank - expected_rank).abs() < 1e-8, "{}: {}", id, rank);
+        }
+    }
+
+    // Test that the ranks keep their precision on large networks, where they
+    // are all tiny. Every node but "n0" and "n1" links to "n0" and nothing
+    // links to them, so their rank is exactly the teleport probability.
+    #[test]
+    fn pagerank_keeps_small_ranks_precise() {
+        let nodes_num: u32 = 200_000;
+        let mut network = Normalised::new(Network::default());
+
+        for ix in 0..nodes_num {
+            network.add_node(
+                format!("n{}", ix),
+                ArtifactType::Project {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+        for ix in 1..nodes_num {
+            let to = if ix == 1 { "n1" } else { "n0" };
+            network.add_edge(
+                ix as usize,
+                &format!("n{}", ix),
+                &to.to_string(),
+                1.0,
+                DependencyType::Influence(1.0),
+            )
+        }
+        network.add_edge(
+            0,
+            &"n0".to_string(),
+            &"n1".to_string(),
+            1.0,
+            DependencyType::Influence(1.0),
+        );
+
+        let algo: MockPageRankAlgorithm = Mock {
+            unmock: PageRankAlgorithm::default(),
+        };
+        let mut ctx = PageRankMockContext::default();
+        ctx.ledger_view.set_damping_factors(DampingFactors {
+            project: 0.95,
+            account: 0.95,
+        });
+        let mut annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+        assert_eq!(algo.execute(&mut ctx, &network, &mut annotator, ()), Ok(()));
+
+        let expected = 0.05 / f64::from(nodes_num);
+        assert!(expected < 5e-7);
+        for ix in 2..nodes_num {
+            let rank = annotator.annotator[&format!("n{}", ix)].to_f64().unwrap();
+            assert!(
+                ((rank - expected) / expected).abs() < 1e-6,
+                "n{}: {} (expected {})",
+                ix,
+                rank,
+                expected
+            );
+        }
+
+        let total = annotator
+            .annotator
+            .values()
+            .fold(Osrank::zero(), |acc, rank| acc + *rank);
+        assert!((total.to_f64().unwrap() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn pagerank_reports_non_convergence() {
+        let mut network = Normalised::new(Network::default());
+
+        for node in &["n0", "n1"] {
+            network.add_node(
+                node.to_string(),
+                ArtifactType::Project {
+                    osrank: Zero::zero(),
+                },
+            )
+        }
+        network.add_edge(
+            0,
+            &"n0".to_string(),
+            &"n1".to_string(),
+            1.0,
+            DependencyType::Influence(1.0),
+        );
+
+        let algo: MockPageRankAlgorithm = Mock {
+            unmock: PageRankAlgorithm::default(),
+        };
+        let mut ctx = PageRankMockContext {
+            max_iterations: 1,
+            ..Default::default()
+        };
+        let mut annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+
+        assert_eq!(
+            algo.execute(&mut ctx, &network, &mut annotator, ()),
+            Err(OsrankError::PageRankDidNotConverge(1))
+        );
+    }
+}
@@ -61,7 +61,7 @@ pub fn construct_osrank_naive_algorithm<'a>() -> (
 }
 
 pub fn construct_network_small() -> Normalised<MockNetwork> {
-    let mut network = Network::default();
+    let mut network: MockNetwork = Network::default();
     for node in &["p1", "p2", "p3"] {
         network.add_node(
             node.to_string(),
@@ -92,13 +92,12 @@ pub fn construct_network_small() -> Normalised<MockNetwork> {
         ("a3", "p3", Weight::new(1, 1)),
     ];
     for edge in &edges {
-        network.add_edge(
-            2,
+        network.add_new_edge(
             &edge.0.to_string(),
             &edge.1.to_string(),
             edge.2.as_f64().unwrap(),
             DependencyType::Influence(edge.2.as_f64().unwrap()),
-        )
+        );
     }
     Normalised::new(network)
 }
@@ -187,6 +186,7 @@ pub fn run_random_walk(network: &Normalised<MockNetwork>, iter: u32, initial_see
         &network,
         &mock_ledger,
         &Xoshiro256StarStar::from_seed(initial_seed),
+        None,
     )
     .unwrap();
 }
@@ -3,7 +3,9 @@
 use ndarray::Array2;
 use sprs::CsMat;
 
+use core::fmt;
 use core::iter::IntoIterator;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 /// A wrapper that allows labeling data structures (in particular, array and
@@ -51,19 +53,19 @@ impl<N> MatrixLike for Array2<N> {
     }
 }
 
+/// The labels are assigned to the rows and columns of the matrix, regardless
+/// of whether it's stored in CSR or CSC order.
 impl<'a, N> WithLabels<'a> for CsMat<N> {
     fn labeled(self: CsMat<N>, labels: (DimLabels<'a>, DimLabels<'a>)) -> Labeled<CsMat<N>> {
         let mut row_labels = HashMap::default();
         let mut col_labels = HashMap::default();
 
-        if self.is_csr() {
-            for ((ix, _vec), label) in self.outer_iterator().enumerate().zip(labels.0.iter()) {
-                row_labels.insert(ix as u32, label.clone());
-            }
-        } else {
-            for ((ix, _vec), label) in self.outer_iterator().enumerate().zip(labels.1.iter()) {
-                col_labels.insert(ix as u32, label.clone());
-            }
+        for (row_ix, label) in (0..self.get_rows()).zip(labels.0.iter()) {
+            row_labels.insert(row_ix as u32, label.clone());
+        }
+
+        for (col_ix, label) in (0..self.get_cols()).zip(labels.1.iter()) {
+            col_labels.insert(col_ix as u32, label.clone());
         }
 
         Labeled {
@@ -102,6 +104,8 @@ impl<'a, N> WithLabels<'a> for Array2<N> {
 /// A Rank is a Nx1 labeled matrix which can be sorted.
 pub struct Rank<N> {
     internal: Labeled<Array2<N>>,
+    /// The row of every label, for the label-keyed lookups.
+    label_rows: HashMap<String, u32>,
 }
 
 #[derive(Debug)]
@@ -111,13 +115,43 @@ pub enum RankError {
     SortFailedCannotConstructArray,
 }
 
+impl fmt::Display for RankError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RankError::ShapeMismatch(rows, cols) => write!(
+                f,
+                "a rank must be a Nx1 matrix, but a {}x{} one was given",
+                rows, cols
+            ),
+            RankError::SortFailedCannotDeconstructArray => {
+                write!(f, "sorting the rank failed: the matrix is not contiguous")
+            }
+            RankError::SortFailedCannotConstructArray => {
+                write!(
+                    f,
+                    "sorting the rank failed: the sorted matrix couldn't be built"
+                )
+            }
+        }
+    }
+}
+
 impl<N> Rank<N> {
     pub fn from(labeled: Labeled<Array2<N>>) -> Result<Self, RankError> {
         let (rows, cols) = (labeled.internal.get_rows(), labeled.internal.get_cols());
         if cols > 1 {
             Err(RankError::ShapeMismatch(rows, cols))
         } else {
-            Ok(Rank { internal: labeled })
+            let label_rows = labeled
+                .labels
+                .row_labels
+                .iter()
+                .map(|(row, label)| (label.clone(), *row))
+                .collect();
+            Ok(Rank {
+                internal: labeled,
+                label_rows,
+            })
         }
     }
 
@@ -128,6 +162,88 @@ impl<N> Rank<N> {
             labeled: self,
         }
     }
+
+    /// The number of entries of the rank.
+    pub fn len(&self) -> usize {
+        self.internal.internal.get_rows()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the value for the input label, if any.
+    pub fn get(&self, label: &str) -> Option<&N> {
+        self.label_rows
+            .get(label)
+            .map(|row| &self.internal.internal[[*row as usize, 0]])
+    }
+
+    /// Returns the percentage (between 0 and 100) of the entries whose value
+    /// is lower than or equal to the one for the input label, if any. The
+    /// highest ranked label is therefore at the 100th percentile.
+    pub fn percentile_of(&self, label: &str) -> Option<f64>
+    where
+        N: PartialOrd,
+    {
+        let value = self.get(label)?;
+        let below = self
+            .internal
+            .internal
+            .iter()
+            .filter(|v| *v <= value)
+            .count();
+        Some(100.0 * below as f64 / self.len() as f64)
+    }
+
+    /// Sorts the rank, from the highest value to the lowest, carrying the
+    /// labels along. Values which can't be compared (e.g. `NaN`s) are
+    /// considered equal, and entries with equal values keep their order.
+    pub fn sorted(self) -> Result<Self, RankError>
+    where
+        N: PartialOrd + Clone,
+    {
+        let len = self.len();
+        self.top_k(len)
+    }
+
+    /// Like `sorted`, but only keeps the `k` highest ranked entries.
+    pub fn top_k(self, k: usize) -> Result<Self, RankError>
+    where
+        N: PartialOrd + Clone,
+    {
+        let Labeled { labels, internal } = self.internal;
+
+        let values = internal
+            .as_slice()
+            .ok_or(RankError::SortFailedCannotDeconstructArray)?;
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        order.sort_by(|a, b| {
+            values[*b]
+                .partial_cmp(&values[*a])
+                .unwrap_or(Ordering::Equal)
+        });
+        order.truncate(k);
+
+        let mut row_labels = HashMap::default();
+        for (new_ix, old_ix) in order.iter().enumerate() {
+            if let Some(label) = labels.row_labels.get(&(*old_ix as u32)) {
+                row_labels.insert(new_ix as u32, label.clone());
+            }
+        }
+
+        let sorted_values = order.iter().map(|ix| values[*ix].clone()).collect();
+        let sorted = Array2::from_shape_vec((order.len(), 1), sorted_values)
+            .map_err(|_| RankError::SortFailedCannotConstructArray)?;
+
+        Rank::from(Labeled {
+            internal: sorted,
+            labels: Labels {
+                row_labels,
+                col_labels: labels.col_labels,
+            },
+        })
+    }
 }
 
 pub struct RankIter<'a, N> {
@@ -243,4 +359,72 @@ mod tests {
             vec![("bar", &9), ("baz", &7)]
         );
     }
+
+    #[test]
+    fn labeled_csmat_has_row_and_col_labels_in_both_orders() {
+        let dense = arr2(&[[1, 0, 0], [1, 0, 0]]);
+        let rows = vec![String::from("foo"), String::from("bar")];
+        let cols = vec![
+            String::from("quux"),
+            String::from("lorem"),
+            String::from("ipsum"),
+        ];
+
+        for mtrx in &[
+            CsMat::csr_from_dense(dense.view(), 0),
+            CsMat::csc_from_dense(dense.view(), 0),
+        ] {
+            let lbld = mtrx.clone().labeled((rows.as_slice(), cols.as_slice()));
+            assert_eq!(lbld.labels.row_labels.len(), 2);
+            assert_eq!(lbld.labels.col_labels.len(), 3);
+            assert_eq!(lbld.labels.row_labels[&1], "bar");
+            assert_eq!(lbld.labels.col_labels[&2], "ipsum");
+        }
+    }
+
+    fn rank_of(values: &[i32]) -> Rank<i32> {
+        let labels = (0..values.len())
+            .map(|ix| format!("n{}", ix))
+            .collect::<Vec<String>>();
+        let mtrx = Array2::from_shape_vec((values.len(), 1), values.to_vec()).unwrap();
+        Rank::from(mtrx.labeled((labels.as_slice(), &[]))).unwrap_or_else(|e| panic!(e))
+    }
+
+    #[quickcheck]
+    fn rank_sorted_keeps_the_labels_and_sorts_the_values(values: Vec<i32>) -> bool {
+        let rank = rank_of(&values);
+        let expected = rank
+            .into_iter()
+            .map(|(l, v)| (l.to_string(), *v))
+            .sorted_by(|(_, v1), (_, v2)| v2.cmp(v1))
+            .collect::<Vec<_>>();
+
+        let sorted = rank.sorted().unwrap();
+        let actual = sorted
+            .into_iter()
+            .map(|(l, v)| (l.to_string(), *v))
+            .collect::<Vec<_>>();
+
+        actual == expected && actual.iter().all(|(l, v)| sorted.get(l) == Some(v))
+    }
+
+    #[test]
+    fn rank_top_k_and_percentiles() {
+        let rank = rank_of(&[1, 9, 7, 3]);
+
+        assert_eq!(rank.get("n1"), Some(&9));
+        assert_eq!(rank.get("quux"), None);
+        assert_eq!(rank.percentile_of("n1"), Some(100.0));
+        assert_eq!(rank.percentile_of("n3"), Some(50.0));
+        assert_eq!(rank.percentile_of("quux"), None);
+
+        let top = rank.top_k(2).unwrap();
+        assert_eq!(top.len(), 2);
+        assert_eq!(
+            top.into_iter().collect::<Vec<(&str, &i32)>>(),
+            vec![("n1", &9), ("n2", &7)]
+        );
+        assert_eq!(top.get("n0"), None);
+        assert_eq!(top.get("n2"), Some(&7));
+    }
 }
@@ -1,35 +1,41 @@
+use super::ExportError;
+use crate::types::RankEstimate;
+use fraction::ToPrimitive;
 use itertools::Itertools;
-use std::fs::OpenOptions;
 use std::io::Write;
 
-#[derive(Debug, Display)]
-pub enum CsvExporterError {
-    IOError(std::io::Error),
-}
-
-impl From<std::io::Error> for CsvExporterError {
-    fn from(err: std::io::Error) -> CsvExporterError {
-        CsvExporterError::IOError(err)
-    }
-}
-
-/// Given a (id,rank) iterator, write into a `.csv` file the (sorted) rank,
+/// Given a (id,rank) iterator, write into the output `.csv` the (sorted) rank,
 /// from the highest to the lowest.
-pub fn export_rank_to_csv<K, V>(
+pub fn export_rank_to_csv<K, V, W>(
     annotator: impl Iterator<Item = (K, V)>,
     to_f64: Box<dyn Fn(V) -> f64>,
-    out_path: &str,
-) -> Result<(), CsvExporterError>
+    output_csv: W,
+) -> Result<(), ExportError>
 where
     V: PartialOrd,
     K: std::fmt::Display,
+    W: Write,
 {
-    let mut output_csv = OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .open(out_path)?;
+    write_rank_to_csv(
+        annotator.sorted_by(|(_, v1), (_, v2)| v2.partial_cmp(v1).unwrap()),
+        to_f64,
+        output_csv,
+    )
+}
 
-    for (node_id, rank) in annotator.sorted_by(|(_, v1), (_, v2)| v2.partial_cmp(v1).unwrap()) {
+/// Like `export_rank_to_csv`, but for a rank which is already sorted (e.g.
+/// a `collections::Rank` after `sorted` or `top_k`), which is written in the
+/// order it's given.
+pub fn write_rank_to_csv<K, V, W>(
+    rank: impl Iterator<Item = (K, V)>,
+    to_f64: Box<dyn Fn(V) -> f64>,
+    mut output_csv: W,
+) -> Result<(), ExportError>
+where
+    K: std::fmt::Display,
+    W: Write,
+{
+    for (node_id, rank) in rank {
         output_csv.write_all(
             format!("{},{:.32}\n", node_id, to_f64(rank))
                 .as_str()
@@ -37,5 +43,39 @@ where
         )?;
     }
 
+    output_csv.flush()?;
+    Ok(())
+}
+
+/// Given a (id,estimate) iterator, write into the output `.csv` the (sorted)
+/// rank, from the highest to the lowest, followed by its standard error and
+/// the lower and upper bounds of its confidence interval, which spans `z`
+/// standard errors on each side of the rank (e.g. `1.96` for 95%).
+pub fn export_rank_estimates_to_csv<K, W>(
+    annotator: impl Iterator<Item = (K, RankEstimate)>,
+    z: f64,
+    mut output_csv: W,
+) -> Result<(), ExportError>
+where
+    K: std::fmt::Display,
+    W: Write,
+{
+    for (node_id, estimate) in annotator.sorted_by(|(_, v1), (_, v2)| v2.rank.cmp(&v1.rank)) {
+        let (lower, upper) = estimate.interval(z);
+        output_csv.write_all(
+            format!(
+                "{},{:.32},{:.32},{:.32},{:.32}\n",
+                node_id,
+                estimate.rank.to_f64().unwrap_or(0.0),
+                estimate.std_error,
+                lower,
+                upper
+            )
+            .as_str()
+            .as_bytes(),
+        )?;
+    }
+
+    output_csv.flush()?;
     Ok(())
 }
@@ -0,0 +1,387 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
+use crate::types::mock::KeyValueAnnotator;
+use oscoin_graph_api::{Direction, Edge, EdgeRef, Graph, GraphObject};
+
+use super::{size_from_rank, ExportError, Exporter, NodeType, Rank, RgbColor, WritePolicy};
+
+use num_traits::Zero;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::convert::TryInto;
+use std::hash::Hash;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// The static header for a `.dot` file.
+///
+/// Nodes are filled circles, whose size (a minimum, so that labels are never
+/// clipped) and colour are set on a node-by-node basis.
+static DOT_META: &str = r###"digraph osrank {
+    node [shape=circle, style=filled, fontcolor=white];
+    edge [fontsize=10];
+"###;
+
+static DOT_FOOTER: &str = "}\n";
+
+/// The number of points in an inch, as Graphviz sizes nodes in inches.
+const POINTS_PER_INCH: f64 = 72.0;
+
+pub trait IntoDot {
+    fn render(&self) -> String;
+}
+
+/// Strings are rendered as quoted DOT identifiers.
+impl IntoDot for String {
+    fn render(&self) -> String {
+        format!("\"{}\"", self.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+impl IntoDot for f64 {
+    fn render(&self) -> String {
+        format!("{}", self)
+    }
+}
+
+impl IntoDot for usize {
+    fn render(&self) -> String {
+        format!("{}", self)
+    }
+}
+
+impl IntoDot for RgbColor {
+    fn render(&self) -> String {
+        format!("\"#{:02x}{:02x}{:02x}\"", self.red, self.green, self.blue)
+    }
+}
+
+struct DotNode<I> {
+    id: I,
+    label: String,
+    rank: f64,
+    size: f64,
+    fill_color: RgbColor,
+}
+
+impl<I> IntoDot for DotNode<I>
+where
+    I: IntoDot,
+{
+    fn render(&self) -> String {
+        let width = self.size / POINTS_PER_INCH;
+        format!(
+            "    {} [label={}, tooltip={}, width={}, height={}, fillcolor={}];",
+            self.id.render(),
+            self.label.render(),
+            format!("osrank: {}", self.rank).render(),
+            width,
+            width,
+            self.fill_color.render()
+        )
+    }
+}
+
+struct DotEdge<N, W> {
+    source: N,
+    target: N,
+    weight: W,
+}
+
+impl<N, W> IntoDot for DotEdge<N, W>
+where
+    N: IntoDot,
+    W: IntoDot,
+{
+    fn render(&self) -> String {
+        format!(
+            "    {} -> {} [label=\"{}\"];",
+            self.source.render(),
+            self.target.render(),
+            self.weight.render()
+        )
+    }
+}
+
+/// Returns the rank of the input node, as stored in the annotator.
+fn rank_of<I, V>(annotator: &KeyValueAnnotator<I, V>, id: &I) -> Rank<f64>
+where
+    I: Eq + Hash,
+    V: Into<Rank<f64>> + Zero + Clone,
+{
+    annotator
+        .annotator
+        .get(id)
+        .cloned()
+        .unwrap_or_else(V::zero)
+        .into()
+}
+
+/// Converts a `Graph::Node` into a DOT node statement.
+fn write_node<N, V, W>(
+    node: &N,
+    annotator: &KeyValueAnnotator<<N as GraphObject>::Id, V>,
+    out: &mut W,
+) -> Result<(), ExportError>
+where
+    N: GraphObject,
+    V: Into<Rank<f64>> + Zero + Clone,
+    <N as GraphObject>::Data: Clone + Into<NodeType>,
+    <N as GraphObject>::Id: IntoDot + Clone + TryInto<String> + Eq + Hash,
+    W: Write,
+{
+    let rank = rank_of(annotator, node.id());
+    let node_type: NodeType = node.data().clone().into();
+
+    let dot_node = DotNode {
+        id: node.id().clone(),
+        label: node
+            .id()
+            .clone()
+            .try_into()
+            .unwrap_or_else(|_| String::from("Unlabeled node")),
+        rank: rank.rank,
+        size: size_from_rank(rank),
+        fill_color: node_type.into(),
+    };
+
+    out.write_all(dot_node.render().as_bytes())?;
+    Ok(())
+}
+
+/// Converts a `Graph::Edge` into a DOT edge statement, labelled with its
+/// weight.
+fn write_edge<G, W>(
+    g: &G,
+    edge: &EdgeRef<<G::Node as GraphObject>::Id, <G::Edge as GraphObject>::Id>,
+    out: &mut W,
+) -> Result<(), ExportError>
+where
+    G: Graph,
+    <G::Node as GraphObject>::Id: IntoDot + Clone,
+    <G as Graph>::Weight: IntoDot + Zero,
+    W: Write,
+{
+    let dot_edge = DotEdge {
+        source: edge.from.clone(),
+        target: edge.to.clone(),
+        weight: g
+            .get_edge(edge.id)
+            .map(|e| e.weight())
+            .unwrap_or_else(<G as Graph>::Weight::zero),
+    };
+
+    out.write_all(dot_edge.render().as_bytes())?;
+    Ok(())
+}
+
+/// Returns the ids of the `n` nodes with the highest rank. Nodes with the
+/// same rank are picked in the order the graph yields them.
+fn top_nodes<G, V>(
+    g: &G,
+    annotator: &KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
+    n: usize,
+) -> HashSet<<G::Node as GraphObject>::Id>
+where
+    G: Graph,
+    <G::Node as GraphObject>::Id: Clone + Eq + Hash,
+    V: Into<Rank<f64>> + Zero + Clone,
+{
+    let mut ranked: Vec<_> = g
+        .nodes()
+        .map(|node| (node.id().clone(), rank_of(annotator, node.id()).rank))
+        .collect();
+    ranked.sort_by(|(_, r1), (_, r2)| r2.partial_cmp(r1).unwrap_or(Ordering::Equal));
+    ranked.into_iter().take(n).map(|(id, _)| id).collect()
+}
+
+/// Converts the `Graph` into some DOT statements. If `top_n` is given, only
+/// the `top_n` nodes with the highest rank (and the edges between them) are
+/// written.
+fn write_graph<G, V, W>(
+    g: &G,
+    annotator: &KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
+    top_n: Option<usize>,
+    out: &mut W,
+) -> Result<(), ExportError>
+where
+    G: Graph,
+    V: Into<Rank<f64>> + Zero + Clone,
+    <G::Node as GraphObject>::Data: Clone + Into<NodeType>,
+    <G::Node as GraphObject>::Id: IntoDot + Clone + TryInto<String> + Eq + Hash,
+    <G as Graph>::Weight: IntoDot + Zero,
+    W: Write,
+{
+    let kept = top_n.map(|n| top_nodes(g, annotator, n));
+    let is_kept = |id: &<G::Node as GraphObject>::Id| match &kept {
+        None => true,
+        Some(k) => k.contains(id),
+    };
+
+    let mut all_edges = Vec::new();
+
+    for n in g.nodes().filter(|n| is_kept(n.id())) {
+        write_node(n, annotator, out)?;
+        out.write_all(b"\n")?;
+        all_edges.extend(
+            g.edges_directed(n.id(), Direction::Outgoing)
+                .into_iter()
+                .filter(|e| is_kept(e.to)),
+        )
+    }
+
+    for e in &all_edges {
+        write_edge(g, e, out)?;
+        out.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
+
+/// Exports a graph `G` into DOT, written into `out`. If `top_n` is given,
+/// only the `top_n` nodes with the highest rank are exported.
+///
+/// The output can then be rendered with [Graphviz](https://graphviz.org/),
+/// e.g. via `dot -Tsvg`.
+fn export_graph_impl<G, V, W>(
+    g: &G,
+    annotator: &KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
+    top_n: Option<usize>,
+    out: &mut W,
+) -> Result<(), ExportError>
+where
+    G: Graph,
+    V: Into<Rank<f64>> + Zero + Clone,
+    <G::Node as GraphObject>::Data: Clone + Into<NodeType>,
+    <G::Node as GraphObject>::Id: IntoDot + Clone + TryInto<String> + Eq + Hash,
+    <G as Graph>::Weight: IntoDot + Zero,
+    W: Write,
+{
+    out.write_all(DOT_META.as_bytes())?;
+    write_graph(g, annotator, top_n, out)?;
+    out.write_all(DOT_FOOTER.as_bytes())?;
+    out.flush()?;
+
+    Ok(())
+}
+
+/// Exports a graph `G` to the `.dot` file at `path`, following `policy` if
+/// the file already exists. If `top_n` is given, only the `top_n` nodes with
+/// the highest rank are exported.
+/// `WritePolicy::Append` is rejected, as the file holds a single document.
+pub fn export_graph_to_file<G, V, P>(
+    g: &G,
+    annotator: &KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
+    top_n: Option<usize>,
+    path: P,
+    policy: WritePolicy,
+) -> Result<(), ExportError>
+where
+    G: Graph,
+    V: Into<Rank<f64>> + Zero + Clone,
+    <G::Node as GraphObject>::Data: Clone + Into<NodeType>,
+    <G::Node as GraphObject>::Id: IntoDot + Clone + TryInto<String> + Eq + Hash,
+    <G as Graph>::Weight: IntoDot + Zero,
+    P: AsRef<Path>,
+{
+    let mut out = BufWriter::new(policy.open_document(path)?);
+    export_graph_impl(g, annotator, top_n, &mut out)
+}
+
+pub struct DotExporter<'a, G, V, W>
+where
+    G: Graph,
+{
+    graph: &'a G,
+    annotator: &'a KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
+    out: W,
+    top_n: Option<usize>,
+}
+
+impl<'a, G, V, W> DotExporter<'a, G, V, W>
+where
+    G: Graph,
+{
+    pub fn new(
+        graph: &'a G,
+        annotator: &'a KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
+        out: W,
+    ) -> Self {
+        DotExporter {
+            graph,
+            annotator,
+            out,
+            top_n: None,
+        }
+    }
+
+    /// Only exports the `n` nodes with the highest rank, and the edges
+    /// between them, which keeps the rendering of large graphs readable.
+    pub fn top_n(self, n: usize) -> Self {
+        DotExporter {
+            top_n: Some(n),
+            ..self
+        }
+    }
+}
+
+impl<'a, G, V, W> Exporter for DotExporter<'a, G, V, W>
+where
+    G: Graph,
+    V: Into<Rank<f64>> + Zero + Clone,
+    <G::Node as GraphObject>::Data: Clone + Into<NodeType>,
+    <G::Node as GraphObject>::Id: IntoDot + Clone + TryInto<String> + Eq + Hash,
+    <G as Graph>::Weight: IntoDot + Zero,
+    W: Write,
+{
+    type ExporterOutput = W;
+    type ExporterError = ExportError;
+    fn export(mut self) -> Result<Self::ExporterOutput, Self::ExporterError> {
+        export_graph_impl(self.graph, self.annotator, self.top_n, &mut self.out)?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::types::mock::MockNetwork;
+    use crate::types::network::{ArtifactType, DependencyType};
+    use crate::types::Osrank;
+    use oscoin_graph_api::GraphWriter;
+
+    #[test]
+    fn dot_export_keeps_the_top_n_nodes() {
+        let mut network = MockNetwork::default();
+        let mut annotator = KeyValueAnnotator {
+            annotator: Default::default(),
+        };
+
+        for (id, rank) in &[("foo", 3), ("bar", 1), ("baz", 2)] {
+            let rank = Osrank::new(*rank as u64, 8u64);
+            network.add_node(id.to_string(), ArtifactType::Account { osrank: rank });
+            annotator.annotator.insert(id.to_string(), rank);
+        }
+        let edges = [(0, "foo", "bar"), (1, "foo", "baz"), (2, "baz", "foo")];
+        for (id, from, to) in &edges {
+            let from = from.to_string();
+            let to = to.to_string();
+            network.add_edge(*id, &from, &to, 0.5, DependencyType::Influence(0.5));
+        }
+
+        let out = DotExporter::new(&network, &annotator, Vec::new())
+            .top_n(2)
+            .export()
+            .unwrap();
+        let dot = String::from_utf8(out).unwrap();
+
+        assert!(dot.starts_with("digraph osrank {"));
+        assert!(dot.contains(r#""foo" [label="foo", tooltip="osrank: 0.375""#));
+        assert!(dot.contains(r##"fillcolor="#ff0000""##));
+        assert!(dot.contains(r#""foo" -> "baz" [label="0.5"];"#));
+        assert!(dot.contains(r#""baz" -> "foo" [label="0.5"];"#));
+        assert!(!dot.contains(r#""bar""#));
+    }
+}
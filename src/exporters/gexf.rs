@@ -2,15 +2,17 @@
 #![warn(clippy::all)]
 
 use num_traits::Zero;
-use oscoin_graph_api::{Direction, EdgeRef, Graph, GraphObject};
+use oscoin_graph_api::{Direction, Edge, EdgeRef, Graph, GraphObject};
 
-use super::{size_from_rank, Exporter, NodeType, Rank, RgbColor};
+use super::{
+    size_from_rank, EdgeType, ExportError, Exporter, NodeType, Rank, RgbColor, WritePolicy,
+};
 use crate::types::mock::KeyValueAnnotator;
+use crate::util::xml::escape;
 
 use std::convert::TryInto;
-use std::fs::{File, OpenOptions};
 use std::hash::Hash;
-use std::io::Write;
+use std::io::{BufWriter, Write};
 use std::path::Path;
 
 static GEXF_META: &str = r###"<?xml version="1.0" encoding="UTF-8"?>
@@ -24,29 +26,32 @@ static GEXF_META: &str = r###"<?xml version="1.0" encoding="UTF-8"?>
     <attribute id="osrank" title="osrank" type="float">
         <default>0.0</default>
     </attribute>
+    <attribute id="type" title="type" type="string">
+        <default>project</default>
+    </attribute>
+  </attributes>
+  <attributes class="edge" mode="static">
+    <attribute id="type" title="type" type="string">
+        <default>influence</default>
+    </attribute>
   </attributes>
 "###;
 
 static GEXF_FOOTER: &str = "</gexf>";
 
-#[derive(Debug)]
-pub enum ExportError {
-    IOError(std::io::Error),
-}
-
-impl From<std::io::Error> for ExportError {
-    fn from(err: std::io::Error) -> ExportError {
-        ExportError::IOError(err)
-    }
-}
-
 pub trait IntoGexfXml {
     fn render(&self) -> String;
 }
 
 impl IntoGexfXml for String {
     fn render(&self) -> String {
-        self.clone()
+        escape(self)
+    }
+}
+
+impl IntoGexfXml for f64 {
+    fn render(&self) -> String {
+        format!("{}", self)
     }
 }
 
@@ -95,6 +100,7 @@ impl IntoGexfXml for NodeStyle {
 
 pub struct NodeAttrs {
     rank: f64,
+    node_type: &'static str,
 }
 
 impl IntoGexfXml for NodeAttrs {
@@ -102,9 +108,10 @@ impl IntoGexfXml for NodeAttrs {
         format!(
             r###"<attvalues>
                 <attvalue for="osrank" value="{}"/>
+                <attvalue for="type" value="{}"/>
               </attvalues>
               "###,
-            self.rank,
+            self.rank, self.node_type,
         )
     }
 }
@@ -123,7 +130,7 @@ where
     fn render(&self) -> String {
         let mb_label = match &self.label {
             None => String::new(),
-            Some(l) => format!(r###"label="{}""###, l),
+            Some(l) => format!(r###"label="{}""###, escape(l)),
         };
         format!(
             r###"<node id="{}" {}>
@@ -131,45 +138,53 @@ where
                  {}
         </node>"###,
             self.id.render(),
-            mb_label.render(),
+            mb_label,
             self.node_style.render(),
             self.node_attrs.render()
         )
     }
 }
 
-struct GexfEdge<I, N> {
+struct GexfEdge<I, N, W> {
     id: I,
     source: N,
     target: N,
+    weight: W,
+    edge_type: &'static str,
 }
 
-impl<I, N> IntoGexfXml for GexfEdge<I, N>
+impl<I, N, W> IntoGexfXml for GexfEdge<I, N, W>
 where
     I: IntoGexfXml,
     N: IntoGexfXml,
+    W: IntoGexfXml,
 {
     fn render(&self) -> String {
         format!(
-            "<edge id=\"{}\" source=\"{}\" target=\"{}\"/>",
+            r###"<edge id="{}" source="{}" target="{}" weight="{}">
+              <attvalues><attvalue for="type" value="{}"/></attvalues>
+            </edge>"###,
             self.id.render(),
             self.source.render(),
             self.target.render(),
+            self.weight.render(),
+            self.edge_type,
         )
     }
 }
 
 /// Converts a `Graph::Node` into some GEXF tags.
-fn write_node<N, V>(
+fn write_node<N, V, W>(
     node: &N,
     annotator: &KeyValueAnnotator<<N as GraphObject>::Id, V>,
-    out: &mut File,
+    out: &mut W,
 ) -> Result<(), ExportError>
 where
     N: GraphObject,
     N::Id: IntoGexfXml + Clone + TryInto<String> + Eq + Hash,
     <N as GraphObject>::Data: Clone + Into<NodeType>,
     V: Into<Rank<f64>> + Zero + Clone,
+    W: Write,
 {
     let data: <N as GraphObject>::Data = (*node).data().clone();
     let node_type: NodeType = data.clone().into();
@@ -182,11 +197,14 @@ where
     let gexf_node = GexfNode {
         id: node.id().clone(),
         label: node.id().clone().try_into().ok(),
+        node_attrs: NodeAttrs {
+            rank: rank.rank,
+            node_type: node_type.as_str(),
+        },
         node_style: NodeStyle {
             fill_color: node_type.into(),
             size: size_from_rank(rank),
         },
-        node_attrs: NodeAttrs { rank: rank.rank },
     };
 
     out.write_all(gexf_node.render().as_bytes())?;
@@ -194,15 +212,31 @@ where
 }
 
 /// Converts a `Graph::Edge` into some GEXF tags.
-fn write_edge<N, E>(edge: &EdgeRef<N, E>, out: &mut File) -> Result<(), ExportError>
+fn write_edge<G, W>(
+    g: &G,
+    edge: &EdgeRef<<G::Node as GraphObject>::Id, <G::Edge as GraphObject>::Id>,
+    out: &mut W,
+) -> Result<(), ExportError>
 where
-    E: IntoGexfXml + Clone,
-    N: IntoGexfXml + Clone,
+    G: Graph,
+    <G::Node as GraphObject>::Id: IntoGexfXml + Clone,
+    <G::Edge as GraphObject>::Id: IntoGexfXml + Clone,
+    <G::Edge as GraphObject>::Data: Clone + Into<EdgeType>,
+    <G as Graph>::Weight: IntoGexfXml + Zero,
+    W: Write,
 {
+    let e = g.get_edge(edge.id);
+    let edge_type: EdgeType = e
+        .map(|e| e.data().clone().into())
+        .unwrap_or(EdgeType::Influence);
     let gexf_edge = GexfEdge {
         id: edge.id.clone(),
         source: edge.from.clone(),
         target: edge.to.clone(),
+        weight: e
+            .map(|e| e.weight())
+            .unwrap_or_else(<G as Graph>::Weight::zero),
+        edge_type: edge_type.as_str(),
     };
 
     out.write_all(gexf_edge.render().as_bytes())?;
@@ -210,17 +244,20 @@ where
 }
 
 /// Converts the `Graph` into some GEXF tags.
-fn write_graph<G, V>(
+fn write_graph<G, V, W>(
     g: &G,
     annotator: &KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
-    out: &mut File,
+    out: &mut W,
 ) -> Result<(), ExportError>
 where
     G: Graph,
     <G::Node as GraphObject>::Id: IntoGexfXml + Clone + TryInto<String> + Eq + Hash,
     <G::Node as GraphObject>::Data: Clone + Into<NodeType>,
     <G::Edge as GraphObject>::Id: IntoGexfXml + Clone,
+    <G::Edge as GraphObject>::Data: Clone + Into<EdgeType>,
+    <G as Graph>::Weight: IntoGexfXml + Zero,
     V: Into<Rank<f64>> + Zero + Clone,
+    W: Write,
 {
     let mut all_edges = Vec::new();
 
@@ -235,7 +272,7 @@ where
     out.write_all(b"</nodes>\n<edges>")?;
 
     for e in &all_edges {
-        write_edge(e, out)?;
+        write_edge(g, e, out)?;
         out.write_all(b"\n")?;
     }
 
@@ -244,70 +281,97 @@ where
     Ok(())
 }
 
-/// Exports a graph `G` to a `.gexf` file.
+/// Exports a graph `G` into GEXF, written into `out`.
 ///
-/// This file can then be imported into one of the many graph visualisers,
+/// The output can then be imported into one of the many graph visualisers,
 /// like [Gephi](https://gephi.org/).
-fn export_graph_impl<G, V>(
+fn export_graph_impl<G, V, W>(
     g: &G,
     annotator: &KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
-    out: &Path,
+    out: &mut W,
 ) -> Result<(), ExportError>
 where
     G: Graph,
     <G::Node as GraphObject>::Id: IntoGexfXml + Clone + TryInto<String> + Eq + Hash,
     <G::Node as GraphObject>::Data: Clone + Into<NodeType>,
     <G::Edge as GraphObject>::Id: IntoGexfXml + Clone,
+    <G::Edge as GraphObject>::Data: Clone + Into<EdgeType>,
+    <G as Graph>::Weight: IntoGexfXml + Zero,
     V: Into<Rank<f64>> + Zero + Clone,
+    W: Write,
 {
-    let mut out_file = OpenOptions::new().write(true).create_new(true).open(out)?;
-
-    out_file.write_all(GEXF_META.as_bytes())?;
-    write_graph(g, annotator, &mut out_file)?;
-    out_file.write_all(GEXF_FOOTER.as_bytes())?;
+    out.write_all(GEXF_META.as_bytes())?;
+    write_graph(g, annotator, out)?;
+    out.write_all(GEXF_FOOTER.as_bytes())?;
+    out.flush()?;
 
     Ok(())
 }
 
-pub struct GexfExporter<'a, G, V>
+/// Exports a graph `G` to the `.gexf` file at `path`, following `policy`
+/// if the file already exists.
+/// `WritePolicy::Append` is rejected, as the file holds a single document.
+pub fn export_graph_to_file<G, V, P>(
+    g: &G,
+    annotator: &KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
+    path: P,
+    policy: WritePolicy,
+) -> Result<(), ExportError>
+where
+    G: Graph,
+    <G::Node as GraphObject>::Id: IntoGexfXml + Clone + TryInto<String> + Eq + Hash,
+    <G::Node as GraphObject>::Data: Clone + Into<NodeType>,
+    <G::Edge as GraphObject>::Id: IntoGexfXml + Clone,
+    <G::Edge as GraphObject>::Data: Clone + Into<EdgeType>,
+    <G as Graph>::Weight: IntoGexfXml + Zero,
+    V: Into<Rank<f64>> + Zero + Clone,
+    P: AsRef<Path>,
+{
+    let mut out = BufWriter::new(policy.open_document(path)?);
+    export_graph_impl(g, annotator, &mut out)
+}
+
+pub struct GexfExporter<'a, G, V, W>
 where
     G: Graph,
 {
     graph: &'a G,
     annotator: &'a KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
-    out_path: &'a str,
+    out: W,
 }
 
-impl<'a, G, V> GexfExporter<'a, G, V>
+impl<'a, G, V, W> GexfExporter<'a, G, V, W>
 where
     G: Graph,
 {
     pub fn new(
         graph: &'a G,
         annotator: &'a KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
-        out_path: &'a str,
+        out: W,
     ) -> Self {
         GexfExporter {
             graph,
             annotator,
-            out_path,
+            out,
         }
     }
 }
 
-impl<'a, G, V> Exporter for GexfExporter<'a, G, V>
+impl<'a, G, V, W> Exporter for GexfExporter<'a, G, V, W>
 where
     G: Graph,
     <G::Node as GraphObject>::Id: IntoGexfXml + Clone + TryInto<String> + Eq + Hash,
     <G::Node as GraphObject>::Data: Clone + Into<NodeType>,
     <G::Edge as GraphObject>::Id: IntoGexfXml + Clone,
+    <G::Edge as GraphObject>::Data: Clone + Into<EdgeType>,
+    <G as Graph>::Weight: IntoGexfXml + Zero,
     V: Into<Rank<f64>> + Zero + Clone,
+    W: Write,
 {
-    type ExporterOutput = ();
+    type ExporterOutput = W;
     type ExporterError = ExportError;
-    fn export(self) -> Result<Self::ExporterOutput, Self::ExporterError> {
-        let pth = self.out_path.to_owned() + ".gexf";
-        let out_with_ext = Path::new(&pth);
-        export_graph_impl(self.graph, self.annotator, &out_with_ext)
+    fn export(mut self) -> Result<Self::ExporterOutput, Self::ExporterError> {
+        export_graph_impl(self.graph, self.annotator, &mut self.out)?;
+        Ok(self.out)
     }
 }
@@ -2,15 +2,17 @@
 #![warn(clippy::all)]
 
 use crate::types::mock::KeyValueAnnotator;
+use crate::util::xml::escape;
 use oscoin_graph_api::{Direction, Edge, EdgeRef, Graph, GraphObject};
 
-use super::{size_from_rank, Exporter, NodeType, Rank, RgbColor};
+use super::{
+    size_from_rank, EdgeType, ExportError, Exporter, NodeType, Rank, RgbColor, WritePolicy,
+};
 
 use num_traits::Zero;
 use std::convert::TryInto;
-use std::fs::{File, OpenOptions};
 use std::hash::Hash;
-use std::io::Write;
+use std::io::{BufWriter, Write};
 use std::path::Path;
 
 /// The static header for a `.graphml` file.
@@ -24,7 +26,13 @@ static GRAPHML_META: &str = r###"<?xml version="1.0" encoding="UTF-8"?>
     xsi:schemaLocation="http://graphml.graphdrawing.org/xmlns
      http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd">
      <key for="edge" id="edge_weight" attr.name="weight" attr.type="double"/>
+     <key for="edge" id="edge_type" attr.name="type" attr.type="string">
+       <default>influence</default>
+     </key>
      <key attr.name="label" attr.type="string" for="node" id="label"/>
+     <key attr.name="type" attr.type="string" for="node" id="type">
+       <default>project</default>
+     </key>
      <key attr.name="rank" attr.type="float" for="node" id="rank">
        <default>0.0</default>
      </key>
@@ -44,24 +52,13 @@ static GRAPHML_META: &str = r###"<?xml version="1.0" encoding="UTF-8"?>
 
 static GRAPHML_FOOTER: &str = "</graphml>";
 
-#[derive(Debug)]
-pub enum ExportError {
-    IOError(std::io::Error),
-}
-
-impl From<std::io::Error> for ExportError {
-    fn from(err: std::io::Error) -> ExportError {
-        ExportError::IOError(err)
-    }
-}
-
 pub trait IntoGraphMlXml {
     fn render(&self) -> String;
 }
 
 impl IntoGraphMlXml for String {
     fn render(&self) -> String {
-        self.clone()
+        escape(self)
     }
 }
 
@@ -103,6 +100,7 @@ impl IntoGraphMlXml for RgbColor {
 
 pub struct NodeAttrs {
     label: String,
+    node_type: &'static str,
     fill_color: RgbColor,
     size: f64,
     rank: f64,
@@ -112,11 +110,13 @@ impl IntoGraphMlXml for NodeAttrs {
     fn render(&self) -> String {
         format!(
             r###"<data key="label">{}</data>
+                 <data key="type">{}</data>
                  <data key="size">{}</data>
                  <data key="rank">{}</data>
                  {}
                 "###,
-            self.label,
+            escape(&self.label),
+            self.node_type,
             self.size,
             self.rank,
             self.fill_color.render()
@@ -142,14 +142,15 @@ where
     }
 }
 
-struct GexfEdge<I, N, W> {
+struct GraphMlEdge<I, N, W> {
     id: I,
     source: N,
     target: N,
     weight: W,
+    edge_type: &'static str,
 }
 
-impl<I, N, W> IntoGraphMlXml for GexfEdge<I, N, W>
+impl<I, N, W> IntoGraphMlXml for GraphMlEdge<I, N, W>
 where
     I: IntoGraphMlXml,
     N: IntoGraphMlXml,
@@ -159,26 +160,29 @@ where
         format!(
             r###"<edge id="{}" source="{}" target="{}">
                    <data key="edge_weight">{}</data>
+                   <data key="edge_type">{}</data>
 </edge>"###,
             self.id.render(),
             self.source.render(),
             self.target.render(),
-            self.weight.render()
+            self.weight.render(),
+            self.edge_type
         )
     }
 }
 
 /// Converts a `Graph::Node` into some GRAPHML tags.
-fn write_node<N, V>(
+fn write_node<N, V, W>(
     node: &N,
     annotator: &KeyValueAnnotator<<N as GraphObject>::Id, V>,
-    out: &mut File,
+    out: &mut W,
 ) -> Result<(), ExportError>
 where
     N: GraphObject,
     V: Into<Rank<f64>> + Zero + Clone,
     <N as GraphObject>::Data: Clone + Into<NodeType> + Into<Rank<f64>>,
     <N as GraphObject>::Id: IntoGraphMlXml + Clone + TryInto<String> + Eq + Hash,
+    W: Write,
 {
     let data: <N as GraphObject>::Data = (*node).data().clone();
     let rank: Rank<f64> = annotator
@@ -194,55 +198,62 @@ where
         .try_into()
         .unwrap_or_else(|_| String::from("Unlabeled node"));
 
-    let gexf_node = GraphMlNode {
+    let graphml_node = GraphMlNode {
         id: node.id().clone(),
         node_style: NodeAttrs {
             label: lbl,
+            node_type: node_type.as_str(),
             size: size_from_rank(rank),
             rank: rank.rank,
             fill_color: node_type.into(),
         },
     };
 
-    out.write_all(gexf_node.render().as_bytes())?;
+    out.write_all(graphml_node.render().as_bytes())?;
     Ok(())
 }
 
 /// Converts a `Graph::Edge` into some GRAPHML tags.
-fn write_edge<G>(
+fn write_edge<G, W>(
     g: &G,
     edge: &EdgeRef<<G::Node as GraphObject>::Id, <G::Edge as GraphObject>::Id>,
-    out: &mut File,
+    out: &mut W,
 ) -> Result<(), ExportError>
 where
     G: Graph,
     <G::Node as GraphObject>::Id: IntoGraphMlXml + Clone,
     <G::Edge as GraphObject>::Id: IntoGraphMlXml + Clone,
+    <G::Edge as GraphObject>::Data: Clone + Into<EdgeType>,
     <G as Graph>::Weight: IntoGraphMlXml + Zero,
+    W: Write,
 {
-    let gexf_edge: GexfEdge<
+    let e = g.get_edge(edge.id);
+    let edge_type: EdgeType = e
+        .map(|e| e.data().clone().into())
+        .unwrap_or(EdgeType::Influence);
+    let graphml_edge: GraphMlEdge<
         <G::Edge as GraphObject>::Id,
         <G::Node as GraphObject>::Id,
         <G as Graph>::Weight,
-    > = GexfEdge {
+    > = GraphMlEdge {
         id: edge.id.clone(),
         source: edge.from.clone(),
         target: edge.to.clone(),
-        weight: g
-            .get_edge(edge.id)
+        weight: e
             .and_then(|e| Some(e.weight()))
             .unwrap_or_else(<G as Graph>::Weight::zero),
+        edge_type: edge_type.as_str(),
     };
 
-    out.write_all(gexf_edge.render().as_bytes())?;
+    out.write_all(graphml_edge.render().as_bytes())?;
     Ok(())
 }
 
 /// Converts the `Graph` into some GRAPHML tags.
-fn write_graph<G, V>(
+fn write_graph<G, V, W>(
     g: &G,
     annotator: &KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
-    out: &mut File,
+    out: &mut W,
 ) -> Result<(), ExportError>
 where
     G: Graph,
@@ -250,7 +261,9 @@ where
     <G::Node as GraphObject>::Data: Clone + Into<NodeType> + Into<Rank<f64>>,
     <G::Node as GraphObject>::Id: IntoGraphMlXml + Clone + TryInto<String> + Eq + Hash,
     <G::Edge as GraphObject>::Id: IntoGraphMlXml + Clone,
+    <G::Edge as GraphObject>::Data: Clone + Into<EdgeType>,
     <G as Graph>::Weight: IntoGraphMlXml + Zero,
+    W: Write,
 {
     let mut all_edges = Vec::new();
 
@@ -272,16 +285,16 @@ where
     Ok(())
 }
 
-/// Exports a graph `G` to a `.graphml` file.
+/// Exports a graph `G` into GraphML, written into `out`.
 ///
-/// This file can then be imported into one of the many graph visualisers,
+/// The output can then be imported into one of the many graph visualisers,
 /// like [Gephi](https://gephi.org/).
 /// For a more exhaustive explanation of GraphML, refers to the
 /// [official documentation](http://graphml.graphdrawing.org/).
-pub fn export_graph_impl<G, V>(
+fn export_graph_impl<G, V, W>(
     g: &G,
     annotator: &KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
-    out: &Path,
+    out: &mut W,
 ) -> Result<(), ExportError>
 where
     G: Graph,
@@ -289,57 +302,82 @@ where
     <G::Node as GraphObject>::Data: Clone + Into<NodeType> + Into<Rank<f64>>,
     <G::Node as GraphObject>::Id: IntoGraphMlXml + Clone + TryInto<String> + Eq + Hash,
     <G::Edge as GraphObject>::Id: IntoGraphMlXml + Clone,
+    <G::Edge as GraphObject>::Data: Clone + Into<EdgeType>,
     <G as Graph>::Weight: IntoGraphMlXml + Zero,
+    W: Write,
 {
-    let mut out_file = OpenOptions::new().write(true).create_new(true).open(out)?;
-
-    out_file.write_all(GRAPHML_META.as_bytes())?;
-    write_graph(g, annotator, &mut out_file)?;
-    out_file.write_all(GRAPHML_FOOTER.as_bytes())?;
+    out.write_all(GRAPHML_META.as_bytes())?;
+    write_graph(g, annotator, out)?;
+    out.write_all(GRAPHML_FOOTER.as_bytes())?;
+    out.flush()?;
 
     Ok(())
 }
 
-pub struct GraphMlExporter<'a, G, V>
+/// Exports a graph `G` to the `.graphml` file at `path`, following `policy`
+/// if the file already exists.
+/// `WritePolicy::Append` is rejected, as the file holds a single document.
+pub fn export_graph_to_file<G, V, P>(
+    g: &G,
+    annotator: &KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
+    path: P,
+    policy: WritePolicy,
+) -> Result<(), ExportError>
+where
+    G: Graph,
+    V: Into<Rank<f64>> + Zero + Clone,
+    <G::Node as GraphObject>::Data: Clone + Into<NodeType> + Into<Rank<f64>>,
+    <G::Node as GraphObject>::Id: IntoGraphMlXml + Clone + TryInto<String> + Eq + Hash,
+    <G::Edge as GraphObject>::Id: IntoGraphMlXml + Clone,
+    <G::Edge as GraphObject>::Data: Clone + Into<EdgeType>,
+    <G as Graph>::Weight: IntoGraphMlXml + Zero,
+    P: AsRef<Path>,
+{
+    let mut out = BufWriter::new(policy.open_document(path)?);
+    export_graph_impl(g, annotator, &mut out)
+}
+
+pub struct GraphMlExporter<'a, G, V, W>
 where
     G: Graph,
 {
     graph: &'a G,
     annotator: &'a KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
-    out_path: &'a str,
+    out: W,
 }
 
-impl<'a, G, V> GraphMlExporter<'a, G, V>
+impl<'a, G, V, W> GraphMlExporter<'a, G, V, W>
 where
     G: Graph,
 {
     pub fn new(
         graph: &'a G,
         annotator: &'a KeyValueAnnotator<<G::Node as GraphObject>::Id, V>,
-        out_path: &'a str,
+        out: W,
     ) -> Self {
         GraphMlExporter {
             graph,
             annotator,
-            out_path,
+            out,
         }
     }
 }
 
-impl<'a, G, V> Exporter for GraphMlExporter<'a, G, V>
+impl<'a, G, V, W> Exporter for GraphMlExporter<'a, G, V, W>
 where
     G: Graph,
     V: Into<Rank<f64>> + Zero + Clone,
     <G::Node as GraphObject>::Data: Clone + Into<NodeType> + Into<Rank<f64>>,
     <G::Node as GraphObject>::Id: IntoGraphMlXml + Clone + TryInto<String> + Eq + Hash,
     <G::Edge as GraphObject>::Id: IntoGraphMlXml + Clone,
+    <G::Edge as GraphObject>::Data: Clone + Into<EdgeType>,
     <G as Graph>::Weight: IntoGraphMlXml + Zero,
+    W: Write,
 {
-    type ExporterOutput = ();
+    type ExporterOutput = W;
     type ExporterError = ExportError;
-    fn export(self) -> Result<Self::ExporterOutput, Self::ExporterError> {
-        let pth = self.out_path.to_owned() + ".graphml";
-        let out_with_ext = Path::new(&pth);
-        export_graph_impl(self.graph, self.annotator, &out_with_ext)
+    fn export(mut self) -> Result<Self::ExporterOutput, Self::ExporterError> {
+        export_graph_impl(self.graph, self.annotator, &mut self.out)?;
+        Ok(self.out)
     }
 }
@@ -1,15 +1,91 @@
 /// Exports the rank of a graph into CSV.
 pub mod csv;
+/// Exports a Graph into DOT, the Graphviz language.
 pub mod dot;
 /// Exports a Graph into GEXF (Gephi Exchange Format).
 pub mod gexf;
 /// Exports a Graph into GraphML.
 pub mod graphml;
+/// Exports the ranks of a graph, alongside their metadata, into CSV or JSON.
+pub mod report;
 
-use crate::types::network::ArtifactType;
+use crate::types::network::{ArtifactType, DependencyType};
 use crate::types::Osrank;
+use core::fmt;
 use fraction::ToPrimitive;
+use std::fs::{File, OpenOptions};
 use std::marker::PhantomData;
+use std::path::Path;
+
+/// Errors arising while exporting a graph or its ranks.
+#[derive(Debug)]
+pub enum ExportError {
+    /// Returned in case of generic I/O error.
+    IOError(std::io::Error),
+
+    /// Returned when appending an export which is a single document, like a
+    /// GEXF, GraphML or DOT graph, as the result wouldn't be well-formed.
+    AppendNotSupported,
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::IOError(e) => write!(f, "i/o error when writing the export {}", e),
+            ExportError::AppendNotSupported => write!(
+                f,
+                "the export is a single document, which can't be appended to an existing file"
+            ),
+        }
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(err: std::io::Error) -> ExportError {
+        ExportError::IOError(err)
+    }
+}
+
+/// What to do when the file to export into already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WritePolicy {
+    /// Fail, leaving the existing file untouched.
+    CreateNew,
+    /// Truncate the existing file, and write it from scratch.
+    Overwrite,
+    /// Write at the end of the existing file. Only row-oriented exports,
+    /// like CSV and JSON Lines, can be appended to.
+    Append,
+}
+
+impl Default for WritePolicy {
+    fn default() -> Self {
+        WritePolicy::CreateNew
+    }
+}
+
+impl WritePolicy {
+    /// Opens the file at `path` for writing, creating it if it doesn't exist
+    /// and following the policy if it does.
+    pub fn open<P: AsRef<Path>>(self, path: P) -> Result<File, ExportError> {
+        let mut options = OpenOptions::new();
+        match self {
+            WritePolicy::CreateNew => options.write(true).create_new(true),
+            WritePolicy::Overwrite => options.write(true).create(true).truncate(true),
+            WritePolicy::Append => options.append(true).create(true),
+        };
+        Ok(options.open(path)?)
+    }
+
+    /// Like `open`, but for exports which are a single document, which
+    /// reject `WritePolicy::Append`.
+    pub fn open_document<P: AsRef<Path>>(self, path: P) -> Result<File, ExportError> {
+        match self {
+            WritePolicy::Append => Err(ExportError::AppendNotSupported),
+            _ => self.open(path),
+        }
+    }
+}
 
 pub trait Exporter {
     type ExporterOutput;
@@ -49,6 +125,40 @@ pub enum NodeType {
     Account,
 }
 
+impl NodeType {
+    /// The name of the node type, as written by the exporters.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeType::Project => "project",
+            NodeType::Account => "account",
+        }
+    }
+}
+
+/// The type of an edge, so that it survives an export.
+pub enum EdgeType {
+    Contrib,
+    ContribPrime,
+    Maintain,
+    MaintainPrime,
+    Depend,
+    Influence,
+}
+
+impl EdgeType {
+    /// The name of the edge type, as written by the exporters.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EdgeType::Contrib => "contrib",
+            EdgeType::ContribPrime => "contrib*",
+            EdgeType::Maintain => "maintain",
+            EdgeType::MaintainPrime => "maintain*",
+            EdgeType::Depend => "depend",
+            EdgeType::Influence => "influence",
+        }
+    }
+}
+
 impl std::convert::From<NodeType> for RgbColor {
     fn from(f: NodeType) -> Self {
         match f {
@@ -77,6 +187,22 @@ impl std::convert::From<ArtifactType> for NodeType {
     }
 }
 
+impl<W> std::convert::From<DependencyType<W>> for EdgeType
+where
+    W: Clone,
+{
+    fn from(dtype: DependencyType<W>) -> Self {
+        match dtype {
+            DependencyType::Contrib(_) => EdgeType::Contrib,
+            DependencyType::ContribPrime(_) => EdgeType::ContribPrime,
+            DependencyType::Maintain(_) => EdgeType::Maintain,
+            DependencyType::MaintainPrime(_) => EdgeType::MaintainPrime,
+            DependencyType::Depend(_) => EdgeType::Depend,
+            DependencyType::Influence(_) => EdgeType::Influence,
+        }
+    }
+}
+
 impl std::convert::From<ArtifactType> for Rank<f64> {
     fn from(atype: ArtifactType) -> Self {
         Rank {
@@ -94,3 +220,42 @@ impl std::convert::From<Osrank> for Rank<f64> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn write_policies_handle_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ranks.csv");
+
+        let write = |policy: WritePolicy, contents: &str| -> Result<(), ExportError> {
+            policy.open(&path)?.write_all(contents.as_bytes())?;
+            Ok(())
+        };
+
+        write(WritePolicy::CreateNew, "foo\n").unwrap();
+        match write(WritePolicy::CreateNew, "bar\n") {
+            Err(ExportError::IOError(e)) => assert_eq!(e.kind(), std::io::ErrorKind::AlreadyExists),
+            Ok(()) => panic!("overwrote an existing file"),
+            Err(e) => panic!("unexpected error {}", e),
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "foo\n");
+
+        write(WritePolicy::Append, "bar\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "foo\nbar\n");
+
+        write(WritePolicy::Overwrite, "baz\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "baz\n");
+
+        match WritePolicy::Append.open_document(&path) {
+            Err(ExportError::AppendNotSupported) => {}
+            _ => panic!("appended a document to an existing file"),
+        }
+        assert!(WritePolicy::Overwrite.open_document(&path).is_ok());
+    }
+}
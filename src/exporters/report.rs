@@ -0,0 +1,304 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
+extern crate csv;
+extern crate serde;
+extern crate serde_json;
+
+use super::{ExportError, Exporter, NodeType};
+use crate::types::mock::KeyValueAnnotator;
+use crate::types::RankEstimate;
+use fraction::ToPrimitive;
+use oscoin_graph_api::{Direction, Graph, GraphObject};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::io::{self, Write};
+
+/// The format of a rank report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// A CSV file, with a header row.
+    Csv,
+    /// A JSON array, with an object per row.
+    Json,
+    /// A JSON object per line.
+    JsonLines,
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            "jsonl" => Ok(ReportFormat::JsonLines),
+            _ => Err(format!("unknown report format {} (csv|json|jsonl)", s)),
+        }
+    }
+}
+
+/// A row of a rank report, i.e. a node alongside its rank and the metadata
+/// needed to make sense of it. Rows are sorted by rank, from the highest to
+/// the lowest, and `position` starts from 1.
+///
+/// `visits` is the raw number of random walks visits the rank is estimated
+/// from, if the algorithm is a Monte Carlo one, whereas `platform` is only
+/// known for projects, and only if the report is given the platforms.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportRow {
+    pub position: usize,
+    pub id: String,
+    pub node_type: &'static str,
+    pub rank: f64,
+    pub visits: Option<usize>,
+    pub in_degree: usize,
+    pub out_degree: usize,
+    pub platform: Option<String>,
+}
+
+/// Builds the rows of the rank report of the input graph. Nodes with the
+/// same rank are sorted by id, so that the report is deterministic.
+pub fn report_rows<G>(
+    g: &G,
+    annotator: &KeyValueAnnotator<<G::Node as GraphObject>::Id, RankEstimate>,
+    platforms: &HashMap<<G::Node as GraphObject>::Id, String>,
+) -> Vec<ReportRow>
+where
+    G: Graph,
+    <G::Node as GraphObject>::Id: Display + Eq + Hash,
+    <G::Node as GraphObject>::Data: Clone + Into<NodeType>,
+{
+    let mut rows: Vec<ReportRow> = g
+        .nodes()
+        .map(|node| {
+            let estimate = annotator.annotator.get(node.id());
+            let node_type: NodeType = node.data().clone().into();
+            ReportRow {
+                position: 0,
+                id: node.id().to_string(),
+                node_type: node_type.as_str(),
+                rank: estimate.and_then(|e| e.rank.to_f64()).unwrap_or(0.0),
+                visits: estimate.and_then(|e| e.visits),
+                in_degree: g.edges_directed(node.id(), Direction::Incoming).len(),
+                out_degree: g.edges_directed(node.id(), Direction::Outgoing).len(),
+                platform: platforms.get(node.id()).cloned(),
+            }
+        })
+        .collect();
+
+    rows.sort_by(|r1, r2| {
+        r2.rank
+            .partial_cmp(&r1.rank)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| r1.id.cmp(&r2.id))
+    });
+    for (ix, row) in rows.iter_mut().enumerate() {
+        row.position = ix + 1;
+    }
+
+    rows
+}
+
+/// Writes the input rows into `out`, in the given format. The CSV header row
+/// is only written if `headers` is `true`, e.g. so that it's not repeated
+/// when appending to an existing report.
+pub fn write_report<W>(
+    rows: &[ReportRow],
+    format: ReportFormat,
+    headers: bool,
+    mut out: W,
+) -> Result<(), ExportError>
+where
+    W: Write,
+{
+    match format {
+        ReportFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(headers)
+                .from_writer(&mut out);
+            for row in rows {
+                writer.serialize(row).map_err(io::Error::from)?;
+            }
+            writer.flush()?;
+        }
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, rows).map_err(io::Error::from)?;
+            out.write_all(b"\n")?;
+        }
+        ReportFormat::JsonLines => {
+            for row in rows {
+                serde_json::to_writer(&mut out, row).map_err(io::Error::from)?;
+                out.write_all(b"\n")?;
+            }
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+/// Exports the rank report of a graph `G` into `out`.
+pub struct RankReportExporter<'a, G, W>
+where
+    G: Graph,
+{
+    graph: &'a G,
+    annotator: &'a KeyValueAnnotator<<G::Node as GraphObject>::Id, RankEstimate>,
+    platforms: &'a HashMap<<G::Node as GraphObject>::Id, String>,
+    format: ReportFormat,
+    headers: bool,
+    out: W,
+}
+
+impl<'a, G, W> RankReportExporter<'a, G, W>
+where
+    G: Graph,
+{
+    pub fn new(
+        graph: &'a G,
+        annotator: &'a KeyValueAnnotator<<G::Node as GraphObject>::Id, RankEstimate>,
+        platforms: &'a HashMap<<G::Node as GraphObject>::Id, String>,
+        format: ReportFormat,
+        out: W,
+    ) -> Self {
+        RankReportExporter {
+            graph,
+            annotator,
+            platforms,
+            format,
+            headers: true,
+            out,
+        }
+    }
+
+    /// Whether to write the CSV header row, which is the default.
+    pub fn headers(mut self, headers: bool) -> Self {
+        self.headers = headers;
+        self
+    }
+}
+
+impl<'a, G, W> Exporter for RankReportExporter<'a, G, W>
+where
+    G: Graph,
+    <G::Node as GraphObject>::Id: Display + Eq + Hash,
+    <G::Node as GraphObject>::Data: Clone + Into<NodeType>,
+    W: Write,
+{
+    type ExporterOutput = W;
+    type ExporterError = ExportError;
+    fn export(mut self) -> Result<Self::ExporterOutput, Self::ExporterError> {
+        let rows = report_rows(self.graph, self.annotator, self.platforms);
+        write_report(&rows, self.format, self.headers, &mut self.out)?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::types::mock::MockNetwork;
+    use crate::types::network::{ArtifactType, DependencyType};
+    use crate::types::Osrank;
+    use num_traits::Zero;
+    use oscoin_graph_api::GraphWriter;
+
+    fn export(format: ReportFormat) -> String {
+        export_with_headers(format, true)
+    }
+
+    fn export_with_headers(format: ReportFormat, headers: bool) -> String {
+        let mut network = MockNetwork::default();
+        let mut annotator = KeyValueAnnotator {
+            annotator: Default::default(),
+        };
+
+        let osrank = Osrank::zero();
+        network.add_node("foo".to_string(), ArtifactType::Project { osrank });
+        network.add_node("bar".to_string(), ArtifactType::Project { osrank });
+        network.add_node("github@tom".to_string(), ArtifactType::Account { osrank });
+        for (id, from, to) in &[
+            (0, "foo", "bar"),
+            (1, "github@tom", "foo"),
+            (2, "github@tom", "bar"),
+        ] {
+            let from = from.to_string();
+            let to = to.to_string();
+            network.add_edge(*id, &from, &to, 0.5, DependencyType::Influence(0.5));
+        }
+
+        for (id, rank, visits) in &[
+            ("foo", 1, Some(10)),
+            ("bar", 3, Some(30)),
+            ("github@tom", 0, None),
+        ] {
+            let estimate = RankEstimate {
+                rank: Osrank::new(*rank as u64, 4u64),
+                std_error: 0.0,
+                visits: *visits,
+            };
+            annotator.annotator.insert(id.to_string(), estimate);
+        }
+
+        let platforms = vec![
+            ("foo".to_string(), "Cargo".to_string()),
+            ("bar".to_string(), "Cargo".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        let out = RankReportExporter::new(&network, &annotator, &platforms, format, Vec::new())
+            .headers(headers)
+            .export()
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn rank_report_csv_has_a_header_and_sorted_rows() {
+        assert_eq!(
+            export(ReportFormat::Csv),
+            "position,id,node_type,rank,visits,in_degree,out_degree,platform\n\
+             1,bar,project,0.75,30,2,0,Cargo\n\
+             2,foo,project,0.25,10,1,1,Cargo\n\
+             3,github@tom,account,0.0,,0,2,\n"
+        );
+    }
+
+    #[test]
+    fn rank_report_csv_can_skip_the_header() {
+        let with_header = export(ReportFormat::Csv);
+        let (_, rows) = with_header.split_at(with_header.find('\n').unwrap() + 1);
+        assert_eq!(export_with_headers(ReportFormat::Csv, false), rows);
+    }
+
+    #[test]
+    fn rank_report_json_variants_agree() {
+        let array: Vec<serde_json::Value> =
+            serde_json::from_str(&export(ReportFormat::Json)).unwrap();
+        let lines: Vec<serde_json::Value> = export(ReportFormat::JsonLines)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(array, lines);
+        assert_eq!(
+            lines[2],
+            serde_json::json!({
+                "position": 3,
+                "id": "github@tom",
+                "node_type": "account",
+                "rank": 0.0,
+                "visits": null,
+                "in_degree": 0,
+                "out_degree": 2,
+                "platform": null
+            })
+        );
+    }
+}
@@ -12,11 +12,13 @@ use crate::adjacency::new_network_matrix;
 use crate::algorithm::Normalised;
 use crate::linalg::{DenseMatrix, SparseMatrix};
 use crate::protocol_traits::ledger::LedgerView;
+use crate::types::compact::CompactNetwork;
 use crate::types::network::{Artifact, ArtifactType, Dependency, DependencyType};
 use crate::types::Weight;
 use core::fmt;
 use num_traits::{Num, One, Zero};
-use oscoin_graph_api::{Graph, GraphWriter};
+use oscoin_graph_api::{Graph, GraphObject, GraphWriter};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use sprs::{CsMat, TriMat, TriMatBase};
 use std::collections::{HashMap, HashSet};
@@ -105,6 +107,14 @@ pub struct ContribRow {
     pub project_name: ProjectName,
 }
 
+/// A single, deserialised row of the `{platform}_maintainers.csv` file.
+#[derive(Debug, Deserialize)]
+pub struct MaintainerRow {
+    pub project_id: ProjectId,
+    pub maintainer: Contributor,
+    pub project_name: ProjectName,
+}
+
 /// A single, deserialised row of the `{platform}_dependencies.csv` file.
 #[derive(Debug, Deserialize)]
 pub struct DepRow {
@@ -124,6 +134,10 @@ pub enum CsvImportError {
 
     /// Returned when the CSV deserialisation failed.
     CsvDeserialisationError(csv::Error),
+
+    /// Returned when importing in `ImportMode::Strict` and one of the rows
+    /// is malformed or inconsistent with the others.
+    InvalidRow(ImportIssue),
 }
 
 impl fmt::Display for CsvImportError {
@@ -135,6 +149,7 @@ impl fmt::Display for CsvImportError {
             CsvImportError::CsvDeserialisationError(e) => {
                 write!(f, "Deserialisation failed on a CSV row {}", e)
             }
+            CsvImportError::InvalidRow(issue) => write!(f, "Invalid CSV row: {}", issue),
         }
     }
 }
@@ -151,6 +166,240 @@ impl From<csv::Error> for CsvImportError {
     }
 }
 
+//
+// Import reports
+//
+
+/// The CSV files an import reads from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvFile {
+    Dependencies,
+    DependenciesMetadata,
+    Contributions,
+    Maintainers,
+    Projects,
+}
+
+impl fmt::Display for CsvFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CsvFile::Dependencies => write!(f, "dependencies"),
+            CsvFile::DependenciesMetadata => write!(f, "dependencies metadata"),
+            CsvFile::Contributions => write!(f, "contributions"),
+            CsvFile::Maintainers => write!(f, "maintainers"),
+            CsvFile::Projects => write!(f, "projects"),
+        }
+    }
+}
+
+/// A row which is malformed, or inconsistent with the rest of the input.
+/// Line numbers start from 1, and include the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportIssue {
+    /// The row couldn't be parsed. It is skipped.
+    MalformedRow {
+        file: CsvFile,
+        line: u64,
+        reason: String,
+    },
+    /// A dependency refers to a project missing from the metadata. It is
+    /// skipped.
+    DanglingDependency {
+        line: u64,
+        from: ProjectId,
+        to: ProjectId,
+    },
+    /// A dependency which already appeared on a previous line. It is kept,
+    /// which makes the edge heavier.
+    DuplicateDependency {
+        line: u64,
+        from: ProjectId,
+        to: ProjectId,
+    },
+    /// A project depending on itself. It is kept.
+    SelfDependency { line: u64, project: ProjectId },
+    /// A contribution to a project missing from the metadata. It is skipped,
+    /// but the contributor still becomes an account.
+    UnknownProjectContribution {
+        line: u64,
+        project: ProjectId,
+        contributor: Contributor,
+    },
+    /// A maintainer of a project missing from the metadata. It is skipped,
+    /// but the maintainer still becomes an account.
+    UnknownProjectMaintainer {
+        line: u64,
+        project: ProjectId,
+        maintainer: Contributor,
+    },
+}
+
+impl fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImportIssue::MalformedRow { file, line, reason } => write!(
+                f,
+                "malformed row on line {} of the {} file: {}",
+                line, file, reason
+            ),
+            ImportIssue::DanglingDependency { line, from, to } => write!(
+                f,
+                "dependency {} -> {} on line {} refers to an unknown project",
+                from, to, line
+            ),
+            ImportIssue::DuplicateDependency { line, from, to } => write!(
+                f,
+                "dependency {} -> {} on line {} is a duplicate",
+                from, to, line
+            ),
+            ImportIssue::SelfDependency { line, project } => {
+                write!(f, "project {} depends on itself on line {}", project, line)
+            }
+            ImportIssue::UnknownProjectContribution {
+                line,
+                project,
+                contributor,
+            } => write!(
+                f,
+                "{} contributes to the unknown project {} on line {}",
+                contributor, project, line
+            ),
+            ImportIssue::UnknownProjectMaintainer {
+                line,
+                project,
+                maintainer,
+            } => write!(
+                f,
+                "{} maintains the unknown project {} on line {}",
+                maintainer, project, line
+            ),
+        }
+    }
+}
+
+/// How an import reacts to an `ImportIssue`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Records the issue in the `ImportReport`, and carries on.
+    Lenient,
+    /// Fails with `CsvImportError::InvalidRow`.
+    Strict,
+}
+
+impl Default for ImportMode {
+    fn default() -> Self {
+        ImportMode::Lenient
+    }
+}
+
+/// All the issues found while importing a network, which tells how much of
+/// the input didn't make it (as it is) into the network.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub mode: ImportMode,
+    pub issues: Vec<ImportIssue>,
+}
+
+impl ImportReport {
+    pub fn new(mode: ImportMode) -> Self {
+        ImportReport {
+            mode,
+            issues: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the input had no issues at all.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Records the input issue, or fails with it in `ImportMode::Strict`.
+    pub fn record(&mut self, issue: ImportIssue) -> Result<(), CsvImportError> {
+        match self.mode {
+            ImportMode::Lenient => {
+                self.issues.push(issue);
+                Ok(())
+            }
+            ImportMode::Strict => Err(CsvImportError::InvalidRow(issue)),
+        }
+    }
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut counts = [0; 6];
+        for issue in &self.issues {
+            let ix = match issue {
+                ImportIssue::MalformedRow { .. } => 0,
+                ImportIssue::DanglingDependency { .. } => 1,
+                ImportIssue::DuplicateDependency { .. } => 2,
+                ImportIssue::SelfDependency { .. } => 3,
+                ImportIssue::UnknownProjectContribution { .. } => 4,
+                ImportIssue::UnknownProjectMaintainer { .. } => 5,
+            };
+            counts[ix] += 1;
+        }
+
+        write!(
+            f,
+            "{} malformed rows, {} dangling dependencies, {} duplicate dependencies, \
+             {} self-dependencies, {} contributions to unknown projects, \
+             {} maintainers of unknown projects",
+            counts[0], counts[1], counts[2], counts[3], counts[4], counts[5]
+        )
+    }
+}
+
+/// Reads and deserialises all the rows of the input CSV file, alongside
+/// their line number. Rows which can't be read or deserialised are recorded
+/// in the `ImportReport` as malformed, and skipped.
+pub fn read_rows<T, R>(
+    csv: csv::Reader<R>,
+    file: CsvFile,
+    report: &mut ImportReport,
+) -> Result<Vec<(u64, T)>, CsvImportError>
+where
+    T: DeserializeOwned,
+    R: Read,
+{
+    let mut rows = Vec::new();
+
+    for result in csv.into_records() {
+        let parsed = result.and_then(|record| {
+            let line = record.position().map_or(0, |p| p.line());
+            record.deserialize(None).map(|row| (line, row))
+        });
+        match parsed {
+            Ok(row) => rows.push(row),
+            Err(e) => report.record(ImportIssue::MalformedRow {
+                file,
+                line: e.position().map_or(0, |p| p.line()),
+                reason: e.to_string(),
+            })?,
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Reads the platform of every project out of the
+/// `{platform}_dependencies_meta.csv` file, keyed by the project name, which
+/// is the id of the project in the imported network.
+pub fn import_platforms<R>(
+    deps_meta_csv: csv::Reader<R>,
+    report: &mut ImportReport,
+) -> Result<HashMap<ProjectName, String>, CsvImportError>
+where
+    R: Read,
+{
+    Ok(
+        read_rows::<DepMetaRow, _>(deps_meta_csv, CsvFile::DependenciesMetadata, report)?
+            .into_iter()
+            .map(|(_, row)| (row.name, row.platform))
+            .collect(),
+    )
+}
+
 //
 // Utility Traits
 //
@@ -242,6 +491,19 @@ where
 /// [..]
 /// ```
 ///
+/// #maintainers_csv_file
+/// This is optional, and if given it must be a csv file in this format:
+///
+/// ```ignore,no_run
+/// ID,MAINTAINER,NAME
+/// 30742,github@aepsil0n,acacia
+/// 30745,github@reem,aio
+/// [..]
+/// ```
+///
+/// Maintainers who are not among the contributors are added to the network
+/// as accounts. Rows referring to unknown projects are skipped.
+///
 /// # Using the import_network to load the Cargo ecosystem
 ///
 /// To use the `import_network` to import a `Network` relative to the *whole*
@@ -253,7 +515,7 @@ where
 /// use osrank::types::network::Network;
 /// use std::fs::File;
 ///
-/// type MockNetwork = Network<f64>;
+/// type MockNetwork = Network<String, usize, f64>;
 ///
 /// let mut deps_csv_file = File::open("data/cargo_dependencies.csv").unwrap();
 /// let mut deps_meta_csv_file = File::open("data/cargo_dependencies_meta.csv").unwrap();
@@ -270,10 +532,48 @@ where
 pub fn import_network<G, L, R>(
     deps_csv: csv::Reader<R>,
     deps_meta_csv: csv::Reader<R>,
-    mut contribs_csv: csv::Reader<R>,
-    //TODO(and) We want to consider maintainers at some point.
-    _maintainers_csv_file: Option<csv::Reader<R>>,
+    contribs_csv: csv::Reader<R>,
+    maintainers_csv: Option<csv::Reader<R>>,
+    ledger_view: &L,
+) -> Result<Normalised<G>, CsvImportError>
+where
+    L: LedgerView,
+    R: Read,
+    G: Graph<
+            Node = Artifact<String>,
+            Edge = Dependency<usize, f64>,
+            Weight = f64,
+            NodeData = ArtifactType,
+            EdgeData = DependencyType<f64>,
+        > + GraphWriter,
+{
+    let mut report = ImportReport::new(ImportMode::Lenient);
+    let network = import_network_with_report(
+        deps_csv,
+        deps_meta_csv,
+        contribs_csv,
+        maintainers_csv,
+        ledger_view,
+        &mut report,
+    )?;
+
+    if !report.is_clean() {
+        warn!("Imported the network with some invalid rows: {}", report);
+    }
+
+    Ok(network)
+}
+
+/// Like `import_network`, but records all the malformed or inconsistent
+/// rows into the input `ImportReport`, which in `ImportMode::Strict` turns
+/// the first of them into a `CsvImportError::InvalidRow` instead.
+pub fn import_network_with_report<G, L, R>(
+    deps_csv: csv::Reader<R>,
+    deps_meta_csv: csv::Reader<R>,
+    contribs_csv: csv::Reader<R>,
+    maintainers_csv: Option<csv::Reader<R>>,
     ledger_view: &L,
+    report: &mut ImportReport,
 ) -> Result<Normalised<G>, CsvImportError>
 where
     L: LedgerView,
@@ -285,46 +585,126 @@ where
             NodeData = ArtifactType,
             EdgeData = DependencyType<f64>,
         > + GraphWriter,
+{
+    let (nodes, network_matrix) = import_network_matrix(
+        deps_csv,
+        deps_meta_csv,
+        contribs_csv,
+        maintainers_csv,
+        ledger_view,
+        report,
+    )?;
+
+    let mut graph = G::default();
+
+    for node in &nodes {
+        graph.add_node(node.id().clone(), node.data().clone());
+    }
+
+    debug!("Added all the projects and accounts as nodes to the graph..");
+
+    let mut current_edge_id = 0;
+
+    //FIXME(adn) Here we have a precision problem: we _have_ to convert the
+    //weights from fractions to f64 to avoid arithmetic overflows, but yet here
+    //it's nice to work with fractions.
+    for (&weight, (source, target)) in network_matrix.iter() {
+        graph.add_edge(
+            current_edge_id,
+            nodes[source].id(),
+            nodes[target].id(),
+            weight,
+            DependencyType::Influence(weight),
+        );
+        current_edge_id += 1;
+    }
+
+    // Build a graph out of the matrix.
+    Ok(Normalised::new(graph))
+}
+
+/// Like `import_network_with_report`, but builds a `CompactNetwork` straight
+/// out of the network matrix, without going through a `GraphWriter`. The two
+/// networks have the same nodes and edges, with the same ids.
+pub fn import_compact_network<L, R>(
+    deps_csv: csv::Reader<R>,
+    deps_meta_csv: csv::Reader<R>,
+    contribs_csv: csv::Reader<R>,
+    maintainers_csv: Option<csv::Reader<R>>,
+    ledger_view: &L,
+    report: &mut ImportReport,
+) -> Result<Normalised<CompactNetwork<f64>>, CsvImportError>
+where
+    L: LedgerView,
+    R: Read,
+{
+    let (nodes, network_matrix) = import_network_matrix(
+        deps_csv,
+        deps_meta_csv,
+        contribs_csv,
+        maintainers_csv,
+        ledger_view,
+        report,
+    )?;
+
+    Ok(Normalised::new(CompactNetwork::from_adjacency_matrix(
+        nodes,
+        &network_matrix,
+    )))
+}
+
+/// Reads the CSV files and returns all the projects & accounts, alongside
+/// the network matrix, whose i-th row and column correspond to the i-th
+/// returned node.
+fn import_network_matrix<L, R>(
+    deps_csv: csv::Reader<R>,
+    deps_meta_csv: csv::Reader<R>,
+    contribs_csv: csv::Reader<R>,
+    maintainers_csv: Option<csv::Reader<R>>,
+    ledger_view: &L,
+    report: &mut ImportReport,
+) -> Result<(Vec<Artifact<String>>, SparseMatrix<f64>), CsvImportError>
+where
+    L: LedgerView,
+    R: Read,
 {
     debug!("Starting to import a Graph from the CSV files...");
 
     let mut deps_meta = DependenciesMetadata::new();
     let mut contribs_meta = ContributionsMetadata::new();
 
-    let mut graph = G::default();
-
-    // Stores the global mapping between matrix indexes and node names,
-    // which includes projects & accounts..
-    let mut index2id: HashMap<usize, String> = HashMap::default();
+    // Stores the global mapping between matrix indexes and nodes, which
+    // includes projects & accounts.
+    let mut nodes: Vec<Artifact<String>> = Vec::new();
 
     // Iterate once over the dependencies metadata and store the name and id.
     // We need to maintain some sort of mapping between the order of visit
     // (which will be used as the index in the matrix) and the project id.
-    for result in deps_meta_csv.into_records().filter_map(|e| e.ok()) {
-        let row: DepMetaRow = result.deserialize(None)?;
+    for (_, row) in
+        read_rows::<DepMetaRow, _>(deps_meta_csv, CsvFile::DependenciesMetadata, report)?
+    {
         let prj_id = row.name.clone();
         deps_meta.ids.insert(row.id);
         deps_meta.labels.push(row.name);
         deps_meta
             .project2index
             .insert(row.id, deps_meta.ids.len() - 1);
-        index2id.insert(index2id.len(), prj_id.clone());
-
-        // Add the projects as nodes in the graph.
-        graph.add_node(
-            prj_id,
-            ArtifactType::Project {
-                osrank: Zero::zero(),
-            },
-        );
+        nodes.push(Artifact::new_project(prj_id));
     }
 
-    debug!("Added all the projects as nodes to the graph..");
+    debug!("Collected all the projects..");
 
     // Iterate once over the contributions and build a matrix where
     // rows are the project names and columns the (unique) contributors.
-    for result in contribs_csv.records().filter_map(|e| e.ok()) {
-        let row: ContribRow = result.deserialize(None)?;
+    for (line, row) in read_rows::<ContribRow, _>(contribs_csv, CsvFile::Contributions, report)? {
+        if !deps_meta.project2index.contains_key(&row.project_id) {
+            report.record(ImportIssue::UnknownProjectContribution {
+                line,
+                project: row.project_id,
+                contributor: row.contributor.clone(),
+            })?;
+        }
+
         let contributor = Rc::new(row.contributor.clone());
 
         if contribs_meta.contributors.get(&row.contributor).is_none() {
@@ -334,22 +714,46 @@ where
                 contribs_meta.contributors.len() - 1,
             );
 
-            index2id.insert(index2id.len(), Rc::clone(&contributor).to_string());
-
-            graph.add_node(
-                contributor.to_string(),
-                ArtifactType::Account {
-                    osrank: Zero::zero(),
-                },
-            );
+            nodes.push(Artifact::new_account(contributor.to_string()));
         }
 
         contribs_meta.rows.push(row)
     }
 
-    debug!("Added all the contributions as nodes to the graph..");
+    debug!("Collected all the contributors..");
 
-    let dep_adj_matrix = new_dependency_adjacency_matrix(&deps_meta, deps_csv)?;
+    let mut maintainers: Vec<MaintainerRow> = Vec::new();
+
+    if let Some(maintainers_csv) = maintainers_csv {
+        for (line, row) in
+            read_rows::<MaintainerRow, _>(maintainers_csv, CsvFile::Maintainers, report)?
+        {
+            if !deps_meta.project2index.contains_key(&row.project_id) {
+                report.record(ImportIssue::UnknownProjectMaintainer {
+                    line,
+                    project: row.project_id,
+                    maintainer: row.maintainer.clone(),
+                })?;
+            }
+
+            let maintainer = Rc::new(row.maintainer.clone());
+
+            if contribs_meta.contributors.get(&row.maintainer).is_none() {
+                contribs_meta.contributors.insert(Rc::clone(&maintainer));
+                contribs_meta
+                    .contributor2index
+                    .insert(Rc::clone(&maintainer), contribs_meta.contributors.len() - 1);
+
+                nodes.push(Artifact::new_account(maintainer.to_string()));
+            }
+
+            maintainers.push(row)
+        }
+
+        debug!("Collected all the maintainers..");
+    }
+
+    let dep_adj_matrix = new_dependency_adjacency_matrix(&deps_meta, deps_csv, report)?;
 
     debug!("Generated dep_adj_matrix...");
 
@@ -358,8 +762,10 @@ where
 
     debug!("Generated con_adj_matrix...");
 
-    //FIXME(adn) For now the maintenance matrix is empty.
-    let maintainers_matrix = CsMat::zero((dep_adj_matrix.rows(), con_adj_matrix.cols()));
+    let maintainers_matrix =
+        new_maintenance_adjacency_matrix(&deps_meta, &contribs_meta, &maintainers)?;
+
+    debug!("Generated maintainers_matrix...");
 
     let network_matrix = new_network_matrix(
         &dep_adj_matrix,
@@ -370,47 +776,60 @@ where
 
     debug!("Generated the full graph adjacency matrix...");
 
-    let mut current_edge_id = 0;
-
-    //FIXME(adn) Here we have a precision problem: we _have_ to convert the
-    //weights from fractions to f64 to avoid arithmetic overflows, but yet here
-    //it's nice to work with fractions.
-    for (&weight, (source, target)) in network_matrix.iter() {
-        graph.add_edge(
-            current_edge_id,
-            &index2id.get(&source).unwrap(),
-            &index2id.get(&target).unwrap(),
-            weight,
-            DependencyType::Influence(weight),
-        );
-        current_edge_id += 1;
-    }
-
-    // Build a graph out of the matrix.
-    Ok(Normalised::new(graph))
+    Ok((nodes, network_matrix))
 }
 
 /// Creates a (sparse) adjacency matrix for the dependencies.
 /// Corresponds to the "cargo-dep-adj.csv" from the Python scripts.
+///
+/// Dependencies referring to projects missing from the metadata are
+/// skipped, whereas duplicate and self-dependencies are kept, but all of
+/// them are recorded in the input `ImportReport`.
 pub fn new_dependency_adjacency_matrix<N, R>(
     deps_meta: &DependenciesMetadata,
     deps_csv: csv::Reader<R>,
+    report: &mut ImportReport,
 ) -> Result<DependencyMatrix<N>, CsvImportError>
 where
     N: Num + Clone,
     R: Read,
 {
     let mut dep_adj: TriMat<N> = TriMatBase::new((deps_meta.ids.len(), deps_meta.ids.len()));
+    let mut seen: HashSet<(ProjectId, ProjectId)> = HashSet::new();
 
     // Iterate through the dependencies, populating the matrix.
-    for result in deps_csv.into_records().filter_map(|e| e.ok()) {
-        let row: DepRow = result.deserialize(None)?;
-        if let (Some(from_index), Some(to_index)) = (
+    for (line, row) in read_rows::<DepRow, _>(deps_csv, CsvFile::Dependencies, report)? {
+        let (from_index, to_index) = match (
             deps_meta.project2index.get(&row.from),
             deps_meta.project2index.get(&row.to),
         ) {
-            dep_adj.add_triplet(*from_index as usize, *to_index as usize, One::one());
+            (Some(from_index), Some(to_index)) => (*from_index, *to_index),
+            _ => {
+                report.record(ImportIssue::DanglingDependency {
+                    line,
+                    from: row.from,
+                    to: row.to,
+                })?;
+                continue;
+            }
+        };
+
+        if row.from == row.to {
+            report.record(ImportIssue::SelfDependency {
+                line,
+                project: row.from,
+            })?;
         }
+
+        if !seen.insert((row.from, row.to)) {
+            report.record(ImportIssue::DuplicateDependency {
+                line,
+                from: row.from,
+                to: row.to,
+            })?;
+        }
+
+        dep_adj.add_triplet(from_index, to_index, One::one());
     }
 
     Ok(dep_adj.to_csr())
@@ -449,16 +868,50 @@ where
     Ok(contrib_adj.to_csr())
 }
 
+/// Creates a (sparse) adjacency matrix for the maintainers, which has the
+/// same shape of the contributions one, i.e. projects x contributors. Each
+/// cell is 1 if the contributor maintains the project, and 0 otherwise.
+pub fn new_maintenance_adjacency_matrix<N>(
+    deps_meta: &DependenciesMetadata,
+    contribs_meta: &ContributionsMetadata,
+    maintainers: &[MaintainerRow],
+) -> Result<MaintenanceMatrix<N>, CsvImportError>
+where
+    N: Num + Clone,
+{
+    let mut maintenance_adj: TriMat<N> =
+        TriMatBase::new((deps_meta.ids.len(), contribs_meta.contributors.len()));
+
+    // Stores the cells already set, so that duplicate rows don't add up.
+    let mut seen: HashSet<(LocalMatrixIndex, LocalMatrixIndex)> = HashSet::new();
+
+    for row in maintainers.iter() {
+        if let (Some(row_ix), Some(col_ix)) = (
+            deps_meta.project2index.get(&row.project_id),
+            contribs_meta.contributor2index.get(&row.maintainer),
+        ) {
+            if seen.insert((*row_ix, *col_ix)) {
+                maintenance_adj.add_triplet(*row_ix, *col_ix, One::one())
+            }
+        }
+    }
+
+    Ok(maintenance_adj.to_csr())
+}
+
 #[cfg(test)]
 mod tests {
     extern crate num_traits;
     extern crate tempfile;
 
+    use super::{CsvFile, CsvImportError, ImportIssue, ImportMode, ImportReport};
     use crate::algorithm::Normalised;
     use crate::protocol_traits::graph::GraphExtras;
     use crate::protocol_traits::ledger::MockLedger;
     use crate::types::network::{ArtifactType, DependencyType, Network};
     use num_traits::Zero;
+    use oscoin_graph_api::{Direction, Edge, Graph, GraphObject};
+    use std::collections::HashMap;
     use std::io::{Seek, Write};
     use tempfile::tempfile;
 
@@ -505,7 +958,7 @@ mod tests {
 
         let mock_ledger = MockLedger::default();
 
-        let network: Normalised<Network<f64>> = super::import_network(
+        let network: Normalised<Network<String, usize, f64>> = super::import_network(
             csv::ReaderBuilder::new()
                 .flexible(true)
                 .from_reader(deps_file),
@@ -532,4 +985,224 @@ mod tests {
             Some(&DependencyType::Influence(0.8)),
         )
     }
+
+    fn csv_from_str(contents: &str) -> csv::Reader<std::fs::File> {
+        let mut file = tempfile().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file.seek(std::io::SeekFrom::Start(0)).unwrap();
+        csv::ReaderBuilder::new().flexible(true).from_reader(file)
+    }
+
+    fn import_with_maintainers(
+        maintainers_csv: Option<&str>,
+    ) -> Normalised<Network<String, usize, f64>> {
+        super::import_network(
+            csv_from_str("FROM_ID,TO_ID\n0,1\n2,0\n2,1\n"),
+            csv_from_str("ID,NAME,PLATFORM\n0,foo,Cargo\n1,bar,Cargo\n2,baz,Cargo\n"),
+            csv_from_str(
+                r###"ID,MAINTAINER,REPO,CONTRIBUTIONS,NAME
+0,github@john,https://github.com/foo/foo-rs,100,foo
+1,github@tom,https://github.com/bar/bar-rs,30,bar
+2,github@tom,https://github.com/baz/baz-rs,60,baz
+2,github@alice,https://github.com/baz/baz-rs,20,baz
+"###,
+            ),
+            maintainers_csv.map(csv_from_str),
+            &MockLedger::default(),
+        )
+        .unwrap_or_else(|e| panic!("returned unexpected error: {}", e))
+    }
+
+    /// Returns the weights of the outgoing edges of the input node.
+    fn outgoing_weights(
+        network: &Normalised<Network<String, usize, f64>>,
+        from: &str,
+    ) -> HashMap<String, f64> {
+        network
+            .edges_directed(&from.to_string(), Direction::Outgoing)
+            .into_iter()
+            .map(|e| (e.to.clone(), network.get_edge(e.id).unwrap().weight()))
+            .collect()
+    }
+
+    #[test]
+    fn csv_network_import_uses_maintainers() {
+        let without = import_with_maintainers(None);
+        let with = import_with_maintainers(Some(
+            r###"ID,MAINTAINER,NAME
+2,github@alice,baz
+1,github@bob,bar
+"###,
+        ));
+
+        // Maintainers who never contributed still become accounts.
+        assert_eq!(
+            without.lookup_node_metadata(&String::from("github@bob")),
+            None
+        );
+        assert_eq!(
+            with.lookup_node_metadata(&String::from("github@bob")),
+            Some(&ArtifactType::Account {
+                osrank: Zero::zero()
+            }),
+        );
+
+        // Maintaining `baz` makes alice more influential than her
+        // contributions alone would.
+        let baz_without = outgoing_weights(&without, "baz");
+        let baz_with = outgoing_weights(&with, "baz");
+        assert!(baz_with["github@alice"] > baz_without["github@alice"]);
+        assert!(baz_with["github@tom"] < baz_without["github@tom"]);
+        assert!((baz_with.values().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        assert!(outgoing_weights(&with, "bar").contains_key("github@bob"));
+        assert!(!outgoing_weights(&without, "bar").contains_key("github@bob"));
+    }
+
+    #[test]
+    fn csv_compact_network_import_matches_network_import() {
+        let network = import_with_maintainers(None);
+        let compact = super::import_compact_network(
+            csv_from_str("FROM_ID,TO_ID\n0,1\n2,0\n2,1\n"),
+            csv_from_str("ID,NAME,PLATFORM\n0,foo,Cargo\n1,bar,Cargo\n2,baz,Cargo\n"),
+            csv_from_str(
+                r###"ID,MAINTAINER,REPO,CONTRIBUTIONS,NAME
+0,github@john,https://github.com/foo/foo-rs,100,foo
+1,github@tom,https://github.com/bar/bar-rs,30,bar
+2,github@tom,https://github.com/baz/baz-rs,60,baz
+2,github@alice,https://github.com/baz/baz-rs,20,baz
+"###,
+            ),
+            None,
+            &MockLedger::default(),
+            &mut ImportReport::default(),
+        )
+        .unwrap_or_else(|e| panic!("returned unexpected error: {}", e));
+
+        assert_eq!(compact.node_count(), network.node_count());
+        assert_eq!(compact.edge_count(), network.edge_count());
+
+        for node in network.nodes() {
+            assert_eq!(compact.lookup_node_metadata(node.id()), Some(node.data()));
+            for edge in network.edges(node.id()) {
+                assert_eq!(compact.lookup_edge_metadata(edge.id()), Some(edge.data()));
+            }
+        }
+    }
+
+    fn import_with_report(
+        deps_csv: &str,
+        contribs_csv: &str,
+        maintainers_csv: &str,
+        report: &mut ImportReport,
+    ) -> Result<Normalised<Network<String, usize, f64>>, CsvImportError> {
+        super::import_network_with_report(
+            csv_from_str(deps_csv),
+            csv_from_str("ID,NAME,PLATFORM\n0,foo,Cargo\n1,bar,Cargo\n2,baz,Cargo\n"),
+            csv_from_str(contribs_csv),
+            Some(csv_from_str(maintainers_csv)),
+            &MockLedger::default(),
+            report,
+        )
+    }
+
+    const INVALID_DEPS: &str = "FROM_ID,TO_ID\n0,1\n2,0\nfoo,1\n0,7\n0,1\n2,2\n";
+    const INVALID_CONTRIBS: &str = r###"ID,MAINTAINER,REPO,CONTRIBUTIONS,NAME
+0,github@john,https://github.com/foo/foo-rs,100,foo
+9,github@tom,https://github.com/qux/qux-rs,30,qux
+"###;
+    const INVALID_MAINTAINERS: &str = "ID,MAINTAINER,NAME\n0,github@john,foo\n8,github@bob,quux\n";
+
+    #[test]
+    fn csv_network_import_reports_invalid_rows() {
+        let mut report = ImportReport::default();
+        let network = import_with_report(
+            INVALID_DEPS,
+            INVALID_CONTRIBS,
+            INVALID_MAINTAINERS,
+            &mut report,
+        )
+        .unwrap_or_else(|e| panic!("returned unexpected error: {}", e));
+
+        match report.issues.remove(2) {
+            ImportIssue::MalformedRow {
+                file: CsvFile::Dependencies,
+                line: 4,
+                ..
+            } => (),
+            other => panic!("expected a malformed row, got {}", other),
+        }
+
+        assert_eq!(
+            report.issues,
+            vec![
+                ImportIssue::UnknownProjectContribution {
+                    line: 3,
+                    project: 9,
+                    contributor: "github@tom".to_string(),
+                },
+                ImportIssue::UnknownProjectMaintainer {
+                    line: 3,
+                    project: 8,
+                    maintainer: "github@bob".to_string(),
+                },
+                ImportIssue::DanglingDependency {
+                    line: 5,
+                    from: 0,
+                    to: 7,
+                },
+                ImportIssue::DuplicateDependency {
+                    line: 6,
+                    from: 0,
+                    to: 1,
+                },
+                ImportIssue::SelfDependency {
+                    line: 7,
+                    project: 2,
+                },
+            ]
+        );
+
+        // The accounts are still there, and the valid rows made it into the
+        // network.
+        assert!(network
+            .lookup_node_metadata(&String::from("github@bob"))
+            .is_some());
+        assert!(outgoing_weights(&network, "foo").contains_key("bar"));
+    }
+
+    #[test]
+    fn csv_network_import_strict_mode_fails_on_invalid_rows() {
+        let mut report = ImportReport::new(ImportMode::Strict);
+        let result = import_with_report(
+            INVALID_DEPS,
+            INVALID_CONTRIBS,
+            INVALID_MAINTAINERS,
+            &mut report,
+        );
+
+        match result {
+            Err(CsvImportError::InvalidRow(ImportIssue::UnknownProjectContribution {
+                line,
+                project,
+                ..
+            })) => {
+                assert_eq!(line, 3);
+                assert_eq!(project, 9);
+            }
+            Err(e) => panic!("returned unexpected error: {}", e),
+            Ok(_) => panic!("the import should have failed"),
+        }
+
+        // A clean input imports just fine.
+        let mut report = ImportReport::new(ImportMode::Strict);
+        assert!(import_with_report(
+            "FROM_ID,TO_ID\n0,1\n2,0\n",
+            "ID,MAINTAINER,REPO,CONTRIBUTIONS,NAME\n0,github@john,https://github.com/foo/foo-rs,100,foo\n",
+            "ID,MAINTAINER,NAME\n0,github@john,foo\n",
+            &mut report,
+        )
+        .is_ok());
+        assert!(report.is_clean());
+    }
 }
@@ -1,51 +1,390 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
 extern crate num_traits;
 extern crate oscoin_graph_api;
 extern crate serde;
 extern crate serde_json;
 
-use crate::types::network::{ArtifactType, DependencyType, Network};
-use crate::types::Weight;
+use crate::algorithm::normalisation::{normalise_network, NormalisationError};
+use crate::algorithm::Normalised;
+use crate::types::mock::MockNetwork;
+use crate::types::network::{Artifact, ArtifactType, Dependency, DependencyType};
+use crate::types::{HyperParams, Osrank};
+use core::fmt;
 use num_traits::Zero;
-use oscoin_graph_api::GraphWriter;
+use oscoin_graph_api::{Graph, GraphWriter};
 use serde::Deserialize;
 use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 
+/// A node of a Gephi JSON file. The attribute vector `a` holds, in order,
+/// the type of the node (either `"project"` or `"account"`) and its osrank,
+/// both of which are optional: nodes without a type are projects, and nodes
+/// without an osrank have a rank of zero.
 #[derive(Debug, Deserialize)]
 pub struct GephiNode {
     id: String,
     a: Vec<Value>,
 }
 
+/// An edge of a Gephi JSON file, going from the node with id `s` to the one
+/// with id `t`, with weight `w`. The (optional) `type` is one of `contrib`,
+/// `contrib*`, `maintain`, `maintain*`, `depend` or `influence`, and defaults
+/// to `depend`.
 #[derive(Debug, Deserialize)]
 pub struct GephiEdge {
     s: usize,
     t: usize,
     w: f64,
+    #[serde(default, rename = "type")]
+    edge_type: Option<String>,
+}
+
+/// Errors arising while building a network out of a Gephi JSON file. The
+/// edges are identified by their position in the input.
+#[derive(Debug, PartialEq)]
+pub enum GephiImportError {
+    /// Two nodes have the same id.
+    DuplicateNode(String),
+    /// The attributes of a node don't describe a valid `ArtifactType`.
+    InvalidNodeAttributes { node: String, attributes: String },
+    /// An edge points from or to a node which doesn't exist.
+    UnknownNode { edge: usize, node: String },
+    /// An edge has an unknown type.
+    InvalidEdgeType { edge: usize, edge_type: String },
+    /// An edge has a negative or non-finite weight.
+    InvalidWeight { edge: usize, weight: f64 },
+    /// The network couldn't be normalised, e.g. because it mixes `influence`
+    /// edges with other types of edges.
+    NormalisationFailed(NormalisationError),
 }
 
-/// Construct a Network out of a Gephi json file. Only projects are supported
-/// for now.
-pub fn from_gephi_json(nodes: Vec<GephiNode>, edges: Vec<GephiEdge>) -> Network<Weight> {
-    let mut network = Network::default();
+impl fmt::Display for GephiImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GephiImportError::DuplicateNode(node) => write!(f, "duplicate node {}", node),
+            GephiImportError::InvalidNodeAttributes { node, attributes } => {
+                write!(f, "node {} has invalid attributes {}", node, attributes)
+            }
+            GephiImportError::UnknownNode { edge, node } => {
+                write!(f, "edge {} refers to the unknown node {}", edge, node)
+            }
+            GephiImportError::InvalidEdgeType { edge, edge_type } => {
+                write!(f, "edge {} has the unknown type {}", edge, edge_type)
+            }
+            GephiImportError::InvalidWeight { edge, weight } => {
+                write!(f, "edge {} has the invalid weight {}", edge, weight)
+            }
+            GephiImportError::NormalisationFailed(e) => {
+                write!(f, "the network couldn't be normalised: {}", e)
+            }
+        }
+    }
+}
+
+impl From<NormalisationError> for GephiImportError {
+    fn from(err: NormalisationError) -> GephiImportError {
+        GephiImportError::NormalisationFailed(err)
+    }
+}
+
+/// Constructs a network out of the nodes and edges of a Gephi JSON file.
+///
+/// Nodes keep their type and osrank, whereas edges keep their type and
+/// weight, and the resulting network goes through `normalise_network`, with
+/// the input `HyperParams`, exactly like a network imported from CSV. This
+/// makes the network ready to be ranked again, e.g. after it has been edited
+/// in Gephi.
+///
+/// A network whose edges are all `influence` ones has already been
+/// normalised: its edges are kept as they are, and only their weights get
+/// scaled, so that the outgoing weights of every node sum up to 1.
+pub fn from_gephi_json<G>(
+    nodes: Vec<GephiNode>,
+    edges: Vec<GephiEdge>,
+    hyperparams: &HyperParams,
+) -> Result<Normalised<G>, GephiImportError>
+where
+    G: Graph<
+            Node = Artifact<String>,
+            Edge = Dependency<usize, f64>,
+            Weight = f64,
+            NodeData = ArtifactType,
+            EdgeData = DependencyType<f64>,
+        > + GraphWriter,
+{
+    let mut artifacts = Vec::with_capacity(nodes.len());
+    let mut node_ids = HashSet::new();
+
     for node in nodes {
-        let project_meta = ArtifactType::Project {
-            osrank: Zero::zero(),
-        };
-        network.add_node(node.id, project_meta);
+        let artifact_type = artifact_type_from(&node)?;
+        if !node_ids.insert(node.id.clone()) {
+            return Err(GephiImportError::DuplicateNode(node.id));
+        }
+        artifacts.push((node.id, artifact_type));
     }
 
+    // Validates the edges upfront, summing up the outgoing weights.
+    let mut dependency_types = Vec::with_capacity(edges.len());
+    let mut totals: HashMap<usize, f64> = HashMap::new();
+
     for (ix, edge) in edges.iter().enumerate() {
-        //FIXME(adn) This should take into account normalisation and
-        //redistribution of the weights etc.
-        let depends_on = DependencyType::Depend(Weight::new(1, 1));
+        for node in &[edge.s, edge.t] {
+            if !node_ids.contains(&node.to_string()) {
+                return Err(GephiImportError::UnknownNode {
+                    edge: ix,
+                    node: node.to_string(),
+                });
+            }
+        }
+        if !edge.w.is_finite() || edge.w < 0.0 {
+            return Err(GephiImportError::InvalidWeight {
+                edge: ix,
+                weight: edge.w,
+            });
+        }
+        dependency_types.push(dependency_type_from(ix, edge, edge.w)?);
+        *totals.entry(edge.s).or_insert(0.0) += edge.w;
+    }
+
+    // Only the `Influence` edges carry no factor.
+    let already_normalised = !dependency_types.is_empty()
+        && dependency_types
+            .iter()
+            .all(|t| hyperparams.factor_for(t).is_none());
+
+    if already_normalised {
+        let scaled = edges.iter().map(|edge| {
+            let total = totals[&edge.s];
+            let weight = if total > 0.0 { edge.w / total } else { 0.0 };
+            DependencyType::Influence(weight)
+        });
+        Ok(Normalised::new(build_network(artifacts, &edges, scaled)))
+    } else {
+        let network: MockNetwork = build_network(artifacts, &edges, dependency_types);
+        Ok(normalise_network(&network, hyperparams)?)
+    }
+}
+
+/// Builds a network out of the input nodes and edges, where every edge gets
+/// the weight carried by its `DependencyType`.
+fn build_network<N>(
+    artifacts: Vec<(String, ArtifactType)>,
+    edges: &[GephiEdge],
+    dependency_types: impl IntoIterator<Item = DependencyType<f64>>,
+) -> N
+where
+    N: Graph<
+            Node = Artifact<String>,
+            Edge = Dependency<usize, f64>,
+            Weight = f64,
+            NodeData = ArtifactType,
+            EdgeData = DependencyType<f64>,
+        > + GraphWriter,
+{
+    let mut network = N::default();
+
+    for (id, artifact_type) in artifacts {
+        network.add_node(id, artifact_type);
+    }
+
+    for (ix, (edge, dependency_type)) in edges.iter().zip(dependency_types).enumerate() {
         network.add_edge(
             ix,
             &edge.s.to_string(),
             &edge.t.to_string(),
-            Weight::new(1, 1),
-            depends_on,
+            *dependency_type.get_weight(),
+            dependency_type,
         )
     }
 
     network
 }
+
+/// Maps the attributes of a node into its `ArtifactType`.
+fn artifact_type_from(node: &GephiNode) -> Result<ArtifactType, GephiImportError> {
+    let invalid = || GephiImportError::InvalidNodeAttributes {
+        node: node.id.clone(),
+        attributes: Value::from(node.a.clone()).to_string(),
+    };
+
+    let osrank = match node.a.get(1) {
+        None | Some(Value::Null) => Zero::zero(),
+        Some(v) => match v.as_f64() {
+            Some(rank) if rank.is_finite() && rank >= 0.0 => Osrank::from(rank),
+            _ => return Err(invalid()),
+        },
+    };
+
+    match node.a.first() {
+        None | Some(Value::Null) => Ok(ArtifactType::Project { osrank }),
+        Some(Value::String(t)) if t.eq_ignore_ascii_case("project") => {
+            Ok(ArtifactType::Project { osrank })
+        }
+        Some(Value::String(t)) if t.eq_ignore_ascii_case("account") => {
+            Ok(ArtifactType::Account { osrank })
+        }
+        Some(_) => Err(invalid()),
+    }
+}
+
+/// Maps the type of an edge into its `DependencyType`, carrying the input
+/// weight.
+fn dependency_type_from(
+    ix: usize,
+    edge: &GephiEdge,
+    weight: f64,
+) -> Result<DependencyType<f64>, GephiImportError> {
+    let edge_type = match &edge.edge_type {
+        None => return Ok(DependencyType::Depend(weight)),
+        Some(t) => t,
+    };
+
+    match edge_type.to_lowercase().as_str() {
+        "contrib" => Ok(DependencyType::Contrib(weight)),
+        "contrib*" => Ok(DependencyType::ContribPrime(weight)),
+        "maintain" => Ok(DependencyType::Maintain(weight)),
+        "maintain*" => Ok(DependencyType::MaintainPrime(weight)),
+        "depend" => Ok(DependencyType::Depend(weight)),
+        "influence" => Ok(DependencyType::Influence(weight)),
+        _ => Err(GephiImportError::InvalidEdgeType {
+            edge: ix,
+            edge_type: edge_type.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::protocol_traits::graph::GraphExtras;
+    use oscoin_graph_api::{Direction, Edge, GraphObject};
+
+    fn import(nodes: &str, edges: &str) -> Result<Normalised<MockNetwork>, GephiImportError> {
+        from_gephi_json(
+            serde_json::from_str(nodes).unwrap(),
+            serde_json::from_str(edges).unwrap(),
+            &HyperParams::default(),
+        )
+    }
+
+    fn outgoing_weights(network: &Normalised<MockNetwork>, id: &str) -> Vec<(String, f64)> {
+        let mut weights = network
+            .edges_directed(&id.to_string(), Direction::Outgoing)
+            .into_iter()
+            .map(|e| {
+                let edge = network.get_edge(e.id).unwrap();
+                assert_eq!(edge.data(), &DependencyType::Influence(edge.weight()));
+                (e.to.clone(), edge.weight())
+            })
+            .collect::<Vec<_>>();
+        weights.sort_by(|a, b| a.0.cmp(&b.0));
+        weights
+    }
+
+    fn assert_weights(actual: Vec<(String, f64)>, expected: &[(&str, f64)]) {
+        assert_eq!(actual.len(), expected.len());
+        for ((id, w), (expected_id, expected_w)) in actual.iter().zip(expected) {
+            assert_eq!(id, expected_id);
+            assert!((w - expected_w).abs() < 1e-12, "{}: {}", id, w);
+        }
+    }
+
+    #[test]
+    fn gephi_json_import_keeps_types_and_normalises_weights() {
+        let network = import(
+            r#"[{"id": "0", "a": []},
+                {"id": "1", "a": ["project", 0.25]},
+                {"id": "2", "a": ["Account"]}]"#,
+            r#"[{"s": 0, "t": 1, "w": 3.0},
+                {"s": 0, "t": 2, "w": 1.0, "type": "contrib"},
+                {"s": 2, "t": 0, "w": 5.0, "type": "contrib*"}]"#,
+        )
+        .unwrap_or_else(|e| panic!("returned unexpected error: {}", e));
+
+        assert_eq!(
+            network.lookup_node_metadata(&"1".to_string()),
+            Some(&ArtifactType::Project {
+                osrank: Osrank::new(1u64, 4u64)
+            })
+        );
+        assert_eq!(
+            network.lookup_node_metadata(&"2".to_string()),
+            Some(&ArtifactType::Account {
+                osrank: Zero::zero()
+            })
+        );
+
+        // The edges get weighted by the hyperparams of their type, i.e. 4/7
+        // for the 'depend' one and 1/7 for the 'contrib' one, like for a
+        // network imported from CSV.
+        assert_weights(outgoing_weights(&network, "0"), &[("1", 0.8), ("2", 0.2)]);
+        assert_weights(outgoing_weights(&network, "2"), &[("0", 1.0)]);
+        assert_weights(outgoing_weights(&network, "1"), &[]);
+    }
+
+    #[test]
+    fn gephi_json_import_keeps_normalised_networks() {
+        let nodes = r#"[{"id": "0", "a": []}, {"id": "1", "a": []}, {"id": "2", "a": []}]"#;
+
+        let network = import(
+            nodes,
+            r#"[{"s": 0, "t": 1, "w": 3.0, "type": "influence"},
+                {"s": 0, "t": 2, "w": 1.0, "type": "influence"}]"#,
+        )
+        .unwrap_or_else(|e| panic!("returned unexpected error: {}", e));
+        assert_weights(outgoing_weights(&network, "0"), &[("1", 0.75), ("2", 0.25)]);
+
+        assert_eq!(
+            import(
+                nodes,
+                r#"[{"s": 0, "t": 1, "w": 3.0},
+                    {"s": 0, "t": 2, "w": 1.0, "type": "influence"}]"#,
+            )
+            .map(|_| ()),
+            Err(GephiImportError::NormalisationFailed(
+                NormalisationError::AlreadyNormalised(1)
+            ))
+        );
+    }
+
+    #[test]
+    fn gephi_json_import_rejects_invalid_input() {
+        let nodes = r#"[{"id": "0", "a": []}, {"id": "1", "a": []}]"#;
+
+        assert_eq!(
+            import(nodes, r#"[{"s": 0, "t": 7, "w": 1.0}]"#).map(|_| ()),
+            Err(GephiImportError::UnknownNode {
+                edge: 0,
+                node: "7".to_string()
+            })
+        );
+        assert_eq!(
+            import(nodes, r#"[{"s": 0, "t": 1, "w": -1.0}]"#).map(|_| ()),
+            Err(GephiImportError::InvalidWeight {
+                edge: 0,
+                weight: -1.0
+            })
+        );
+        assert_eq!(
+            import(nodes, r#"[{"s": 0, "t": 1, "w": 1.0, "type": "foo"}]"#).map(|_| ()),
+            Err(GephiImportError::InvalidEdgeType {
+                edge: 0,
+                edge_type: "foo".to_string()
+            })
+        );
+        assert_eq!(
+            import(r#"[{"id": "0", "a": ["robot"]}]"#, "[]").map(|_| ()),
+            Err(GephiImportError::InvalidNodeAttributes {
+                node: "0".to_string(),
+                attributes: r#"["robot"]"#.to_string()
+            })
+        );
+        assert_eq!(
+            import(r#"[{"id": "0", "a": []}, {"id": "0", "a": []}]"#, "[]").map(|_| ()),
+            Err(GephiImportError::DuplicateNode("0".to_string()))
+        );
+    }
+}
@@ -0,0 +1,161 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
+extern crate oscoin_graph_api;
+
+use super::xml::{build_network, XmlEdge, XmlImportError, XmlNode};
+use crate::types::network::{Artifact, ArtifactType, Dependency, DependencyType};
+use crate::util::xml::{parse, Element};
+use oscoin_graph_api::{Graph, GraphWriter};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Returns the `<attvalue>`s of the input element, keyed by the title of
+/// their `<attribute>`, falling back to the defaults of the attributes.
+fn attvalues_of(
+    element: &Element,
+    titles: &HashMap<String, String>,
+    defaults: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut values = defaults.clone();
+    for value in element
+        .child("attvalues")
+        .iter()
+        .flat_map(|a| a.children_named("attvalue"))
+    {
+        if let (Some(title), Some(v)) = (
+            value.attribute("for").and_then(|f| titles.get(f)),
+            value.attribute("value"),
+        ) {
+            values.insert(title.clone(), v.to_string());
+        }
+    }
+    values
+}
+
+/// Constructs a network out of a GEXF document, like the ones written by
+/// `exporters::gexf`.
+///
+/// The `<attribute>`s are looked up by title rather than by id, so that
+/// documents which went through Gephi (which tends to rename the ids) can be
+/// imported as well. Nodes take their type and osrank from the `type` and
+/// `osrank` attributes, and edges take their weight from their `weight` and
+/// their type from the `type` attribute.
+pub fn from_gexf<G>(input: &str) -> Result<G, XmlImportError>
+where
+    G: Graph<
+            Node = Artifact<String>,
+            Edge = Dependency<usize, f64>,
+            Weight = f64,
+            NodeData = ArtifactType,
+            EdgeData = DependencyType<f64>,
+        > + GraphWriter,
+{
+    let root = parse(input)?;
+    if root.name != "gexf" {
+        return Err(XmlImportError::Missing("<gexf> element".to_string()));
+    }
+
+    let graph = root
+        .child("graph")
+        .ok_or_else(|| XmlImportError::Missing("<graph> element".to_string()))?;
+
+    let mut node_titles = HashMap::new();
+    let mut node_defaults = HashMap::new();
+    let mut edge_titles = HashMap::new();
+    let mut edge_defaults = HashMap::new();
+
+    for attributes in graph.children_named("attributes") {
+        let (titles, defaults) = match attributes.attribute("class") {
+            Some("node") => (&mut node_titles, &mut node_defaults),
+            Some("edge") => (&mut edge_titles, &mut edge_defaults),
+            _ => continue,
+        };
+        for a in attributes.children_named("attribute") {
+            if let (Some(id), Some(title)) = (a.attribute("id"), a.attribute("title")) {
+                titles.insert(id.to_string(), title.to_string());
+                if let Some(default) = a.child("default") {
+                    defaults.insert(title.to_string(), default.text.trim().to_string());
+                }
+            }
+        }
+    }
+
+    let mut nodes = Vec::new();
+    for node in graph
+        .child("nodes")
+        .iter()
+        .flat_map(|n| n.children_named("node"))
+    {
+        let mut values = attvalues_of(node, &node_titles, &node_defaults);
+        nodes.push(XmlNode {
+            id: attribute(node, "id")?,
+            node_type: values.remove("type"),
+            rank: values.remove("osrank"),
+        });
+    }
+
+    let mut edges = Vec::new();
+    for edge in graph
+        .child("edges")
+        .iter()
+        .flat_map(|e| e.children_named("edge"))
+    {
+        let mut values = attvalues_of(edge, &edge_titles, &edge_defaults);
+        edges.push(XmlEdge {
+            id: attribute(edge, "id")?,
+            source: attribute(edge, "source")?,
+            target: attribute(edge, "target")?,
+            weight: edge.attribute("weight").map(String::from),
+            edge_type: values.remove("type"),
+        });
+    }
+
+    build_network(nodes, edges)
+}
+
+/// Like `from_gexf`, but reads the document from the file at `path`.
+pub fn import_gexf<G, P>(path: P) -> Result<G, XmlImportError>
+where
+    G: Graph<
+            Node = Artifact<String>,
+            Edge = Dependency<usize, f64>,
+            Weight = f64,
+            NodeData = ArtifactType,
+            EdgeData = DependencyType<f64>,
+        > + GraphWriter,
+    P: AsRef<Path>,
+{
+    from_gexf(&fs::read_to_string(path)?)
+}
+
+fn attribute(element: &Element, name: &str) -> Result<String, XmlImportError> {
+    element.attribute(name).map(String::from).ok_or_else(|| {
+        XmlImportError::Missing(format!("{} attribute of a <{}>", name, element.name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::exporters::gexf::GexfExporter;
+    use crate::exporters::Exporter;
+    use crate::importers::xml::tests::roundtrips;
+    use crate::types::mock::MockNetwork;
+    use quickcheck::TestResult;
+
+    #[quickcheck]
+    fn gexf_export_then_import_roundtrips(network: MockNetwork) -> TestResult {
+        roundtrips(
+            network,
+            |network, annotator| {
+                GexfExporter::new(network, annotator, Vec::new())
+                    .export()
+                    .unwrap()
+            },
+            from_gexf,
+        )
+    }
+}
@@ -0,0 +1,161 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
+extern crate oscoin_graph_api;
+
+use super::xml::{build_network, XmlEdge, XmlImportError, XmlNode};
+use crate::types::network::{Artifact, ArtifactType, Dependency, DependencyType};
+use crate::util::xml::{parse, Element};
+use oscoin_graph_api::{Graph, GraphWriter};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Returns the values of the `<data>` children of the input element, keyed
+/// by the name of their `<key>`, falling back to the defaults of the keys.
+fn data_of(
+    element: &Element,
+    keys: &HashMap<String, String>,
+    defaults: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut data = defaults.clone();
+    for d in element.children_named("data") {
+        if let Some(name) = d.attribute("key").and_then(|k| keys.get(k)) {
+            data.insert(name.clone(), d.text.trim().to_string());
+        }
+    }
+    data
+}
+
+/// Constructs a network out of a GraphML document, like the ones written by
+/// `exporters::graphml`.
+///
+/// The `<key>`s are looked up by name rather than by id, so that documents
+/// which went through other tools (which tend to rename the ids) can be
+/// imported as well. Nodes take their type and osrank from the `type` and
+/// `rank` keys, and edges take their weight and type from the `weight` and
+/// `type` keys.
+pub fn from_graphml<G>(input: &str) -> Result<G, XmlImportError>
+where
+    G: Graph<
+            Node = Artifact<String>,
+            Edge = Dependency<usize, f64>,
+            Weight = f64,
+            NodeData = ArtifactType,
+            EdgeData = DependencyType<f64>,
+        > + GraphWriter,
+{
+    let root = parse(input)?;
+    if root.name != "graphml" {
+        return Err(XmlImportError::Missing("<graphml> element".to_string()));
+    }
+
+    let mut node_keys = HashMap::new();
+    let mut node_defaults = HashMap::new();
+    let mut edge_keys = HashMap::new();
+    let mut edge_defaults = HashMap::new();
+
+    for key in root.children_named("key") {
+        let (keys, defaults) = match key.attribute("for") {
+            Some("node") => (&mut node_keys, &mut node_defaults),
+            Some("edge") => (&mut edge_keys, &mut edge_defaults),
+            _ => continue,
+        };
+        if let (Some(id), Some(name)) = (key.attribute("id"), key.attribute("attr.name")) {
+            keys.insert(id.to_string(), name.to_string());
+            if let Some(default) = key.child("default") {
+                defaults.insert(name.to_string(), default.text.trim().to_string());
+            }
+        }
+    }
+
+    let graph = root
+        .child("graph")
+        .ok_or_else(|| XmlImportError::Missing("<graph> element".to_string()))?;
+
+    let mut nodes = Vec::new();
+    for node in graph.children_named("node") {
+        let mut data = data_of(node, &node_keys, &node_defaults);
+        nodes.push(XmlNode {
+            id: attribute(node, "id")?,
+            node_type: data.remove("type"),
+            rank: data.remove("rank"),
+        });
+    }
+
+    let mut edges = Vec::new();
+    for edge in graph.children_named("edge") {
+        let mut data = data_of(edge, &edge_keys, &edge_defaults);
+        edges.push(XmlEdge {
+            id: attribute(edge, "id")?,
+            source: attribute(edge, "source")?,
+            target: attribute(edge, "target")?,
+            weight: data.remove("weight"),
+            edge_type: data.remove("type"),
+        });
+    }
+
+    build_network(nodes, edges)
+}
+
+/// Like `from_graphml`, but reads the document from the file at `path`.
+pub fn import_graphml<G, P>(path: P) -> Result<G, XmlImportError>
+where
+    G: Graph<
+            Node = Artifact<String>,
+            Edge = Dependency<usize, f64>,
+            Weight = f64,
+            NodeData = ArtifactType,
+            EdgeData = DependencyType<f64>,
+        > + GraphWriter,
+    P: AsRef<Path>,
+{
+    from_graphml(&fs::read_to_string(path)?)
+}
+
+fn attribute(element: &Element, name: &str) -> Result<String, XmlImportError> {
+    element.attribute(name).map(String::from).ok_or_else(|| {
+        XmlImportError::Missing(format!("{} attribute of a <{}>", name, element.name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::exporters::graphml::GraphMlExporter;
+    use crate::exporters::Exporter;
+    use crate::importers::xml::tests::roundtrips;
+    use crate::types::mock::MockNetwork;
+    use quickcheck::TestResult;
+
+    #[quickcheck]
+    fn graphml_export_then_import_roundtrips(network: MockNetwork) -> TestResult {
+        roundtrips(
+            network,
+            |network, annotator| {
+                GraphMlExporter::new(network, annotator, Vec::new())
+                    .export()
+                    .unwrap()
+            },
+            from_graphml,
+        )
+    }
+
+    #[test]
+    fn graphml_import_rejects_unknown_nodes() {
+        let result: Result<MockNetwork, _> = from_graphml(
+            r#"<graphml><graph>
+                 <node id="a"/>
+                 <edge id="0" source="a" target="b"/>
+               </graph></graphml>"#,
+        );
+        assert_eq!(
+            result.map(|_| ()),
+            Err(XmlImportError::UnknownNode {
+                edge: 0,
+                node: "b".to_string()
+            })
+        );
+    }
+}
@@ -1,5 +1,14 @@
 /// Builds a `Network` graph from some key CSV files.
 pub mod csv;
 
+/// Builds a `Network` graph from a GEXF file.
+pub mod gexf;
+
 /// Builds a `Network` graph from a Gephi JSON file.
 pub mod gephi_json;
+
+/// Builds a `Network` graph from a GraphML file.
+pub mod graphml;
+
+/// The errors and the network-building logic shared by the XML importers.
+pub mod xml;
@@ -0,0 +1,255 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
+extern crate num_traits;
+extern crate oscoin_graph_api;
+
+use crate::types::network::{Artifact, ArtifactType, Dependency, DependencyType};
+use crate::types::Osrank;
+use crate::util::xml::XmlError;
+use core::fmt;
+use num_traits::Zero;
+use oscoin_graph_api::{Graph, GraphWriter};
+use std::collections::HashSet;
+
+/// Errors arising while importing a GraphML or a GEXF file.
+#[derive(Debug, PartialEq)]
+pub enum XmlImportError {
+    /// Returned in case of generic I/O error.
+    IOError(String),
+    /// The input is not well-formed XML.
+    XmlError(XmlError),
+    /// A mandatory element or attribute is missing.
+    Missing(String),
+    /// An element or attribute has an invalid value.
+    InvalidValue { what: String, value: String },
+    /// Two nodes have the same id.
+    DuplicateNode(String),
+    /// An edge points from or to a node which doesn't exist.
+    UnknownNode { edge: usize, node: String },
+}
+
+impl fmt::Display for XmlImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            XmlImportError::IOError(e) => write!(f, "i/o error when reading the file {}", e),
+            XmlImportError::XmlError(e) => write!(f, "{}", e),
+            XmlImportError::Missing(what) => write!(f, "missing {}", what),
+            XmlImportError::InvalidValue { what, value } => {
+                write!(f, "invalid {}: {}", what, value)
+            }
+            XmlImportError::DuplicateNode(node) => write!(f, "duplicate node {}", node),
+            XmlImportError::UnknownNode { edge, node } => {
+                write!(f, "edge {} refers to the unknown node {}", edge, node)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for XmlImportError {
+    fn from(err: std::io::Error) -> XmlImportError {
+        XmlImportError::IOError(err.to_string())
+    }
+}
+
+impl From<XmlError> for XmlImportError {
+    fn from(err: XmlError) -> XmlImportError {
+        XmlImportError::XmlError(err)
+    }
+}
+
+/// A node, as read from the file.
+pub(crate) struct XmlNode {
+    pub id: String,
+    pub node_type: Option<String>,
+    pub rank: Option<String>,
+}
+
+/// An edge, as read from the file.
+pub(crate) struct XmlEdge {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    pub weight: Option<String>,
+    pub edge_type: Option<String>,
+}
+
+fn invalid(what: &str, value: &str) -> XmlImportError {
+    XmlImportError::InvalidValue {
+        what: what.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn parse_f64(what: &str, value: &str) -> Result<f64, XmlImportError> {
+    match value.trim().parse::<f64>() {
+        Ok(v) if v.is_finite() && v >= 0.0 => Ok(v),
+        _ => Err(invalid(what, value)),
+    }
+}
+
+/// Builds the network out of the nodes and edges read from the file.
+///
+/// Nodes without a type are projects, and nodes without a rank have a rank
+/// of zero. Edges without a weight have a weight of 1, and edges without a
+/// type are `Influence` ones, like the ones of a normalised network.
+pub(crate) fn build_network<G>(
+    nodes: Vec<XmlNode>,
+    edges: Vec<XmlEdge>,
+) -> Result<G, XmlImportError>
+where
+    G: Graph<
+            Node = Artifact<String>,
+            Edge = Dependency<usize, f64>,
+            Weight = f64,
+            NodeData = ArtifactType,
+            EdgeData = DependencyType<f64>,
+        > + GraphWriter,
+{
+    let mut network = G::default();
+    let mut node_ids = HashSet::new();
+
+    for node in nodes {
+        let osrank: Osrank = match &node.rank {
+            None => Zero::zero(),
+            Some(r) => Osrank::from(parse_f64("rank", r)?),
+        };
+        let artifact_type = match &node.node_type {
+            None => ArtifactType::Project { osrank },
+            Some(t) if t == "project" => ArtifactType::Project { osrank },
+            Some(t) if t == "account" => ArtifactType::Account { osrank },
+            Some(t) => return Err(invalid("node type", t)),
+        };
+
+        if !node_ids.insert(node.id.clone()) {
+            return Err(XmlImportError::DuplicateNode(node.id));
+        }
+        network.add_node(node.id, artifact_type);
+    }
+
+    for edge in edges {
+        let id = edge
+            .id
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| invalid("edge id", &edge.id))?;
+        let weight = match &edge.weight {
+            None => 1.0,
+            Some(w) => parse_f64("edge weight", w)?,
+        };
+        let dependency_type = match &edge.edge_type {
+            None => DependencyType::Influence(weight),
+            Some(t) if t == "influence" => DependencyType::Influence(weight),
+            Some(t) if t == "contrib" => DependencyType::Contrib(weight),
+            Some(t) if t == "contrib*" => DependencyType::ContribPrime(weight),
+            Some(t) if t == "maintain" => DependencyType::Maintain(weight),
+            Some(t) if t == "maintain*" => DependencyType::MaintainPrime(weight),
+            Some(t) if t == "depend" => DependencyType::Depend(weight),
+            Some(t) => return Err(invalid("edge type", t)),
+        };
+
+        for node in &[&edge.source, &edge.target] {
+            if !node_ids.contains(*node) {
+                return Err(XmlImportError::UnknownNode {
+                    edge: id,
+                    node: node.to_string(),
+                });
+            }
+        }
+
+        network.add_edge(id, &edge.source, &edge.target, weight, dependency_type);
+    }
+
+    Ok(network)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+
+    use crate::types::mock::{KeyValueAnnotator, MockNetwork};
+    use crate::types::network::{ArtifactType, DependencyType};
+    use crate::types::Osrank;
+    use oscoin_graph_api::{Direction, Edge, Graph, GraphDataWriter, GraphObject};
+    use quickcheck::TestResult;
+    use std::collections::HashSet;
+
+    type Nodes = Vec<(String, ArtifactType)>;
+    type Edges = Vec<(usize, String, String, f64, DependencyType<f64>)>;
+
+    /// The nodes and edges of a network, sorted.
+    fn contents_of(network: &MockNetwork) -> (Nodes, Edges) {
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+
+        for node in network.nodes() {
+            nodes.push((node.id().clone(), node.data().clone()));
+            for e in network.edges_directed(node.id(), Direction::Outgoing) {
+                let edge = network.get_edge(e.id).unwrap();
+                edges.push((
+                    *e.id,
+                    e.from.clone(),
+                    e.to.clone(),
+                    edge.weight(),
+                    edge.data().clone(),
+                ));
+            }
+        }
+
+        nodes.sort_by(|a, b| a.0.cmp(&b.0));
+        edges.sort_by_key(|e| e.0);
+        (nodes, edges)
+    }
+
+    /// Exports the input network via the input exporter, and imports it back
+    /// via the input importer, checking it's the same network, edge types
+    /// included. The ranks are exported through the annotator, so they have
+    /// to be representable as an `f64` to survive the round-trip.
+    pub(crate) fn roundtrips<X, I>(mut network: MockNetwork, export: X, import: I) -> TestResult
+    where
+        X: Fn(&MockNetwork, &KeyValueAnnotator<String, Osrank>) -> Vec<u8>,
+        I: Fn(&str) -> Result<MockNetwork, super::XmlImportError>,
+    {
+        let ids: Vec<String> = network.nodes().map(|n| n.id().clone()).collect();
+        if ids.iter().collect::<HashSet<_>>().len() != ids.len() {
+            // Duplicate ids make the nodes indistinguishable.
+            return TestResult::discard();
+        }
+
+        let mut annotator = KeyValueAnnotator {
+            annotator: Default::default(),
+        };
+        for (ix, id) in ids.iter().enumerate() {
+            let rank = Osrank::new(ix as u64, 8u64);
+            network.node_data_mut(id).unwrap().set_osrank(rank);
+            annotator.annotator.insert(id.clone(), rank);
+        }
+
+        // The arbitrary networks are normalised, hence all their edges are
+        // `Influence` ones: retype some of them, so that the round-trip
+        // covers every `DependencyType`.
+        let edge_ids: Vec<usize> = ids
+            .iter()
+            .flat_map(|id| network.edges_directed(id, Direction::Outgoing))
+            .map(|e| *e.id)
+            .collect();
+        for id in edge_ids {
+            let edge_type = network.edge_data_mut(&id).unwrap();
+            let w = *edge_type.get_weight();
+            *edge_type = match id % 6 {
+                0 => DependencyType::Contrib(w),
+                1 => DependencyType::ContribPrime(w),
+                2 => DependencyType::Maintain(w),
+                3 => DependencyType::MaintainPrime(w),
+                4 => DependencyType::Depend(w),
+                _ => DependencyType::Influence(w),
+            };
+        }
+
+        let out = String::from_utf8(export(&network, &annotator)).unwrap();
+
+        match import(&out) {
+            Ok(imported) => TestResult::from_bool(contents_of(&imported) == contents_of(&network)),
+            Err(e) => panic!("returned unexpected error: {}", e),
+        }
+    }
+}
@@ -160,3 +160,109 @@ where
         }
     }
 }
+
+/// The result of `pagerank_sparse`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRank {
+    /// The rank of each node, where the node `i` is the `i`-th row of the
+    /// input transition matrix. The ranks sum up to 1.0.
+    pub ranks: Vec<f64>,
+    /// The number of iterations performed.
+    pub iterations: usize,
+    /// The L1 norm of the difference between the last two iterates.
+    pub residual: f64,
+}
+
+impl PageRank {
+    /// Returns `true` if the residual fell below the input tolerance.
+    pub fn converged(&self, tolerance: f64) -> bool {
+        self.residual <= tolerance
+    }
+}
+
+/// Computes the exact PageRank via sparse power iteration.
+///
+/// The `transition` matrix is a (row-normalised) CSR matrix, where the row `i`
+/// holds the outgoing edges of the node `i`. From the node `i`, the surfer
+/// follows an outgoing edge with probability `damping_factors[i]`, and
+/// teleports to a random node otherwise. Dangling nodes (i.e. empty rows)
+/// redistribute their whole rank uniformly across all the nodes.
+///
+/// The iteration stops as soon as the L1 norm of the difference between two
+/// iterates falls below `tolerance`, or after `max_iterations`, whichever
+/// comes first. Each iteration is O(nnz).
+pub fn pagerank_sparse(
+    transition: &SparseMatrix<f64>,
+    damping_factors: &[f64],
+    tolerance: f64,
+    max_iterations: usize,
+) -> PageRank {
+    let nodes = transition.rows();
+    if transition.cols() != nodes {
+        panic!("Dimension mismatch: the transition matrix is not square.");
+    }
+    if damping_factors.len() != nodes {
+        panic!("Dimension mismatch: one damping factor per node is required.");
+    }
+    if !transition.is_csr() {
+        panic!("The transition matrix is not a CSR matrix.");
+    }
+
+    if nodes == 0 {
+        return PageRank {
+            ranks: Vec::new(),
+            iterations: 0,
+            residual: 0.0,
+        };
+    }
+
+    let uniform = 1.0 / nodes as f64;
+    let dangling: Vec<bool> = transition
+        .outer_iterator()
+        .map(|row| row.nnz() == 0)
+        .collect();
+
+    // At t = 0, the rank for all the nodes is the same.
+    let mut rank = vec![uniform; nodes];
+    let mut next = vec![0.0; nodes];
+    let mut iterations = 0;
+    let mut residual = std::f64::INFINITY;
+
+    while iterations < max_iterations && residual > tolerance {
+        // The mass which gets spread uniformly, i.e. the teleports plus the
+        // rank of the dangling nodes.
+        let mut teleport = 0.0;
+
+        for v in next.iter_mut() {
+            *v = 0.0;
+        }
+
+        for (ix, row) in transition.outer_iterator().enumerate() {
+            let damping = damping_factors[ix];
+            if dangling[ix] {
+                teleport += rank[ix];
+            } else {
+                teleport += (1.0 - damping) * rank[ix];
+                let flow = damping * rank[ix];
+                for (col_ix, &weight) in row.iter() {
+                    next[col_ix] += flow * weight;
+                }
+            }
+        }
+
+        residual = 0.0;
+        for (ix, v) in next.iter_mut().enumerate() {
+            *v += teleport * uniform;
+            residual += (*v - rank[ix]).abs();
+        }
+
+        std::mem::swap(&mut rank, &mut next);
+        iterations += 1;
+    }
+
+    PageRank {
+        ranks: rank,
+        iterations,
+        residual,
+    }
+}
@@ -0,0 +1,732 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
+extern crate fs2;
+extern crate serde;
+extern crate serde_json;
+
+use super::ledger::{LedgerView, MockLedger, MockLedgerState};
+use crate::types::{DampingFactors, HyperParams, MaxWalkLength, Tau, R};
+use core::fmt;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The version of a `FileLedger`, i.e. the number of changes recorded into
+/// it. Version 0 is the ledger before any change, whose parameters are the
+/// snapshot the ledger file starts with.
+pub type LedgerVersion = u64;
+
+/// The full set of parameters of a `LedgerView`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerParams {
+    pub hyperparams: HyperParams,
+    pub random_walks_num: R,
+    pub tau: Tau,
+    pub max_walk_length: MaxWalkLength,
+    pub damping_factors: DampingFactors,
+}
+
+impl LedgerParams {
+    /// Takes a snapshot of the parameters of the input ledger.
+    pub fn of<L>(ledger: &L) -> Self
+    where
+        L: LedgerView,
+    {
+        LedgerParams {
+            hyperparams: ledger.get_hyperparams().clone(),
+            random_walks_num: *ledger.get_random_walks_num(),
+            tau: *ledger.get_tau(),
+            max_walk_length: *ledger.get_max_walk_length(),
+            damping_factors: ledger.get_damping_factors().clone(),
+        }
+    }
+
+    fn apply_to<L>(&self, ledger: &mut L)
+    where
+        L: LedgerView,
+    {
+        ledger.set_hyperparams(self.hyperparams.clone());
+        ledger.set_random_walks_num(self.random_walks_num);
+        ledger.set_tau(self.tau);
+        ledger.set_max_walk_length(self.max_walk_length);
+        ledger.set_damping_factors(self.damping_factors.clone());
+    }
+
+    /// Returns a `MockLedger` with these parameters.
+    pub fn to_ledger(&self) -> MockLedger {
+        // Every parameter gets overwritten, so the defaults don't leak in.
+        let mut ledger = MockLedger::default();
+        self.apply_to(&mut ledger);
+        ledger
+    }
+}
+
+/// A change to one of the parameters of a `LedgerView`, one per setter, or
+/// the snapshot of all of them the ledger starts from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamChange {
+    /// The initial parameters of the ledger, which are always (and only) its
+    /// entry for version 0.
+    Initial(LedgerParams),
+    HyperParams(HyperParams),
+    RandomWalksNum(R),
+    Tau(Tau),
+    MaxWalkLength(MaxWalkLength),
+    DampingFactors(DampingFactors),
+}
+
+impl ParamChange {
+    fn apply_to<L>(&self, ledger: &mut L)
+    where
+        L: LedgerView,
+    {
+        match self {
+            ParamChange::Initial(params) => params.apply_to(ledger),
+            ParamChange::HyperParams(params) => ledger.set_hyperparams(params.clone()),
+            ParamChange::RandomWalksNum(r) => ledger.set_random_walks_num(*r),
+            ParamChange::Tau(tau) => ledger.set_tau(*tau),
+            ParamChange::MaxWalkLength(len) => ledger.set_max_walk_length(*len),
+            ParamChange::DampingFactors(factors) => ledger.set_damping_factors(factors.clone()),
+        }
+    }
+}
+
+/// An entry of the history of a `FileLedger`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    /// The version of the ledger right after this change.
+    pub version: LedgerVersion,
+    /// When the change was recorded, in seconds since the UNIX epoch.
+    pub timestamp: u64,
+    pub change: ParamChange,
+}
+
+/// Errors that opening or writing a `FileLedger` might throw.
+#[derive(Debug)]
+pub enum FileLedgerError {
+    /// Returned in case of generic I/O error.
+    IOError(io::Error),
+
+    /// Returned when an entry of the ledger file couldn't be serialised or
+    /// deserialised.
+    EncodingError {
+        line: usize,
+        error: serde_json::Error,
+    },
+
+    /// Returned when the entries of the ledger file aren't numbered
+    /// 0, 1, 2 ..., i.e. an entry got lost or the file got tampered with.
+    NonSequentialVersion {
+        line: usize,
+        found: LedgerVersion,
+        expected: LedgerVersion,
+    },
+
+    /// Returned when the first entry of the ledger file isn't the snapshot
+    /// of its initial parameters, or when a later entry is.
+    MisplacedSnapshot { line: usize },
+
+    /// Returned when reading the ledger as of a version it hasn't reached
+    /// (yet).
+    UnknownVersion {
+        requested: LedgerVersion,
+        latest: LedgerVersion,
+    },
+}
+
+impl fmt::Display for FileLedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileLedgerError::IOError(e) => {
+                write!(f, "i/o error when reading/writing on the ledger {}", e)
+            }
+            FileLedgerError::EncodingError { line, error } => {
+                write!(f, "invalid ledger entry at line {}: {}", line, error)
+            }
+            FileLedgerError::NonSequentialVersion {
+                line,
+                found,
+                expected,
+            } => write!(
+                f,
+                "the ledger entry at line {} has version {}, but version {} was expected",
+                line, found, expected
+            ),
+            FileLedgerError::MisplacedSnapshot { line } => write!(
+                f,
+                "the ledger entry at line {} should be the initial parameters if and only if it's the first one",
+                line
+            ),
+            FileLedgerError::UnknownVersion { requested, latest } => write!(
+                f,
+                "the ledger has no version {} (the latest is {})",
+                requested, latest
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for FileLedgerError {
+    fn from(err: io::Error) -> FileLedgerError {
+        FileLedgerError::IOError(err)
+    }
+}
+
+/// The entries read out of a ledger file.
+#[derive(Default)]
+struct Contents {
+    /// The initial parameters, unless the file is empty.
+    initial: Option<LedgerParams>,
+    /// The changes on top of the initial parameters.
+    history: Vec<LedgerEntry>,
+    /// The length of the file up to the end of its last complete line.
+    complete_len: u64,
+}
+
+/// Reads and validates all the entries of the ledger file.
+///
+/// A last line without a trailing newline is what's left of a write which
+/// didn't complete: the change it holds was never acknowledged, so it's
+/// skipped rather than making the whole ledger unreadable.
+fn read_contents(file: &mut File) -> Result<Contents, FileLedgerError> {
+    let mut bytes = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut bytes)?;
+
+    let complete_len = bytes
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |ix| ix + 1);
+    if complete_len < bytes.len() {
+        warn!("Skipping the incomplete last line of the ledger.");
+    }
+
+    let mut contents = Contents {
+        complete_len: complete_len as u64,
+        ..Contents::default()
+    };
+
+    for (ix, line) in bytes[..complete_len].split(|b| *b == b'\n').enumerate() {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+
+        let entry: LedgerEntry =
+            serde_json::from_slice(line).map_err(|error| FileLedgerError::EncodingError {
+                line: ix + 1,
+                error,
+            })?;
+        let expected = match contents.initial {
+            None => 0,
+            Some(_) => contents.history.len() as LedgerVersion + 1,
+        };
+        if entry.version != expected {
+            return Err(FileLedgerError::NonSequentialVersion {
+                line: ix + 1,
+                found: entry.version,
+                expected,
+            });
+        }
+
+        match (&entry.change, &contents.initial) {
+            (ParamChange::Initial(params), None) => contents.initial = Some(params.clone()),
+            (ParamChange::Initial(_), Some(_)) | (_, None) => {
+                return Err(FileLedgerError::MisplacedSnapshot { line: ix + 1 })
+            }
+            (_, Some(_)) => contents.history.push(entry),
+        }
+    }
+
+    Ok(contents)
+}
+
+fn encode(entry: &LedgerEntry, out: &mut Vec<u8>) -> Result<(), FileLedgerError> {
+    serde_json::to_writer(&mut *out, entry).map_err(|error| FileLedgerError::EncodingError {
+        line: entry.version as usize + 1,
+        error,
+    })?;
+    out.push(b'\n');
+    Ok(())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// A `LedgerView` backed by a file, which keeps the whole history of its
+/// parameters.
+///
+/// The file is in the JSON Lines format: it starts with a snapshot of all
+/// the parameters, and every change is then appended to it as a
+/// `LedgerEntry`, and nothing is ever rewritten. The parameters as of any
+/// version are obtained by replaying the changes up to it on top of the
+/// snapshot, so that a rank can be reproduced with exactly the parameters it
+/// was computed with, whatever the defaults of the code running it.
+///
+/// The file is locked while being read or appended to, so that concurrent
+/// writers don't record two changes with the same version.
+///
+/// # Panics
+///
+/// The setters of the `LedgerView` can't fail, hence they panic if the change
+/// can't be written to disk. Use `record` to handle the error instead.
+pub struct FileLedger {
+    path: PathBuf,
+    initial: LedgerParams,
+    history: Vec<LedgerEntry>,
+    current: MockLedger,
+}
+
+impl FileLedger {
+    /// Opens the ledger stored at `path`. If the file doesn't exist yet (or
+    /// is empty), it's created with a snapshot of the parameters of a
+    /// `MockLedger::default()`, which the ledger starts with.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, FileLedgerError> {
+        let path = path.as_ref().to_path_buf();
+
+        let contents = match File::open(&path) {
+            Ok(mut file) => {
+                file.lock_shared()?;
+                let contents = read_contents(&mut file);
+                file.unlock()?;
+                contents?
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Contents::default(),
+            Err(e) => return Err(e.into()),
+        };
+        let contents = match contents.initial {
+            Some(_) => contents,
+            None => initialise(&path)?,
+        };
+
+        let initial = contents
+            .initial
+            .unwrap_or_else(|| LedgerParams::of(&MockLedger::default()));
+        let current = replay(&initial, &contents.history);
+        Ok(FileLedger {
+            path,
+            initial,
+            history: contents.history,
+            current,
+        })
+    }
+
+    /// The latest version of the ledger.
+    pub fn version(&self) -> LedgerVersion {
+        self.history.len() as LedgerVersion
+    }
+
+    /// The parameters the ledger started with, i.e. as of version 0.
+    pub fn initial(&self) -> &LedgerParams {
+        &self.initial
+    }
+
+    /// All the changes recorded so far, from the oldest to the latest.
+    pub fn history(&self) -> &[LedgerEntry] {
+        &self.history
+    }
+
+    /// Returns the parameters as they were at the given version.
+    pub fn as_of(&self, version: LedgerVersion) -> Result<MockLedger, FileLedgerError> {
+        if version > self.version() {
+            return Err(FileLedgerError::UnknownVersion {
+                requested: version,
+                latest: self.version(),
+            });
+        }
+
+        Ok(replay(&self.initial, &self.history[..version as usize]))
+    }
+
+    /// Returns the parameters as they are at the latest version.
+    pub fn latest(&self) -> MockLedger {
+        replay(&self.initial, &self.history)
+    }
+
+    /// Appends the change to the ledger file and applies it, returning the
+    /// new version of the ledger. The change is only applied once it's been
+    /// written to disk.
+    ///
+    /// The changes recorded by other processes since the ledger was opened
+    /// are read back first, and the new change is applied on top of them.
+    pub fn record(&mut self, change: ParamChange) -> Result<LedgerVersion, FileLedgerError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&self.path)?;
+
+        file.lock_exclusive()?;
+        let version = self.record_locked(&mut file, change);
+        file.unlock()?;
+        version
+    }
+
+    fn record_locked(
+        &mut self,
+        file: &mut File,
+        change: ParamChange,
+    ) -> Result<LedgerVersion, FileLedgerError> {
+        let contents = read_contents(file)?;
+        if contents.complete_len < file.metadata()?.len() {
+            file.set_len(contents.complete_len)?;
+        }
+
+        let mut lines = Vec::new();
+        match contents.initial {
+            Some(initial) => self.initial = initial,
+            None => encode(
+                &LedgerEntry {
+                    version: 0,
+                    timestamp: now(),
+                    change: ParamChange::Initial(self.initial.clone()),
+                },
+                &mut lines,
+            )?,
+        }
+        self.history = contents.history;
+
+        let entry = LedgerEntry {
+            version: self.version() + 1,
+            timestamp: now(),
+            change,
+        };
+        encode(&entry, &mut lines)?;
+
+        file.write_all(&lines)?;
+        file.sync_data()?;
+
+        self.history.push(entry);
+        self.current = replay(&self.initial, &self.history);
+
+        Ok(self.version())
+    }
+
+    fn record_or_panic(&mut self, change: ParamChange) {
+        if let Err(e) = self.record(change) {
+            panic!(
+                "couldn't record the change into the ledger at {}: {}",
+                self.path.display(),
+                e
+            )
+        }
+    }
+}
+
+/// Creates the ledger file at `path`, with a snapshot of the parameters of a
+/// `MockLedger::default()`, unless another writer did it first. Returns the
+/// contents of the file.
+fn initialise(path: &Path) -> Result<Contents, FileLedgerError> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)?;
+
+    file.lock_exclusive()?;
+    let contents = initialise_locked(&mut file);
+    file.unlock()?;
+    contents
+}
+
+fn initialise_locked(file: &mut File) -> Result<Contents, FileLedgerError> {
+    let mut contents = read_contents(file)?;
+    if contents.initial.is_some() {
+        return Ok(contents);
+    }
+    if contents.complete_len < file.metadata()?.len() {
+        file.set_len(contents.complete_len)?;
+    }
+
+    let initial = LedgerParams::of(&MockLedger::default());
+    let mut lines = Vec::new();
+    encode(
+        &LedgerEntry {
+            version: 0,
+            timestamp: now(),
+            change: ParamChange::Initial(initial.clone()),
+        },
+        &mut lines,
+    )?;
+    file.write_all(&lines)?;
+    file.sync_data()?;
+
+    contents.initial = Some(initial);
+    Ok(contents)
+}
+
+fn replay(initial: &LedgerParams, history: &[LedgerEntry]) -> MockLedger {
+    let mut ledger = initial.to_ledger();
+    for entry in history {
+        entry.change.apply_to(&mut ledger);
+    }
+    ledger
+}
+
+impl LedgerView for FileLedger {
+    type State = MockLedgerState;
+
+    fn get_hyperparams(&self) -> &HyperParams {
+        self.current.get_hyperparams()
+    }
+    fn set_hyperparams(&mut self, new: HyperParams) {
+        self.record_or_panic(ParamChange::HyperParams(new))
+    }
+
+    fn get_random_walks_num(&self) -> &R {
+        self.current.get_random_walks_num()
+    }
+    fn set_random_walks_num(&mut self, new: R) {
+        self.record_or_panic(ParamChange::RandomWalksNum(new))
+    }
+
+    fn get_tau(&self) -> &Tau {
+        self.current.get_tau()
+    }
+    fn set_tau(&mut self, new: Tau) {
+        self.record_or_panic(ParamChange::Tau(new))
+    }
+
+    fn get_max_walk_length(&self) -> &MaxWalkLength {
+        self.current.get_max_walk_length()
+    }
+    fn set_max_walk_length(&mut self, new: MaxWalkLength) {
+        self.record_or_panic(ParamChange::MaxWalkLength(new))
+    }
+
+    fn get_damping_factors(&self) -> &DampingFactors {
+        self.current.get_damping_factors()
+    }
+    fn set_damping_factors(&mut self, new: DampingFactors) {
+        self.record_or_panic(ParamChange::DampingFactors(new))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::types::Weight;
+    use std::fs;
+    use std::thread;
+
+    #[test]
+    fn file_ledger_reads_as_of_any_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let mut ledger = FileLedger::open(&path).unwrap();
+        assert_eq!(ledger.version(), 0);
+        // Opening the ledger creates it, with its initial parameters.
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+        ledger.set_tau(0.25);
+        ledger.set_random_walks_num(100);
+        let params = HyperParams {
+            depend_factor: Weight::new(1, 3),
+            ..HyperParams::default()
+        };
+        ledger.set_hyperparams(params.clone());
+        ledger.set_tau(0.5);
+
+        assert_eq!(ledger.version(), 4);
+        assert_eq!(*ledger.get_tau(), 0.5);
+        // The initial parameters, followed by the changes.
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 5);
+
+        // Reopening the ledger replays its whole history.
+        let ledger = FileLedger::open(&path).unwrap();
+        assert_eq!(ledger.version(), 4);
+        assert_eq!(*ledger.get_tau(), 0.5);
+        assert_eq!(*ledger.get_random_walks_num(), 100);
+        assert_eq!(*ledger.get_hyperparams(), params);
+        assert!(ledger
+            .history()
+            .windows(2)
+            .all(|w| w[0].timestamp <= w[1].timestamp));
+
+        let v0 = ledger.as_of(0).unwrap();
+        assert_eq!(*v0.get_tau(), 0.0);
+        assert_eq!(*v0.get_hyperparams(), HyperParams::default());
+
+        let v2 = ledger.as_of(2).unwrap();
+        assert_eq!(*v2.get_tau(), 0.25);
+        assert_eq!(*v2.get_random_walks_num(), 100);
+        assert_eq!(*v2.get_hyperparams(), HyperParams::default());
+
+        match ledger.as_of(5) {
+            Err(FileLedgerError::UnknownVersion { requested, latest }) => {
+                assert_eq!((requested, latest), (5, 4))
+            }
+            _ => panic!("read a version the ledger doesn't have"),
+        }
+    }
+
+    #[test]
+    fn file_ledger_open_records_the_initial_parameters() {
+        let dir = tempfile::tempdir().unwrap();
+
+        for path in &[
+            dir.path().join("missing.jsonl"),
+            dir.path().join("empty.jsonl"),
+        ] {
+            if path.ends_with("empty.jsonl") {
+                fs::write(path, "").unwrap();
+            }
+            FileLedger::open(path).unwrap();
+
+            let contents = fs::read_to_string(path).unwrap();
+            let lines: Vec<&str> = contents.lines().collect();
+            assert_eq!(lines.len(), 1);
+            let snapshot: LedgerEntry = serde_json::from_str(lines[0]).unwrap();
+            assert_eq!(snapshot.version, 0);
+            assert_eq!(
+                snapshot.change,
+                ParamChange::Initial(LedgerParams::of(&MockLedger::default()))
+            );
+
+            // Reopening it leaves it as it is.
+            let ledger = FileLedger::open(path).unwrap();
+            assert_eq!(ledger.version(), 0);
+            assert_eq!(fs::read_to_string(path).unwrap(), contents);
+        }
+    }
+
+    #[test]
+    fn file_ledger_rejects_non_sequential_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let mut ledger = FileLedger::open(&path).unwrap();
+        ledger.set_tau(0.25);
+        ledger.set_tau(0.5);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+
+        match FileLedger::open(&path) {
+            Err(FileLedgerError::NonSequentialVersion {
+                line,
+                found,
+                expected,
+            }) => assert_eq!((line, found, expected), (2, 2, 1)),
+            _ => panic!("opened a ledger with a missing entry"),
+        }
+
+        fs::write(&path, format!("{}\n", lines[1])).unwrap();
+        match FileLedger::open(&path) {
+            Err(FileLedgerError::NonSequentialVersion {
+                line,
+                found,
+                expected,
+            }) => assert_eq!((line, found, expected), (1, 1, 0)),
+            _ => panic!("opened a ledger without its initial parameters"),
+        }
+    }
+
+    #[test]
+    fn file_ledger_replays_from_its_initial_parameters() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let mut ledger = FileLedger::open(&path).unwrap();
+        ledger.set_tau(0.25);
+
+        // Change the initial parameters on disk, as if the ledger had been
+        // created by a version of the code with different defaults.
+        let contents = fs::read_to_string(&path).unwrap();
+        let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+        let mut snapshot: LedgerEntry = serde_json::from_str(&lines[0]).unwrap();
+        match &mut snapshot.change {
+            ParamChange::Initial(params) => params.random_walks_num = 42,
+            change => panic!("the first entry is {:?}", change),
+        }
+        lines[0] = serde_json::to_string(&snapshot).unwrap();
+        fs::write(&path, format!("{}\n", lines.join("\n"))).unwrap();
+
+        let ledger = FileLedger::open(&path).unwrap();
+        assert_eq!(ledger.initial().random_walks_num, 42);
+        assert_eq!(*ledger.as_of(0).unwrap().get_random_walks_num(), 42);
+        assert_eq!(*ledger.as_of(0).unwrap().get_tau(), 0.0);
+        assert_eq!(*ledger.get_random_walks_num(), 42);
+        assert_eq!(*ledger.get_tau(), 0.25);
+    }
+
+    #[test]
+    fn file_ledger_writers_keep_the_versions_sequential() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let mut first = FileLedger::open(&path).unwrap();
+        let mut second = FileLedger::open(&path).unwrap();
+
+        assert_eq!(first.record(ParamChange::Tau(0.25)).unwrap(), 1);
+        // The second writer picks up the change of the first one.
+        assert_eq!(second.record(ParamChange::RandomWalksNum(100)).unwrap(), 2);
+        assert_eq!(*second.get_tau(), 0.25);
+        assert_eq!(first.record(ParamChange::Tau(0.5)).unwrap(), 3);
+
+        let ledger = FileLedger::open(&path).unwrap();
+        assert_eq!(ledger.version(), 3);
+        assert_eq!(*ledger.get_tau(), 0.5);
+        assert_eq!(*ledger.get_random_walks_num(), 100);
+    }
+
+    #[test]
+    fn file_ledger_concurrent_writers_dont_clash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let writers: Vec<_> = (0..4)
+            .map(|_| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    let mut ledger = FileLedger::open(&path).unwrap();
+                    for r in 0..10 {
+                        ledger.record(ParamChange::RandomWalksNum(r)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        // Opening the ledger checks the versions are 0, 1, 2 ...
+        let ledger = FileLedger::open(&path).unwrap();
+        assert_eq!(ledger.version(), 40);
+    }
+
+    #[test]
+    fn file_ledger_skips_an_incomplete_last_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.jsonl");
+
+        let mut ledger = FileLedger::open(&path).unwrap();
+        ledger.set_tau(0.25);
+
+        // A write which didn't complete.
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"version\":2,\"times").unwrap();
+
+        let mut ledger = FileLedger::open(&path).unwrap();
+        assert_eq!(ledger.version(), 1);
+        assert_eq!(*ledger.get_tau(), 0.25);
+
+        // Recording a change drops what's left of the incomplete one.
+        assert_eq!(ledger.record(ParamChange::Tau(0.5)).unwrap(), 2);
+        let ledger = FileLedger::open(&path).unwrap();
+        assert_eq!(ledger.version(), 2);
+        assert_eq!(*ledger.get_tau(), 0.5);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 3);
+    }
+}
@@ -5,6 +5,13 @@ extern crate oscoin_graph_api as oscoin;
 extern crate petgraph;
 extern crate rand;
 
+use oscoin::{Direction, Edge};
+use rand::distributions::uniform::SampleUniform;
+use rand::distributions::WeightedError;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::ops::AddAssign;
+
 /// This is a compatibility-shim trait for things that the "official"
 /// GraphAPI trait(s) don't give us for free.
 pub trait GraphExtras: oscoin::Graph {
@@ -27,6 +34,33 @@ pub trait GraphExtras: oscoin::Graph {
 
     /// Creates a subgraph of on the nodes of `sub_nodes` of `self`.
     fn subgraph_by_nodes(&self, sub_nodes: Vec<&oscoin::Id<Self::Node>>) -> Self;
+
+    /// Picks one of the outgoing edges of the input node at random, with a
+    /// probability proportional to its weight, and returns the node it
+    /// points to, or `None` if there are no outgoing edges.
+    ///
+    /// The default implementation goes through `edges_directed`, which
+    /// allocates: graphs storing the outgoing edges contiguously should
+    /// sample straight out of them, drawing from `rng` in the same way.
+    fn choose_outgoing<R>(
+        &self,
+        node_id: &oscoin::Id<Self::Node>,
+        rng: &mut R,
+    ) -> Result<Option<&oscoin::Id<Self::Node>>, WeightedError>
+    where
+        R: Rng + ?Sized,
+        Self::Weight:
+            Default + Clone + PartialOrd + for<'x> AddAssign<&'x Self::Weight> + SampleUniform,
+    {
+        let neighbors = self.edges_directed(node_id, Direction::Outgoing);
+        match neighbors.choose_weighted(rng, |item| {
+            self.get_edge(item.id).map(|m| m.weight()).unwrap()
+        }) {
+            Ok(next_edge) => Ok(Some(next_edge.to)),
+            Err(WeightedError::NoItem) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
 }
 
 /// Little shim that rename some petgraph edge-related methods to be less
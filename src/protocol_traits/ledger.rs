@@ -1,7 +1,7 @@
 #![allow(unknown_lints)]
 #![warn(clippy::all)]
 
-use crate::types::{DampingFactors, HyperParams, Tau, R};
+use crate::types::{DampingFactors, HyperParams, MaxWalkLength, Tau, R};
 
 /// An Osrank-specific _view_ of a more general _Ledger_.
 ///
@@ -38,6 +38,11 @@ pub trait LedgerView {
     fn get_tau(&self) -> &Tau;
     fn set_tau(&mut self, new: Tau);
 
+    /// Returns the maximum number of steps a single random walk can take
+    /// before being considered a runaway walk.
+    fn get_max_walk_length(&self) -> &MaxWalkLength;
+    fn set_max_walk_length(&mut self, new: MaxWalkLength);
+
     fn get_damping_factors(&self) -> &DampingFactors;
     fn set_damping_factors(&mut self, new: DampingFactors);
 }
@@ -53,6 +58,7 @@ pub struct MockLedgerState {
     factors: DampingFactors,
     r: R,
     tau: Tau,
+    max_walk_length: MaxWalkLength,
 }
 
 impl Default for MockLedgerState {
@@ -62,6 +68,7 @@ impl Default for MockLedgerState {
             factors: DampingFactors::default(),
             r: 10,
             tau: 0.0,
+            max_walk_length: 1_000,
         }
     }
 }
@@ -91,6 +98,13 @@ impl LedgerView for MockLedger {
         self.state.tau = new
     }
 
+    fn get_max_walk_length(&self) -> &MaxWalkLength {
+        &self.state.max_walk_length
+    }
+    fn set_max_walk_length(&mut self, new: MaxWalkLength) {
+        self.state.max_walk_length = new
+    }
+
     fn get_damping_factors(&self) -> &DampingFactors {
         &self.state.factors
     }
@@ -1,3 +1,4 @@
+pub mod file_ledger;
 pub mod graph;
 pub mod ledger;
 pub mod storage;
@@ -0,0 +1,219 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
+extern crate oscoin_graph_api;
+extern crate rand;
+
+use oscoin_graph_api::{Direction, Edge, Graph, GraphObject};
+use rand::Rng;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Precomputed alias tables (à la Walker/Vose) for the outgoing edges of
+/// every node of a network, which allow a random walk to pick the next node
+/// in `O(1)`, rather than in time linear in the out-degree of the current one.
+///
+/// The tables are a snapshot of the network they have been built from, so
+/// they have to be rebuilt every time the network changes. Being read-only,
+/// they can be shared by all the threads performing the walks.
+///
+/// Edges with a zero (or an invalid) weight are never sampled, and nodes
+/// without any edge with a positive weight are treated as dangling ones.
+#[derive(Debug, Clone)]
+pub struct AliasTables<I>
+where
+    I: Eq + Hash,
+{
+    /// The range of `targets`, `probs` and `aliases` holding the table of
+    /// each node.
+    ranges: HashMap<I, (usize, usize)>,
+    targets: Vec<I>,
+    probs: Vec<f64>,
+    /// The aliases, as offsets from the start of the table of the node.
+    aliases: Vec<usize>,
+}
+
+impl<I> AliasTables<I>
+where
+    I: Clone + Eq + Hash,
+{
+    /// Builds the alias tables for all the nodes of the input network.
+    pub fn new<G>(network: &G) -> Self
+    where
+        G: Graph,
+        G::Node: GraphObject<Id = I>,
+        G::Weight: Into<f64>,
+    {
+        let mut tables = AliasTables {
+            ranges: HashMap::new(),
+            targets: Vec::new(),
+            probs: Vec::new(),
+            aliases: Vec::new(),
+        };
+
+        for node in network.nodes() {
+            let mut weights = Vec::new();
+            let mut targets = Vec::new();
+
+            for edge_ref in network.edges_directed(node.id(), Direction::Outgoing) {
+                let weight: f64 = match network.get_edge(edge_ref.id) {
+                    None => continue,
+                    Some(edge) => edge.weight().into(),
+                };
+                if weight.is_finite() && weight > 0.0 {
+                    weights.push(weight);
+                    targets.push(edge_ref.to.clone());
+                }
+            }
+
+            if weights.is_empty() {
+                continue;
+            }
+
+            let (probs, aliases) = vose(&weights);
+            let start = tables.targets.len();
+            tables.targets.extend(targets);
+            tables.probs.extend(probs);
+            tables.aliases.extend(aliases);
+            tables
+                .ranges
+                .insert(node.id().clone(), (start, tables.targets.len()));
+        }
+
+        tables
+    }
+
+    /// Picks one of the targets of the outgoing edges of the input node,
+    /// proportionally to the weight of the edges. Returns `None` if the node
+    /// is dangling or is not part of the network.
+    pub fn sample<R>(&self, node_id: &I, rng: &mut R) -> Option<&I>
+    where
+        R: Rng + ?Sized,
+    {
+        let (start, end) = *self.ranges.get(node_id)?;
+        let column = rng.gen_range(0, end - start);
+        let ix = if rng.gen::<f64>() < self.probs[start + column] {
+            column
+        } else {
+            self.aliases[start + column]
+        };
+        Some(&self.targets[start + ix])
+    }
+}
+
+/// Builds a single alias table out of the input (positive) weights, using
+/// Vose's algorithm. Returns, for each column, the probability of picking
+/// the column itself rather than its alias, alongside the aliases.
+fn vose(weights: &[f64]) -> (Vec<f64>, Vec<usize>) {
+    let n = weights.len();
+    let total: f64 = weights.iter().sum();
+
+    let mut scaled: Vec<f64> = weights.iter().map(|w| w * n as f64 / total).collect();
+    let mut probs = vec![1.0; n];
+    let mut aliases: Vec<usize> = (0..n).collect();
+
+    let (mut small, mut large): (Vec<usize>, Vec<usize>) = (0..n).partition(|&i| scaled[i] < 1.0);
+
+    while let (Some(s), Some(l)) = (small.pop(), large.pop()) {
+        probs[s] = scaled[s];
+        aliases[s] = l;
+        scaled[l] += scaled[s] - 1.0;
+        if scaled[l] < 1.0 {
+            small.push(l);
+        } else {
+            large.push(l);
+        }
+    }
+
+    // Whatever is left has (up to rounding errors) a probability of 1.
+    (probs, aliases)
+}
+
+#[cfg(test)]
+mod tests {
+
+    extern crate rand_xoshiro;
+
+    use super::*;
+    use crate::algorithm::Normalised;
+    use crate::types::mock::MockNetwork;
+    use crate::types::network::{Artifact, DependencyType, Network};
+    use oscoin_graph_api::GraphWriter;
+    use quickcheck::TestResult;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[quickcheck]
+    // Every column of a table gives back exactly its share of the total
+    // weight, split between itself and the columns aliasing it.
+    fn vose_preserves_the_weights(weights: Vec<u8>) -> TestResult {
+        let weights: Vec<f64> = weights
+            .into_iter()
+            .filter(|w| *w > 0)
+            .map(f64::from)
+            .collect();
+        if weights.is_empty() {
+            return TestResult::discard();
+        }
+
+        let n = weights.len() as f64;
+        let total: f64 = weights.iter().sum();
+        let (probs, aliases) = vose(&weights);
+
+        let mut shares: Vec<f64> = probs.iter().map(|p| p / n).collect();
+        for (column, alias) in aliases.iter().enumerate() {
+            shares[*alias] += (1.0 - probs[column]) / n;
+        }
+
+        TestResult::from_bool(
+            shares
+                .iter()
+                .zip(weights.iter())
+                .all(|(share, w)| (share - w / total).abs() < 1e-9),
+        )
+    }
+
+    #[test]
+    fn sample_follows_the_weights() {
+        let mut network: Normalised<MockNetwork> = Normalised::new(Network::default());
+
+        for node in &["hub", "a", "b", "c", "dangling"] {
+            let a = Artifact::new_project(node.to_string());
+            network.add_node(a.id().clone(), a.data().clone());
+        }
+        let edges = [("hub", "a", 0.5), ("hub", "b", 0.3), ("hub", "c", 0.2)];
+        for (ix, edge) in edges.iter().enumerate() {
+            network.add_edge(
+                ix,
+                &edge.0.to_string(),
+                &edge.1.to_string(),
+                edge.2,
+                DependencyType::Influence(edge.2),
+            )
+        }
+
+        let tables = AliasTables::new(&network);
+        let mut rng = Xoshiro256StarStar::seed_from_u64(42);
+
+        assert_eq!(tables.sample(&"dangling".to_string(), &mut rng), None);
+        assert_eq!(tables.sample(&"foo".to_string(), &mut rng), None);
+
+        let samples: u32 = 100_000;
+        let mut counts: HashMap<&String, usize> = HashMap::new();
+        for _ in 0..samples {
+            let next = tables.sample(&"hub".to_string(), &mut rng).unwrap();
+            *counts.entry(next).or_insert(0) += 1;
+        }
+
+        for (_, target, weight) in &edges {
+            let freq = counts[&target.to_string()] as f64 / f64::from(samples);
+            assert!(
+                (freq - weight).abs() < 0.01,
+                "{} sampled with frequency {}, expected {}",
+                target,
+                freq,
+                weight
+            );
+        }
+    }
+}
@@ -0,0 +1,594 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
+extern crate oscoin_graph_api;
+extern crate rand;
+extern crate sprs;
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::AddAssign;
+
+use super::network::{Artifact, ArtifactType, Dependency, DependencyType};
+use crate::linalg::SparseMatrix;
+use crate::protocol_traits::graph::GraphExtras;
+use oscoin_graph_api::{Data, Direction, Edge, EdgeRefs, Edges, Graph, GraphObject, Id, Nodes};
+use rand::distributions::uniform::SampleUniform;
+use rand::distributions::{Distribution, WeightedError, WeightedIndex};
+use rand::Rng;
+
+/// The index of a node inside a `CompactNetwork`. Using a `u32` rather than
+/// a `usize` halves the size of the adjacency arrays on 64 bits platforms,
+/// while still allowing for networks with billions of nodes.
+pub type NodeIx = u32;
+
+/// A read-only network stored in compressed sparse row (CSR) format.
+///
+/// Node ids are interned into contiguous `NodeIx` indexes, and the outgoing
+/// edges of every node live in a contiguous slice, which makes the random
+/// walks (which only ever follow outgoing edges) cache-friendly and avoids
+/// the per-node and per-edge overhead of a `Network`.
+///
+/// The incoming edges are indexed as well, in compressed sparse column (CSC)
+/// format, as the positions of the edges targeting every node, so that
+/// `edges_directed` and `neighbors` only visit the edges of the input node.
+///
+/// A `CompactNetwork` can be built out of any other network via
+/// `CompactNetwork::from_graph`, or straight out of the CSV files via
+/// `importers::csv::import_compact_network`.
+#[derive(Clone, Debug)]
+pub struct CompactNetwork<W: Clone> {
+    nodes: Vec<Artifact<String>>,
+    node_ixs: HashMap<String, NodeIx>,
+    /// The outgoing edges of the node `n` are `edges[offsets[n]..offsets[n + 1]]`.
+    offsets: Vec<usize>,
+    edges: Vec<Dependency<usize, W>>,
+    targets: Vec<NodeIx>,
+    /// The incoming edges of the node `n` are the ones at the positions
+    /// `in_edges[in_offsets[n]..in_offsets[n + 1]]`, coming from the nodes
+    /// `in_sources[in_offsets[n]..in_offsets[n + 1]]`.
+    in_offsets: Vec<usize>,
+    in_edges: Vec<usize>,
+    in_sources: Vec<NodeIx>,
+    edge_ixs: HashMap<usize, usize>,
+}
+
+impl<W> Default for CompactNetwork<W>
+where
+    W: Clone,
+{
+    fn default() -> Self {
+        CompactNetwork {
+            nodes: Vec::new(),
+            node_ixs: HashMap::new(),
+            offsets: vec![0],
+            edges: Vec::new(),
+            targets: Vec::new(),
+            in_offsets: vec![0],
+            in_edges: Vec::new(),
+            in_sources: Vec::new(),
+            edge_ixs: HashMap::new(),
+        }
+    }
+}
+
+impl<W> CompactNetwork<W>
+where
+    W: Clone,
+{
+    /// Builds a `CompactNetwork` out of its nodes and of a list of
+    /// `(source, target, edge)` triples, where `source` and `target` are
+    /// indexes into `nodes`. The edges don't need to be sorted: they are
+    /// bucketed by source, preserving their relative order, and then indexed
+    /// by target.
+    ///
+    /// Nodes with an id which has been already seen are ignored, and edges
+    /// pointing to them are redirected to the first node with that id.
+    fn from_parts(
+        nodes: Vec<Artifact<String>>,
+        edges: Vec<(usize, usize, Dependency<usize, W>)>,
+    ) -> Self {
+        let mut network = CompactNetwork::default();
+
+        // Maps the input indexes to the interned ones.
+        let mut interned: Vec<NodeIx> = Vec::with_capacity(nodes.len());
+
+        for node in nodes {
+            let next_ix = network.nodes.len() as NodeIx;
+            let ix = *network.node_ixs.entry(node.id().clone()).or_insert(next_ix);
+            if ix == next_ix {
+                network.nodes.push(node);
+            }
+            interned.push(ix);
+        }
+
+        // Counting sort of the edges by source.
+        let mut offsets = vec![0; network.nodes.len() + 1];
+        for (source, _, _) in &edges {
+            offsets[interned[*source] as usize + 1] += 1;
+        }
+        for ix in 1..offsets.len() {
+            offsets[ix] += offsets[ix - 1];
+        }
+
+        let mut next_slot = offsets.clone();
+        let mut slots: Vec<Option<(NodeIx, Dependency<usize, W>)>> = vec![None; edges.len()];
+        for (source, target, edge) in edges {
+            let slot = &mut next_slot[interned[source] as usize];
+            slots[*slot] = Some((interned[target], edge));
+            *slot += 1;
+        }
+
+        network.edges.reserve_exact(slots.len());
+        network.targets.reserve_exact(slots.len());
+        for (target, edge) in slots.into_iter().flatten() {
+            network.edge_ixs.insert(*edge.id(), network.edges.len());
+            network.edges.push(edge);
+            network.targets.push(target);
+        }
+        network.offsets = offsets;
+
+        // Counting sort of the positions of the edges by target, which keeps
+        // the incoming edges of every node sorted by source.
+        let mut in_offsets = vec![0; network.nodes.len() + 1];
+        for target in &network.targets {
+            in_offsets[*target as usize + 1] += 1;
+        }
+        for ix in 1..in_offsets.len() {
+            in_offsets[ix] += in_offsets[ix - 1];
+        }
+
+        let mut next_slot = in_offsets.clone();
+        network.in_edges = vec![0; network.edges.len()];
+        network.in_sources = vec![0; network.edges.len()];
+        for source in 0..network.nodes.len() as NodeIx {
+            for eix in network.out_range(source) {
+                let slot = &mut next_slot[network.targets[eix] as usize];
+                network.in_edges[*slot] = eix;
+                network.in_sources[*slot] = source;
+                *slot += 1;
+            }
+        }
+        network.in_offsets = in_offsets;
+
+        network
+    }
+
+    /// Builds a `CompactNetwork` out of any network with the same node and
+    /// edge types, preserving the order of the nodes and of the outgoing
+    /// edges of each node. In particular, walks over the two networks which
+    /// start from the same seed visit the same nodes.
+    pub fn from_graph<G>(graph: &G) -> Self
+    where
+        G: Graph<
+            Node = Artifact<String>,
+            Edge = Dependency<usize, W>,
+            NodeData = ArtifactType,
+            EdgeData = DependencyType<W>,
+        >,
+    {
+        let nodes: Vec<Artifact<String>> = graph.nodes().cloned().collect();
+        let node_ixs: HashMap<&String, usize> = nodes
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(ix, n)| (n.id(), ix))
+            .collect();
+
+        let mut edges = Vec::new();
+        let mut visited = BTreeSet::new();
+
+        for (source, node) in nodes.iter().enumerate() {
+            if !visited.insert(node.id()) {
+                continue;
+            }
+            for edge_ref in graph.edges_directed(node.id(), Direction::Outgoing) {
+                if let (Some(target), Some(edge)) =
+                    (node_ixs.get(edge_ref.to), graph.get_edge(edge_ref.id))
+                {
+                    edges.push((source, *target, edge.clone()));
+                }
+            }
+        }
+
+        Self::from_parts(nodes, edges)
+    }
+
+    /// Builds a `CompactNetwork` out of an adjacency matrix, where the
+    /// i-th row and column correspond to the i-th node of `nodes`. Every
+    /// non-zero entry becomes an `Influence` edge, and the edges are numbered
+    /// in the order the matrix stores them, like `import_network` does.
+    pub fn from_adjacency_matrix(nodes: Vec<Artifact<String>>, matrix: &SparseMatrix<W>) -> Self {
+        let edges = matrix
+            .iter()
+            .enumerate()
+            .map(|(edge_id, (weight, (source, target)))| {
+                let edge = Dependency::new(edge_id, DependencyType::Influence(weight.clone()));
+                (source, target, edge)
+            })
+            .collect();
+
+        Self::from_parts(nodes, edges)
+    }
+
+    /// Returns the index of the node with the given id, if any.
+    pub fn node_index(&self, id: &str) -> Option<NodeIx> {
+        self.node_ixs.get(id).cloned()
+    }
+
+    /// Returns the node at the given index.
+    pub fn node_at(&self, ix: NodeIx) -> &Artifact<String> {
+        &self.nodes[ix as usize]
+    }
+
+    /// Returns the outgoing edges of the node at the given index.
+    pub fn out_edges(&self, ix: NodeIx) -> &[Dependency<usize, W>] {
+        &self.edges[self.out_range(ix)]
+    }
+
+    /// Returns the targets of the outgoing edges of the node at the given
+    /// index, in the same order as `out_edges`.
+    pub fn out_targets(&self, ix: NodeIx) -> &[NodeIx] {
+        &self.targets[self.out_range(ix)]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.edges.is_empty()
+    }
+
+    /// Returns the sources of the incoming edges of the node at the given
+    /// index, sorted.
+    pub fn in_sources(&self, ix: NodeIx) -> &[NodeIx] {
+        &self.in_sources[self.in_range(ix)]
+    }
+
+    fn out_range(&self, ix: NodeIx) -> std::ops::Range<usize> {
+        self.offsets[ix as usize]..self.offsets[ix as usize + 1]
+    }
+
+    fn in_range(&self, ix: NodeIx) -> std::ops::Range<usize> {
+        self.in_offsets[ix as usize]..self.in_offsets[ix as usize + 1]
+    }
+
+    fn edge_ref<'a>(
+        &'a self,
+        source: NodeIx,
+        target: NodeIx,
+        edge: &'a Dependency<usize, W>,
+    ) -> oscoin_graph_api::EdgeRef<'a, String, usize> {
+        oscoin_graph_api::EdgeRef {
+            from: self.node_at(source).id(),
+            to: self.node_at(target).id(),
+            id: edge.id(),
+        }
+    }
+}
+
+impl<W> Graph for CompactNetwork<W>
+where
+    W: Clone,
+{
+    type Node = Artifact<String>;
+    type Edge = Dependency<usize, W>;
+    type Weight = W;
+    type NodeData = ArtifactType;
+    type EdgeData = DependencyType<W>;
+
+    fn neighbors(&self, node_id: &Id<Self::Node>) -> Nodes<Self::Node> {
+        let mut nodes = Vec::new();
+        let mut unique_node_ixs = BTreeSet::new();
+
+        if let Some(nix) = self.node_index(node_id) {
+            unique_node_ixs.insert(nix); // avoids having the input as neighbor.
+
+            // The sources of the incoming edges are sorted: visiting the ones
+            // before the input node first yields the neighbors in the order
+            // of a scan of all the edges.
+            let sources = self.in_sources(nix);
+            let split = sources.iter().take_while(|s| **s < nix).count();
+            let others = sources[..split]
+                .iter()
+                .chain(self.out_targets(nix))
+                .chain(&sources[split..]);
+
+            for other in others {
+                if unique_node_ixs.insert(*other) {
+                    nodes.push(self.node_at(*other));
+                }
+            }
+        }
+
+        Nodes {
+            range: nodes.into_iter(),
+        }
+    }
+
+    fn get_node(&self, id: &Id<Self::Node>) -> Option<&Self::Node> {
+        self.node_index(id).map(|nix| self.node_at(nix))
+    }
+
+    fn get_edge(&self, id: &Id<Self::Edge>) -> Option<&Self::Edge> {
+        self.edge_ixs.get(id).map(|eix| &self.edges[*eix])
+    }
+
+    fn nodes(&self) -> Nodes<Self::Node> {
+        Nodes {
+            range: self.nodes.iter().collect::<Vec<_>>().into_iter(),
+        }
+    }
+
+    fn edges(&self, node: &Id<Self::Node>) -> Edges<Self::Edge> {
+        let edges = match self.node_index(node) {
+            None => Vec::new(),
+            Some(nix) => self.out_edges(nix).iter().collect(),
+        };
+        Edges {
+            range: edges.into_iter(),
+        }
+    }
+
+    fn edges_directed(
+        &self,
+        node_id: &Id<Self::Node>,
+        dir: Direction,
+    ) -> EdgeRefs<Id<Self::Node>, Id<Self::Edge>> {
+        let nix = match self.node_index(node_id) {
+            None => return Vec::new(),
+            Some(nix) => nix,
+        };
+
+        match dir {
+            Direction::Outgoing => self
+                .out_targets(nix)
+                .iter()
+                .zip(self.out_edges(nix))
+                .map(|(target, edge)| self.edge_ref(nix, *target, edge))
+                .collect(),
+            Direction::Incoming => self
+                .in_sources(nix)
+                .iter()
+                .zip(&self.in_edges[self.in_range(nix)])
+                .map(|(source, eix)| self.edge_ref(*source, nix, &self.edges[*eix]))
+                .collect(),
+        }
+    }
+}
+
+impl<W> GraphExtras for CompactNetwork<W>
+where
+    W: Clone,
+{
+    fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn lookup_node_metadata(&self, node_id: &Id<Self::Node>) -> Option<&Data<Self::Node>> {
+        self.get_node(node_id).map(|n| n.data())
+    }
+
+    fn lookup_edge_metadata(&self, edge_id: &Id<Self::Edge>) -> Option<&Data<Self::Edge>> {
+        self.get_edge(edge_id).map(|e| e.data())
+    }
+
+    fn subgraph_by_nodes(&self, sub_nodes: Vec<&String>) -> Self {
+        // Maps the indexes in `self` to the ones in `nodes`.
+        let mut sub_ixs: HashMap<NodeIx, usize> = HashMap::new();
+        let mut nodes = Vec::new();
+        let mut nixs = Vec::new();
+
+        for id in sub_nodes {
+            if let Some(nix) = self.node_index(id) {
+                if let Entry::Vacant(entry) = sub_ixs.entry(nix) {
+                    entry.insert(nodes.len());
+                    nodes.push(self.node_at(nix).clone());
+                    nixs.push(nix);
+                }
+            }
+        }
+
+        let mut edges = Vec::new();
+        for (source, nix) in nixs.into_iter().enumerate() {
+            for (target, edge) in self.out_targets(nix).iter().zip(self.out_edges(nix)) {
+                if let Some(target) = sub_ixs.get(target) {
+                    edges.push((source, *target, edge.clone()));
+                }
+            }
+        }
+
+        Self::from_parts(nodes, edges)
+    }
+
+    fn choose_outgoing<R>(
+        &self,
+        node_id: &Id<Self::Node>,
+        rng: &mut R,
+    ) -> Result<Option<&Id<Self::Node>>, WeightedError>
+    where
+        R: Rng + ?Sized,
+        W: Default + Clone + PartialOrd + for<'x> AddAssign<&'x W> + SampleUniform,
+    {
+        let nix = match self.node_index(node_id) {
+            None => return Ok(None),
+            Some(nix) => nix,
+        };
+
+        // Same distribution as the `choose_weighted` of the default
+        // implementation, so that the walks over a `CompactNetwork` and over
+        // the network it's been built from stay the same.
+        match WeightedIndex::new(self.out_edges(nix).iter().map(|e| e.weight())) {
+            Ok(index) => Ok(Some(
+                self.node_at(self.out_targets(nix)[index.sample(rng)]).id(),
+            )),
+            Err(WeightedError::NoItem) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    extern crate quickcheck;
+    extern crate rand;
+    extern crate rand_xoshiro;
+
+    use super::*;
+    use crate::algorithm::naive::osrank_naive;
+    use crate::algorithm::{Normalised, OsrankOptions};
+    use crate::protocol_traits::ledger::{LedgerView, MockLedger};
+    use crate::types::mock::{MockAnnotator, MockNetwork};
+    use crate::types::network::Network;
+    use oscoin_graph_api::GraphWriter;
+    use quickcheck::TestResult;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    fn outgoing<G>(graph: &G, id: &String) -> Vec<(String, usize)>
+    where
+        G: Graph<Node = Artifact<String>, Edge = Dependency<usize, f64>>,
+    {
+        graph
+            .edges_directed(id, Direction::Outgoing)
+            .into_iter()
+            .map(|e| (e.to.clone(), *e.id))
+            .collect()
+    }
+
+    fn incoming<G>(graph: &G, id: &String) -> Vec<(String, usize)>
+    where
+        G: Graph<Node = Artifact<String>, Edge = Dependency<usize, f64>>,
+    {
+        let mut edges = graph
+            .edges_directed(id, Direction::Incoming)
+            .into_iter()
+            .map(|e| (e.from.clone(), *e.id))
+            .collect::<Vec<_>>();
+        edges.sort();
+        edges
+    }
+
+    fn neighbors<G>(graph: &G, id: &String) -> Vec<String>
+    where
+        G: Graph<Node = Artifact<String>>,
+    {
+        let mut nodes = graph
+            .neighbors(id)
+            .map(|n| n.id().clone())
+            .collect::<Vec<_>>();
+        nodes.sort();
+        nodes
+    }
+
+    fn has_unique_nodes(network: &MockNetwork) -> bool {
+        let ids = network.nodes().map(|n| n.id()).collect::<BTreeSet<_>>();
+        ids.len() == network.node_count()
+    }
+
+    #[quickcheck]
+    // The compact network exposes the same nodes and edges of the network it
+    // has been built from.
+    fn from_graph_preserves_the_network(network: MockNetwork) -> TestResult {
+        if !has_unique_nodes(&network) {
+            return TestResult::discard();
+        }
+
+        let compact = CompactNetwork::from_graph(&network);
+
+        let ids = network.nodes().map(|n| n.id()).collect::<Vec<_>>();
+        let compact_ids = compact.nodes().map(|n| n.id()).collect::<Vec<_>>();
+        if ids != compact_ids || compact.edge_count() != network.edge_count() {
+            return TestResult::failed();
+        }
+
+        TestResult::from_bool(ids.iter().all(|id| {
+            outgoing(&network, id) == outgoing(&compact, id)
+                && incoming(&network, id) == incoming(&compact, id)
+                && neighbors(&network, id) == neighbors(&compact, id)
+                && compact.lookup_node_metadata(id) == network.lookup_node_metadata(id)
+        }))
+    }
+
+    #[test]
+    fn subgraph_by_nodes_keeps_the_edges_within() {
+        let mut network = Network::default();
+
+        for node in &["p1", "p2", "p3"] {
+            let a = Artifact::new_project(node.to_string());
+            network.add_node(a.id().clone(), a.data().clone());
+        }
+        let edges = [("p1", "p2"), ("p1", "p3"), ("p3", "p1"), ("p2", "p2")];
+        for (ix, edge) in edges.iter().enumerate() {
+            network.add_edge(
+                ix,
+                &edge.0.to_string(),
+                &edge.1.to_string(),
+                1.0,
+                DependencyType::Influence(1.0),
+            )
+        }
+
+        let compact = CompactNetwork::from_graph(&network);
+        let p1 = "p1".to_string();
+        let p3 = "p3".to_string();
+        let sub = compact.subgraph_by_nodes(vec![&p3, &p1, &"bar".to_string()]);
+
+        assert_eq!(sub.node_count(), 2);
+        assert_eq!(sub.edge_count(), 2);
+        assert_eq!(outgoing(&sub, &p1), vec![("p3".to_string(), 1)]);
+        assert_eq!(incoming(&sub, &p1), vec![("p3".to_string(), 2)]);
+        assert_eq!(
+            sub.neighbors(&p1).map(|n| n.id()).collect::<Vec<_>>(),
+            vec!["p3"]
+        );
+        assert!(compact.subgraph_by_nodes(vec![]).is_empty());
+    }
+
+    #[quickcheck]
+    // Walking the compact network gives exactly the same ranks as walking
+    // the network it has been built from, given the same seed.
+    fn osrank_naive_on_compact_network_is_the_same(network: MockNetwork) -> TestResult {
+        if !has_unique_nodes(&network) {
+            return TestResult::discard();
+        }
+
+        let mut ledger = MockLedger::default();
+        ledger.set_random_walks_num(5);
+        let rng = Xoshiro256StarStar::from_seed([7; 32]);
+
+        let network = Normalised::new(network);
+        let compact = Normalised::new(CompactNetwork::from_graph(&network));
+
+        let mut annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+        let mut compact_annotator: MockAnnotator<Normalised<MockNetwork>> = Default::default();
+        let to_annotation = |node: &Artifact<String>, estimate: crate::types::RankEstimate| {
+            (node.id().clone(), estimate.rank)
+        };
+
+        let result = osrank_naive(
+            None,
+            OsrankOptions::default(),
+            &network,
+            &mut annotator,
+            &ledger,
+            &rng,
+            &to_annotation,
+        );
+        let compact_result = osrank_naive(
+            None,
+            OsrankOptions::default(),
+            &compact,
+            &mut compact_annotator,
+            &ledger,
+            &rng,
+            &to_annotation,
+        );
+
+        TestResult::from_bool(
+            result == compact_result && annotator.annotator == compact_annotator.annotator,
+        )
+    }
+}
@@ -3,10 +3,10 @@
 
 extern crate oscoin_graph_api;
 
-use crate::exporters::csv::{export_rank_to_csv, CsvExporterError};
-use crate::exporters::Exporter;
+use crate::exporters::csv::{export_rank_estimates_to_csv, export_rank_to_csv};
+use crate::exporters::{ExportError, Exporter};
 use crate::types::network::{Artifact, DependencyType, Network};
-use crate::types::Osrank;
+use crate::types::{Osrank, RankEstimate};
 use crate::util::quickcheck::frequency;
 use fraction::ToPrimitive;
 use oscoin_graph_api::{Graph, GraphAnnotator, GraphObject, GraphWriter};
@@ -14,8 +14,9 @@ use quickcheck::{Arbitrary, Gen};
 use rand::Rng;
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::io::Write;
 
-pub type MockNetwork = Network<f64>;
+pub type MockNetwork = Network<String, usize, f64>;
 
 /// Equivalent to `newtype Mock a = Mock a` in Haskell.
 ///
@@ -137,28 +138,69 @@ impl Default for MockAnnotator<MockNetwork> {
     }
 }
 
-pub struct MockAnnotatorCsvExporter<'a> {
+/// A `MockAnnotator` which also keeps the standard error of each rank.
+pub type MockEstimateAnnotator<G> =
+    KeyValueAnnotator<<<G as Graph>::Node as GraphObject>::Id, RankEstimate>;
+
+impl Default for MockEstimateAnnotator<MockNetwork> {
+    fn default() -> Self {
+        KeyValueAnnotator {
+            annotator: Default::default(),
+        }
+    }
+}
+
+/// Exports a `MockAnnotator` into a `.csv`, written into `out`.
+pub struct MockAnnotatorCsvExporter<W> {
     pub annotator: MockAnnotator<MockNetwork>,
-    pub out_path: &'a str,
+    pub out: W,
 }
 
-impl<'a> MockAnnotatorCsvExporter<'a> {
-    pub fn new(annotator: MockAnnotator<MockNetwork>, out_path: &'a str) -> Self {
-        MockAnnotatorCsvExporter {
-            annotator,
-            out_path,
-        }
+impl<W> MockAnnotatorCsvExporter<W> {
+    pub fn new(annotator: MockAnnotator<MockNetwork>, out: W) -> Self {
+        MockAnnotatorCsvExporter { annotator, out }
     }
 }
 
-impl<'a> Exporter for MockAnnotatorCsvExporter<'a> {
-    type ExporterOutput = ();
-    type ExporterError = CsvExporterError;
-    fn export(self) -> Result<Self::ExporterOutput, Self::ExporterError> {
+impl<W> Exporter for MockAnnotatorCsvExporter<W>
+where
+    W: Write,
+{
+    type ExporterOutput = W;
+    type ExporterError = ExportError;
+    fn export(mut self) -> Result<Self::ExporterOutput, Self::ExporterError> {
         export_rank_to_csv(
             self.annotator.annotator.into_iter(),
             Box::new(|v: Osrank| v.to_f64().unwrap_or(0.0)),
-            self.out_path,
-        )
+            &mut self.out,
+        )?;
+        Ok(self.out)
+    }
+}
+
+/// Exports a `MockEstimateAnnotator` into a `.csv`, written into `out`,
+/// including the confidence interval spanning `z` standard errors around
+/// each rank.
+pub struct MockEstimateAnnotatorCsvExporter<W> {
+    pub annotator: MockEstimateAnnotator<MockNetwork>,
+    pub z: f64,
+    pub out: W,
+}
+
+impl<W> MockEstimateAnnotatorCsvExporter<W> {
+    pub fn new(annotator: MockEstimateAnnotator<MockNetwork>, z: f64, out: W) -> Self {
+        MockEstimateAnnotatorCsvExporter { annotator, z, out }
+    }
+}
+
+impl<W> Exporter for MockEstimateAnnotatorCsvExporter<W>
+where
+    W: Write,
+{
+    type ExporterOutput = W;
+    type ExporterError = ExportError;
+    fn export(mut self) -> Result<Self::ExporterOutput, Self::ExporterError> {
+        export_rank_estimates_to_csv(self.annotator.annotator.into_iter(), self.z, &mut self.out)?;
+        Ok(self.out)
     }
 }
@@ -4,20 +4,129 @@
 extern crate fraction;
 extern crate num_traits;
 extern crate petgraph;
+extern crate serde;
 
-use fraction::{Fraction, GenericFraction};
+use fraction::{Fraction, GenericFraction, ToPrimitive};
 use num_traits::{Num, One, Signed, Zero};
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 use std::ops::{Div, Mul, Rem};
 
+use self::network::{ArtifactType, DependencyType};
+
+pub mod alias;
+pub mod compact;
 pub mod mock;
 pub mod network;
+pub mod snapshot;
 pub mod walk;
 
 /// The `Osrank` score, modeled as a fraction. It has a default value of `Zero`,
 /// in case no `Osrank` is provided/calculated yet.
 pub type Osrank = Fraction;
 
+/// An `Osrank` alongside the standard error of its Monte Carlo estimate,
+/// which tells how much the rank would fluctuate if the walks were redone,
+/// and the raw number of visits it was estimated from. Exact algorithms
+/// report a standard error of 0, and no visits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RankEstimate {
+    pub rank: Osrank,
+    pub std_error: f64,
+    pub visits: Option<usize>,
+}
+
+impl RankEstimate {
+    /// Creates a `RankEstimate` for a rank which carries no uncertainty.
+    pub fn exact(rank: Osrank) -> Self {
+        RankEstimate {
+            rank,
+            std_error: 0.0,
+            visits: None,
+        }
+    }
+
+    /// Returns the `(lower, upper)` bounds of the confidence interval which
+    /// spans `z` standard errors on each side of the rank (e.g. `1.96` for a
+    /// 95% interval). The lower bound never goes below 0.
+    ///
+    /// ```
+    /// use osrank::types::{Osrank, RankEstimate};
+    ///
+    /// let estimate = RankEstimate {
+    ///     rank: Osrank::new(1u64, 4u64),
+    ///     std_error: 0.1,
+    ///     visits: None,
+    /// };
+    ///
+    /// let (lower, upper) = estimate.interval(2.0);
+    /// assert!((lower - 0.05).abs() < 1e-12);
+    /// assert!((upper - 0.45).abs() < 1e-12);
+    /// assert_eq!(estimate.interval(3.0).0, 0.0);
+    /// ```
+    pub fn interval(&self, z: f64) -> (f64, f64) {
+        let rank = self.rank.to_f64().unwrap_or(0.0);
+        let margin = z * self.std_error;
+        ((rank - margin).max(0.0), rank + margin)
+    }
+}
+
+/// The largest denominator used by `approximate_fraction` when converting the
+/// damping factors into exact fractions. Any damping factor with at most six
+/// decimal digits gets converted exactly.
+pub const MAX_DAMPING_DENOMINATOR: u64 = 1_000_000;
+
+/// Converts a non-negative `f64` into the closest `Fraction` whose
+/// denominator doesn't exceed `max_denominator`, using continued fractions.
+///
+/// Unlike `Fraction::from(f64)`, this keeps both the numerator and the
+/// denominator small, which makes it safe to use in further arithmetic.
+///
+/// ```
+/// use osrank::types::approximate_fraction;
+/// use fraction::Fraction;
+///
+/// assert_eq!(approximate_fraction(0.85, 1_000), Fraction::new(17u64, 20u64));
+/// assert_eq!(approximate_fraction(0.875, 1_000), Fraction::new(7u64, 8u64));
+/// assert_eq!(approximate_fraction(1.0 / 3.0, 1_000), Fraction::new(1u64, 3u64));
+/// ```
+pub fn approximate_fraction(value: f64, max_denominator: u64) -> Fraction {
+    if value.is_nan() || value <= 0.0 {
+        return Fraction::zero();
+    }
+
+    // The last two convergents, h/k, starting from 0/1 and 1/0.
+    let (mut h0, mut k0, mut h1, mut k1) = (0u64, 1u64, 1u64, 0u64);
+    let mut rest = value;
+
+    loop {
+        let a = rest.floor();
+        let a_u64 = a as u64;
+        let k2 = a_u64.saturating_mul(k1).saturating_add(k0);
+        if k2 > max_denominator {
+            break;
+        }
+        let h2 = a_u64.saturating_mul(h1).saturating_add(h0);
+        h0 = h1;
+        k0 = k1;
+        h1 = h2;
+        k1 = k2;
+
+        let remainder = rest - a;
+        if remainder < 1e-9 {
+            break;
+        }
+        rest = 1.0 / remainder;
+    }
+
+    if k1 == 0 {
+        // Even the first convergent exceeded `max_denominator`.
+        Fraction::new(value.round() as u64, 1u64)
+    } else {
+        Fraction::new(h1, k1)
+    }
+}
+
 /// The number of random walks the algorithm has to perform for each node.
 pub type R = u32;
 
@@ -27,6 +136,12 @@ pub type R = u32;
 /// hostile behaviours.
 pub type Tau = f64;
 
+/// The maximum number of steps a single random walk is allowed to take. It
+/// acts as a safeguard against walks which would otherwise run (almost)
+/// forever, for example when the damping factor is very close to 1.0 or when
+/// the walk gets trapped into a self-loop.
+pub type MaxWalkLength = u32;
+
 #[derive(Clone, Copy, PartialEq, Add, Sub, Neg, PartialOrd)]
 pub struct Weight {
     get_weight: GenericFraction<u32>,
@@ -68,6 +183,47 @@ impl Default for Weight {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+struct WeightRepr {
+    numer: u32,
+    denom: u32,
+}
+
+/// A `Weight` is (de)serialised as its numerator and denominator, so that it
+/// round-trips without losing any precision.
+impl Serialize for Weight {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match (self.get_weight.numer(), self.get_weight.denom()) {
+            (Some(numer), Some(denom)) => WeightRepr {
+                numer: *numer,
+                denom: *denom,
+            }
+            .serialize(serializer),
+            _ => Err(ser::Error::custom(format!(
+                "the weight {} is not a fraction",
+                self
+            ))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Weight {
+    fn deserialize<D>(deserializer: D) -> Result<Weight, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let repr = WeightRepr::deserialize(deserializer)?;
+        if repr.denom == 0 {
+            Err(de::Error::custom("the denominator of a weight can't be 0"))
+        } else {
+            Ok(Weight::new(repr.numer, repr.denom))
+        }
+    }
+}
+
 impl std::convert::From<Weight> for f64 {
     fn from(w: Weight) -> Self {
         w.as_f64().unwrap()
@@ -158,7 +314,7 @@ impl Zero for Weight {
 }
 
 /// The hyperparams from the paper, which are used to weight the edges.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct HyperParams {
     pub contrib_factor: Weight,
     pub contrib_prime_factor: Weight,
@@ -180,7 +336,24 @@ impl Default for HyperParams {
     }
 }
 
+impl HyperParams {
+    /// Returns the factor which applies to an edge of the given
+    /// `DependencyType`, or `None` for `Influence` edges, which are the
+    /// result of a normalisation and carry no factor.
+    pub fn factor_for<W: Clone>(&self, dependency_type: &DependencyType<W>) -> Option<Weight> {
+        match dependency_type {
+            DependencyType::Contrib(_) => Some(self.contrib_factor),
+            DependencyType::ContribPrime(_) => Some(self.contrib_prime_factor),
+            DependencyType::Maintain(_) => Some(self.maintain_factor),
+            DependencyType::MaintainPrime(_) => Some(self.maintain_prime_factor),
+            DependencyType::Depend(_) => Some(self.depend_factor),
+            DependencyType::Influence(_) => None,
+        }
+    }
+}
+
 /// The damping factors for project and accounts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DampingFactors {
     pub project: f64,
     pub account: f64,
@@ -196,3 +369,14 @@ impl Default for DampingFactors {
         }
     }
 }
+
+impl DampingFactors {
+    /// Returns the damping factor which applies to a node of the given
+    /// `ArtifactType`.
+    pub fn for_artifact(&self, artifact_type: &ArtifactType) -> f64 {
+        match artifact_type {
+            ArtifactType::Project { .. } => self.project,
+            ArtifactType::Account { .. } => self.account,
+        }
+    }
+}
@@ -5,14 +5,18 @@ extern crate fraction;
 extern crate num_traits;
 extern crate petgraph;
 extern crate quickcheck;
+extern crate serde;
 
 use num_traits::Zero;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use std::fmt;
 use std::fs::OpenOptions;
+use std::hash::Hash;
 use std::io::Write;
 use std::path::Path;
 
+use super::snapshot::{Snapshot, SnapshotError, SnapshotKind};
 use super::Osrank;
 use crate::protocol_traits::graph::{GraphExtras, PetgraphEdgeAdaptor, PetgraphNodeAdaptor};
 use oscoin_graph_api::{
@@ -23,16 +27,31 @@ use petgraph::dot::{Config, Dot};
 use petgraph::graph::{node_index, EdgeIndex, NodeIndex};
 use petgraph::visit::EdgeRef;
 use petgraph::Directed;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 
 use crate::util::quickcheck::frequency;
 use quickcheck::{Arbitrary, Gen};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Dependency<Id: Clone, W: Clone> {
     id: Id,
     dependency_type: DependencyType<W>,
 }
 
+impl<Id, W> Dependency<Id, W>
+where
+    Id: Clone,
+    W: Clone,
+{
+    pub fn new(id: Id, dependency_type: DependencyType<W>) -> Self {
+        Dependency {
+            id,
+            dependency_type,
+        }
+    }
+}
+
 impl<Id, W> fmt::Display for Dependency<Id, W>
 where
     W: fmt::Display + Clone,
@@ -47,7 +66,7 @@ where
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum DependencyType<W: Clone> {
     Contrib(W),
     ContribPrime(W),
@@ -123,7 +142,7 @@ where
     }
 }
 
-#[derive(Clone, Debug, PartialOrd, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Artifact<Id: Clone> {
     id: Id,
     artifact_type: ArtifactType,
@@ -152,10 +171,16 @@ where
     }
 }
 
-#[derive(Clone, Debug, PartialOrd, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ArtifactType {
-    Project { osrank: Osrank },
-    Account { osrank: Osrank },
+    Project {
+        #[serde(with = "super::snapshot::osrank_format")]
+        osrank: Osrank,
+    },
+    Account {
+        #[serde(with = "super::snapshot::osrank_format")]
+        osrank: Osrank,
+    },
 }
 
 impl ArtifactType {
@@ -242,20 +267,79 @@ where
     }
 }
 
+/// Edge ids which a `Network` can allocate by itself, like integers.
+pub trait SequentialId {
+    /// Returns the n-th id of the sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the id type can't represent the n-th id, rather than
+    /// wrapping around and handing out an id which is already in use.
+    fn nth_id(n: u64) -> Self;
+}
+
+impl SequentialId for usize {
+    fn nth_id(n: u64) -> Self {
+        usize::try_from(n).expect("ran out of usize edge ids")
+    }
+}
+
+impl SequentialId for u32 {
+    fn nth_id(n: u64) -> Self {
+        u32::try_from(n).expect("ran out of u32 edge ids")
+    }
+}
+
+impl SequentialId for u64 {
+    fn nth_id(n: u64) -> Self {
+        n
+    }
+}
+
 /// The network graph from the paper, comprising of both accounts and projects.
-#[derive(Clone, Debug, Default)]
-pub struct Network<W: Clone> {
-    from_graph: petgraph::Graph<Artifact<String>, Dependency<usize, W>, Directed>,
-    node_ids: HashMap<String, NodeIndex>,
-    edge_ids: HashMap<usize, EdgeIndex>,
+///
+/// Nodes are identified by a `NodeId` and edges by an `EdgeId`, which can be
+/// anything hashable, like compact integers or content hashes. The edge ids
+/// can be either supplied by the caller, via `GraphWriter::add_edge`, or
+/// allocated by the `Network` itself, via `Network::add_new_edge`.
+#[derive(Clone, Debug)]
+pub struct Network<NodeId, EdgeId, W>
+where
+    NodeId: Clone + Eq + Hash,
+    EdgeId: Clone + Eq + Hash,
+    W: Clone,
+{
+    from_graph: petgraph::Graph<Artifact<NodeId>, Dependency<EdgeId, W>, Directed>,
+    node_ids: HashMap<NodeId, NodeIndex>,
+    edge_ids: HashMap<EdgeId, EdgeIndex>,
+    /// The position in the sequence of the next id `add_new_edge` tries.
+    next_edge_id: u64,
 }
 
-impl<W> Network<W>
+impl<NodeId, EdgeId, W> Default for Network<NodeId, EdgeId, W>
 where
-    W: fmt::Display + Clone,
+    NodeId: Clone + Eq + Hash,
+    EdgeId: Clone + Eq + Hash,
+    W: Clone,
+{
+    fn default() -> Self {
+        Network {
+            from_graph: petgraph::Graph::default(),
+            node_ids: HashMap::default(),
+            edge_ids: HashMap::default(),
+            next_edge_id: 0,
+        }
+    }
+}
+
+impl<NodeId, EdgeId, W> Network<NodeId, EdgeId, W>
+where
+    NodeId: Clone + Eq + Hash,
+    EdgeId: Clone + Eq + Hash,
+    W: Clone,
 {
     /// Adds an Artifact to the Network.
-    fn add_artifact(&mut self, id: String, artifact_type: ArtifactType) {
+    fn add_artifact(&mut self, id: NodeId, artifact_type: ArtifactType) {
         let id_cloned = id.clone();
         let a = Artifact { id, artifact_type };
         let nid = self.from_graph.add_node(a);
@@ -270,11 +354,11 @@ where
         &mut self,
         source: usize,
         target: usize,
-        id: usize,
+        id: EdgeId,
         dependency_type: DependencyType<W>,
     ) {
         let d = Dependency {
-            id,
+            id: id.clone(),
             dependency_type,
         };
         let eid = self
@@ -283,6 +367,75 @@ where
         self.edge_ids.insert(id, eid);
     }
 
+    /// Removes the Dependency with the given index from the Network.
+    ///
+    /// petgraph fills the hole left by the removed edge with the last edge,
+    /// whose index therefore changes, so we have to remap it.
+    fn remove_dependency(&mut self, eid: EdgeIndex) {
+        let last = EdgeIndex::new(self.from_graph.edge_count() - 1);
+
+        if let Some(removed) = self.from_graph.remove_edge(eid) {
+            if self.edge_ids.get(removed.id()) == Some(&eid) {
+                self.edge_ids.remove(removed.id());
+            }
+
+            if eid != last {
+                let moved_id = self.from_graph[eid].id().clone();
+                if self.edge_ids.get(&moved_id) == Some(&last) {
+                    self.edge_ids.insert(moved_id, eid);
+                }
+            }
+        }
+    }
+
+    /// Removes the Artifact with the given index from the Network, alongside
+    /// all its incoming and outgoing Dependencies.
+    ///
+    /// Like for the edges, petgraph fills the hole left by the removed node
+    /// with the last node, whose index therefore changes. The edges are
+    /// removed one by one beforehand, so that they get remapped as well.
+    fn remove_artifact(&mut self, nid: NodeIndex) {
+        while let Some(eid) = self
+            .from_graph
+            .first_edge(nid, petgraph::Direction::Outgoing)
+            .or_else(|| {
+                self.from_graph
+                    .first_edge(nid, petgraph::Direction::Incoming)
+            })
+        {
+            self.remove_dependency(eid);
+        }
+
+        let last = NodeIndex::new(self.from_graph.node_count() - 1);
+
+        if let Some(removed) = self.from_graph.remove_node(nid) {
+            if self.node_ids.get(removed.id()) == Some(&nid) {
+                self.node_ids.remove(removed.id());
+            }
+
+            if nid != last {
+                let moved_id = self.from_graph[nid].id().clone();
+                if self.node_ids.get(&moved_id) == Some(&last) {
+                    self.node_ids.insert(moved_id, nid);
+                }
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.from_graph.node_count() == 0
+            && self.from_graph.edge_count() == 0
+            && self.node_ids.is_empty()
+            && self.edge_ids.is_empty()
+    }
+}
+
+impl<NodeId, EdgeId, W> Network<NodeId, EdgeId, W>
+where
+    NodeId: Clone + Eq + Hash + fmt::Display,
+    EdgeId: Clone + Eq + Hash + fmt::Display,
+    W: fmt::Display + Clone,
+{
     /// Debug-only function to render a Network into a Graphiz dot file.
     pub fn to_graphviz_dot(&self, output_path: &Path) -> Result<(), Box<std::io::Error>> {
         let mut dot_file = OpenOptions::new()
@@ -295,28 +448,50 @@ where
         ))?;
         Ok(())
     }
+}
 
-    pub fn is_empty(&self) -> bool {
-        self.from_graph.node_count() == 0
-            && self.from_graph.edge_count() == 0
-            && self.node_ids.is_empty()
-            && self.edge_ids.is_empty()
+impl<NodeId, EdgeId, W> Network<NodeId, EdgeId, W>
+where
+    NodeId: Clone + Eq + Hash + fmt::Debug,
+    EdgeId: Clone + Eq + Hash + SequentialId,
+    W: Default + Clone,
+{
+    /// Adds an edge between the input nodes, allocating a fresh id for it,
+    /// which gets returned. Ids already in use, e.g. because they have been
+    /// supplied to `add_edge`, are skipped.
+    pub fn add_new_edge(
+        &mut self,
+        source: &NodeId,
+        to: &NodeId,
+        weight: W,
+        edge_metadata: DependencyType<W>,
+    ) -> EdgeId {
+        let mut edge_id = EdgeId::nth_id(self.next_edge_id);
+        while self.edge_ids.contains_key(&edge_id) {
+            self.next_edge_id += 1;
+            edge_id = EdgeId::nth_id(self.next_edge_id);
+        }
+        self.next_edge_id += 1;
+
+        self.add_edge(edge_id.clone(), source, to, weight, edge_metadata);
+        edge_id
     }
 }
 
-impl<W> GraphWriter for Network<W>
+impl<NodeId, EdgeId, W> GraphWriter for Network<NodeId, EdgeId, W>
 where
-    W: Default + fmt::Display + Clone,
+    NodeId: Clone + Eq + Hash + fmt::Debug,
+    EdgeId: Clone + Eq + Hash,
+    W: Default + Clone,
 {
     fn add_node(&mut self, node_id: Id<Self::Node>, node_metadata: Data<Self::Node>) {
         self.add_artifact(node_id, node_metadata);
     }
 
     fn remove_node(&mut self, node_id: Id<Self::Node>) {
-        // Removes the node from petgraph as well as from the internal map
-        if let Some(nid) = self.node_ids.get(&node_id) {
-            self.from_graph.remove_node(*nid);
-            self.node_ids.remove(&node_id);
+        // Removes the node from petgraph as well as from the internal maps
+        if let Some(nid) = self.node_ids.get(&node_id).cloned() {
+            self.remove_artifact(nid);
         }
     }
 
@@ -342,7 +517,7 @@ where
                 self.unsafe_add_dependency(src, tgt, edge_id, edge_metadata);
             }
             None => panic!(
-                "add_adge: invalid link, source {} or target {} are missing.",
+                "add_adge: invalid link, source {:?} or target {:?} are missing.",
                 source, to
             ),
         }
@@ -350,9 +525,8 @@ where
 
     fn remove_edge(&mut self, edge_id: Id<Self::Edge>) {
         // Removes the edge from petgraph as well as from the internal map
-        if let Some(eid) = self.edge_ids.get(&edge_id) {
-            self.from_graph.remove_edge(*eid);
-            self.edge_ids.remove(&edge_id);
+        if let Some(eid) = self.edge_ids.get(&edge_id).cloned() {
+            self.remove_dependency(eid);
         }
     }
 
@@ -373,19 +547,21 @@ where
     }
 }
 
-impl<W> Graph for Network<W>
+impl<NodeId, EdgeId, W> Graph for Network<NodeId, EdgeId, W>
 where
-    W: Default + fmt::Display + Clone,
+    NodeId: Clone + Eq + Hash,
+    EdgeId: Clone + Eq + Hash,
+    W: Default + Clone,
 {
-    type Node = Artifact<String>;
-    type Edge = Dependency<usize, W>;
+    type Node = Artifact<NodeId>;
+    type Edge = Dependency<EdgeId, W>;
     type Weight = W;
     type NodeData = ArtifactType;
     type EdgeData = DependencyType<W>;
 
     fn neighbors(&self, node_id: &Id<Self::Node>) -> Nodes<Self::Node> {
         let mut nodes = Vec::new();
-        let mut unique_node_ids = BTreeSet::new();
+        let mut unique_node_ids = HashSet::new();
 
         unique_node_ids.insert(node_id); // avoids having the input as neighbor.
 
@@ -489,9 +665,11 @@ where
     }
 }
 
-impl<W> GraphDataWriter for Network<W>
+impl<NodeId, EdgeId, W> GraphDataWriter for Network<NodeId, EdgeId, W>
 where
-    W: Default + fmt::Display + Clone,
+    NodeId: Clone + Eq + Hash + fmt::Debug,
+    EdgeId: Clone + Eq + Hash,
+    W: Default + Clone,
 {
     fn edge_data_mut(&mut self, id: &Id<Self::Edge>) -> Option<&mut Data<Self::Edge>> {
         let mb_id = self.edge_ids.get(id);
@@ -510,9 +688,11 @@ where
     }
 }
 
-impl<W> GraphExtras for Network<W>
+impl<NodeId, EdgeId, W> GraphExtras for Network<NodeId, EdgeId, W>
 where
-    W: Default + fmt::Display + Clone,
+    NodeId: Clone + Eq + Hash + fmt::Debug,
+    EdgeId: Clone + Eq + Hash,
+    W: Default + Clone,
 {
     fn edge_count(&self) -> usize {
         self.from_graph.edge_count()
@@ -533,14 +713,14 @@ where
             .and_then(|e| Some(self.from_graph[*e].data()))
     }
 
-    fn subgraph_by_nodes(&self, sub_nodes: Vec<&String>) -> Self {
+    fn subgraph_by_nodes(&self, sub_nodes: Vec<&NodeId>) -> Self {
         let mut sub_network = Network::default();
 
         for graph_node_id in &sub_nodes {
             // Add the node only if `graph_node_id` exists.
             if let Some(petgraph_node_id) = &self.node_ids.get(*graph_node_id) {
                 let node = &self.from_graph[**petgraph_node_id].clone();
-                sub_network.add_node(node.id().to_string(), node.data().clone());
+                sub_network.add_node(node.id().clone(), node.data().clone());
             }
         }
 
@@ -570,15 +750,85 @@ where
     }
 }
 
+/// The serialisable contents of a `Network` snapshot.
+///
+/// The nodes and edges are stored in the order petgraph keeps them, with the
+/// edges referring to their endpoints by position in `nodes`, so that loading
+/// a snapshot gives back exactly the same `Network`.
+#[derive(Serialize, Deserialize)]
+pub struct NetworkContents<NodeId: Clone, EdgeId: Clone, W: Clone> {
+    nodes: Vec<Artifact<NodeId>>,
+    edges: Vec<(usize, usize, Dependency<EdgeId, W>)>,
+    next_edge_id: u64,
+}
+
+impl<NodeId, EdgeId, W> Snapshot for Network<NodeId, EdgeId, W>
+where
+    NodeId: Clone + Eq + Hash + Serialize + DeserializeOwned,
+    EdgeId: Clone + Eq + Hash + Serialize + DeserializeOwned,
+    W: Clone + Serialize + DeserializeOwned,
+{
+    type Contents = NetworkContents<NodeId, EdgeId, W>;
+
+    const KIND: SnapshotKind = SnapshotKind::Network;
+
+    fn to_contents(&self) -> Self::Contents {
+        NetworkContents {
+            nodes: self
+                .from_graph
+                .raw_nodes()
+                .iter()
+                .map(|n| n.weight.clone())
+                .collect(),
+            edges: self
+                .from_graph
+                .raw_edges()
+                .iter()
+                .map(|e| (e.source().index(), e.target().index(), e.weight.clone()))
+                .collect(),
+            next_edge_id: self.next_edge_id,
+        }
+    }
+
+    fn from_contents(contents: Self::Contents) -> Result<Self, SnapshotError> {
+        let mut network = Network::default();
+        let nodes_num = contents.nodes.len();
+
+        for node in contents.nodes {
+            network.add_artifact(node.id, node.artifact_type);
+        }
+
+        for (source, target, dependency) in contents.edges {
+            if source >= nodes_num || target >= nodes_num {
+                return Err(SnapshotError::InvalidContents(format!(
+                    "edge {} -> {} refers to a missing node",
+                    source, target
+                )));
+            }
+            network.unsafe_add_dependency(
+                source,
+                target,
+                dependency.id,
+                dependency.dependency_type,
+            );
+        }
+
+        network.next_edge_id = contents.next_edge_id;
+        Ok(network)
+    }
+}
+
 /// Trait to print (parts of) the for debugging purposes
 pub trait PrintableGraph<'a>: Graph {
     /// Prints all nodes and their attributes
     fn print_nodes(&self);
 }
 
-impl<'a, W> PrintableGraph<'a> for Network<W>
+impl<'a, NodeId, EdgeId, W> PrintableGraph<'a> for Network<NodeId, EdgeId, W>
 where
-    W: Default + fmt::Display + Clone,
+    NodeId: Clone + Eq + Hash + fmt::Display,
+    EdgeId: Clone + Eq + Hash,
+    W: Default + Clone,
 {
     fn print_nodes(&self) {
         for arti in self
@@ -597,8 +847,9 @@ mod tests {
 
     use super::*;
     use crate::types::Weight;
+    use std::collections::{BTreeMap, BTreeSet};
 
-    fn network_fixture() -> Network<f64> {
+    fn network_fixture() -> Network<String, usize, f64> {
         let mut network = Network::default();
 
         for node in &["p1", "p2", "p3"] {
@@ -690,6 +941,172 @@ mod tests {
         assert_eq!(subgraph.is_empty(), true);
     }
 
+    #[test]
+    // Removing a node moves the last one into its slot: we test that both
+    // the moved node and its edges can still be looked up.
+    fn remove_node_keeps_the_lookups_valid() {
+        let mut graph = network_fixture();
+        graph.add_edge(
+            7,
+            &"p3".to_string(),
+            &"p3".to_string(),
+            1.0,
+            DependencyType::Influence(1.0),
+        );
+        graph.remove_node("p1".to_string());
+
+        assert_eq!(graph.get_node(&"p1".to_string()), None);
+        assert_eq!(
+            graph.get_node(&"p3".to_string()).map(|n| n.id().as_str()),
+            Some("p3")
+        );
+        assert_eq!(graph.get_edge(&7).map(|e| *e.id()), Some(7));
+        let mut targets = graph
+            .edges_directed(&"p3".to_string(), Direction::Outgoing)
+            .into_iter()
+            .map(|e| e.to.as_str())
+            .collect::<Vec<_>>();
+        targets.sort();
+        assert_eq!(targets, vec!["p2", "p3"]);
+    }
+
+    #[test]
+    // We test that a `Network` with integer ids allocates fresh edge ids,
+    // skipping the ones supplied by the caller.
+    fn add_new_edge_allocates_fresh_ids() {
+        let mut network: Network<u32, u64, f64> = Network::default();
+
+        for node in 0..3 {
+            network.add_node(node, Artifact::new_project(node).data().clone());
+        }
+        network.add_edge(1, &0, &1, 1.0, DependencyType::Influence(1.0));
+
+        let e0 = network.add_new_edge(&1, &2, 0.5, DependencyType::Influence(0.5));
+        let e2 = network.add_new_edge(&2, &0, 0.5, DependencyType::Influence(0.5));
+        assert_eq!((e0, e2), (0, 2));
+        assert_eq!(network.edge_count(), 3);
+
+        let all_outgoing = network
+            .edges_directed(&2, Direction::Outgoing)
+            .into_iter()
+            .map(|eref| (*eref.to, *eref.id))
+            .collect::<Vec<_>>();
+        assert_eq!(all_outgoing, vec![(0, 2)]);
+        assert_eq!(
+            network.lookup_edge_metadata(&0),
+            Some(&DependencyType::Influence(0.5))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ran out of u32 edge ids")]
+    fn sequential_ids_do_not_wrap_around() {
+        u32::nth_id(u64::from(u32::max_value()) + 1);
+    }
+
+    /// A change to a `Network`, where nodes are referred to by a small
+    /// number, so that the removals often hit existing nodes and edges.
+    #[derive(Clone, Debug)]
+    enum Change {
+        AddNode(u8),
+        RemoveNode(u8),
+        AddEdge(u8, u8),
+        RemoveEdge(u8),
+    }
+
+    impl Arbitrary for Change {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let node = |g: &mut G| g.next_u32() as u8 % 10;
+            let choices = vec![
+                (30, Change::AddNode(node(g))),
+                (10, Change::RemoveNode(node(g))),
+                (45, Change::AddEdge(node(g), node(g))),
+                (15, Change::RemoveEdge(g.next_u32() as u8)),
+            ];
+            frequency(g, choices)
+        }
+    }
+
+    #[quickcheck]
+    // We test that, after any sequence of additions and removals, the
+    // `Network` agrees with a naive model of nodes and edges.
+    fn network_agrees_with_model_after_changes(changes: Vec<Change>) -> bool {
+        let mut network: Network<String, usize, f64> = Network::default();
+        let mut nodes: BTreeMap<String, ArtifactType> = BTreeMap::new();
+        let mut edges: BTreeMap<usize, (String, String)> = BTreeMap::new();
+        let mut next_edge_id = 0;
+
+        for change in changes {
+            match change {
+                Change::AddNode(n) => {
+                    let id = n.to_string();
+                    if !nodes.contains_key(&id) {
+                        let a = if n % 2 == 0 {
+                            Artifact::new_project(id.clone())
+                        } else {
+                            Artifact::new_account(id.clone())
+                        };
+                        network.add_node(id.clone(), a.data().clone());
+                        nodes.insert(id, a.data().clone());
+                    }
+                }
+                Change::RemoveNode(n) => {
+                    let id = n.to_string();
+                    network.remove_node(id.clone());
+                    nodes.remove(&id);
+                    edges = edges
+                        .into_iter()
+                        .filter(|(_, (from, to))| *from != id && *to != id)
+                        .collect();
+                }
+                Change::AddEdge(from, to) => {
+                    let (from, to) = (from.to_string(), to.to_string());
+                    if nodes.contains_key(&from) && nodes.contains_key(&to) {
+                        network.add_edge(
+                            next_edge_id,
+                            &from,
+                            &to,
+                            1.0,
+                            DependencyType::Influence(1.0),
+                        );
+                        edges.insert(next_edge_id, (from, to));
+                        next_edge_id += 1;
+                    }
+                }
+                Change::RemoveEdge(e) => {
+                    let id = e as usize % (next_edge_id + 1);
+                    network.remove_edge(id);
+                    edges.remove(&id);
+                }
+            }
+        }
+
+        let nodes_agree = nodes
+            .iter()
+            .all(|(id, data)| network.get_node(id).map(|n| (n.id(), n.data())) == Some((id, data)))
+            && (0..10)
+                .map(|n| n.to_string())
+                .filter(|id| !nodes.contains_key(id))
+                .all(|id| network.get_node(&id).is_none());
+
+        let edges_agree = edges.iter().all(|(id, (from, to))| {
+            network.get_edge(id).map(|e| e.id()) == Some(id)
+                && network
+                    .edges_directed(from, Direction::Outgoing)
+                    .into_iter()
+                    .any(|e| e.id == id && e.from == from && e.to == to)
+        }) && (0..next_edge_id)
+            .filter(|id| !edges.contains_key(id))
+            .all(|id| network.get_edge(&id).is_none());
+
+        network.node_count() == nodes.len()
+            && network.edge_count() == edges.len()
+            && network.nodes().map(|n| n.id()).collect::<BTreeSet<_>>()
+                == nodes.keys().collect::<BTreeSet<_>>()
+            && nodes_agree
+            && edges_agree
+    }
+
     // Tests that setting & getting an `Artifact`'s metadata roundtrips.
 
     #[quickcheck]
@@ -0,0 +1,434 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
+extern crate crc32fast;
+extern crate fraction;
+extern crate serde;
+extern crate serde_cbor;
+
+use core::fmt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// The bytes every snapshot starts with.
+pub const SNAPSHOT_MAGIC: &[u8; 8] = b"OSRKSNAP";
+
+/// The version of the snapshot format this crate reads and writes. It has to
+/// be bumped every time the `Contents` of any `Snapshot` change shape, so
+/// that snapshots written by older versions get rejected upfront rather than
+/// decoded into garbage.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// The kind of network (or state) a snapshot holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotKind {
+    /// A raw network, whose edges are contributions, dependencies etc.
+    Network,
+    /// A `Normalised` network, which can be fed to the algorithms as it is.
+    Normalised,
+    /// The walks the incremental algorithm carries over from one run to the
+    /// next.
+    IncrementalState,
+}
+
+impl SnapshotKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            SnapshotKind::Network => 0,
+            SnapshotKind::Normalised => 1,
+            SnapshotKind::IncrementalState => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(SnapshotKind::Network),
+            1 => Some(SnapshotKind::Normalised),
+            2 => Some(SnapshotKind::IncrementalState),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SnapshotKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotKind::Network => write!(f, "network"),
+            SnapshotKind::Normalised => write!(f, "normalised network"),
+            SnapshotKind::IncrementalState => write!(f, "incremental state"),
+        }
+    }
+}
+
+/// Errors that saving or loading a snapshot might throw.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Returned in case of generic I/O error.
+    IOError(std::io::Error),
+
+    /// Returned when the input doesn't start with the `SNAPSHOT_MAGIC`, or
+    /// has an unknown kind.
+    NotASnapshot,
+
+    /// Returned when the snapshot has been written with a version of the
+    /// format other than the `SNAPSHOT_VERSION` this crate supports.
+    UnsupportedVersion { found: u32, expected: u32 },
+
+    /// Returned when the snapshot holds a different kind of network than the
+    /// one requested, e.g. a raw network rather than a normalised one.
+    WrongKind {
+        found: SnapshotKind,
+        expected: SnapshotKind,
+    },
+
+    /// Returned when the payload doesn't match the checksum in the header,
+    /// i.e. the snapshot got truncated or corrupted.
+    ChecksumMismatch { found: u32, expected: u32 },
+
+    /// Returned when the payload couldn't be serialised or deserialised.
+    EncodingError(serde_cbor::Error),
+
+    /// Returned when the payload decoded fine, but doesn't describe a valid
+    /// network, e.g. because an edge refers to a node which doesn't exist.
+    InvalidContents(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::IOError(e) => {
+                write!(f, "i/o error when reading/writing on the snapshot {}", e)
+            }
+            SnapshotError::NotASnapshot => write!(f, "the input is not an osrank snapshot"),
+            SnapshotError::UnsupportedVersion { found, expected } => write!(
+                f,
+                "snapshot version {} is not supported (expected version {}), please recreate the snapshot",
+                found, expected
+            ),
+            SnapshotError::WrongKind { found, expected } => write!(
+                f,
+                "the snapshot holds a {}, but a {} was expected",
+                found, expected
+            ),
+            SnapshotError::ChecksumMismatch { found, expected } => write!(
+                f,
+                "the snapshot is corrupted (checksum {:08x}, expected {:08x})",
+                found, expected
+            ),
+            SnapshotError::EncodingError(e) => {
+                write!(f, "encoding failed on the snapshot payload {}", e)
+            }
+            SnapshotError::InvalidContents(e) => write!(f, "invalid snapshot contents: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(err: std::io::Error) -> SnapshotError {
+        SnapshotError::IOError(err)
+    }
+}
+
+impl From<serde_cbor::Error> for SnapshotError {
+    fn from(err: serde_cbor::Error) -> SnapshotError {
+        SnapshotError::EncodingError(err)
+    }
+}
+
+/// A network which can be saved into (and loaded back from) a snapshot,
+/// by going through its serialisable `Contents`.
+pub trait Snapshot: Sized {
+    type Contents: Serialize + DeserializeOwned;
+
+    /// The kind recorded in the header of the snapshot.
+    const KIND: SnapshotKind;
+
+    fn to_contents(&self) -> Self::Contents;
+
+    fn from_contents(contents: Self::Contents) -> Result<Self, SnapshotError>;
+}
+
+/// Writes the snapshot of the input network.
+///
+/// A snapshot is made of a fixed-size header, comprising the
+/// `SNAPSHOT_MAGIC`, the `SNAPSHOT_VERSION` (as a little-endian `u32`), the
+/// `SnapshotKind` (as a byte), the CRC32 of the payload and its length (as a
+/// little-endian `u32` and `u64`), followed by the CBOR-encoded payload.
+pub fn save_snapshot<S, W>(network: &S, mut writer: W) -> Result<(), SnapshotError>
+where
+    S: Snapshot,
+    W: Write,
+{
+    let payload = serde_cbor::to_vec(&network.to_contents())?;
+
+    writer.write_all(SNAPSHOT_MAGIC)?;
+    writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+    writer.write_all(&[S::KIND.to_byte()])?;
+    writer.write_all(&crc32fast::hash(&payload).to_le_bytes())?;
+    writer.write_all(&(payload.len() as u64).to_le_bytes())?;
+    writer.write_all(&payload)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Reads a network back from a snapshot written by `save_snapshot`.
+pub fn load_snapshot<S, R>(mut reader: R) -> Result<S, SnapshotError>
+where
+    S: Snapshot,
+    R: Read,
+{
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != SNAPSHOT_MAGIC {
+        return Err(SnapshotError::NotASnapshot);
+    }
+
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion {
+            found: version,
+            expected: SNAPSHOT_VERSION,
+        });
+    }
+
+    let mut kind = [0; 1];
+    reader.read_exact(&mut kind)?;
+    let kind = SnapshotKind::from_byte(kind[0]).ok_or(SnapshotError::NotASnapshot)?;
+    if kind != S::KIND {
+        return Err(SnapshotError::WrongKind {
+            found: kind,
+            expected: S::KIND,
+        });
+    }
+
+    let mut checksum = [0; 4];
+    reader.read_exact(&mut checksum)?;
+    let expected = u32::from_le_bytes(checksum);
+
+    let mut len = [0; 8];
+    reader.read_exact(&mut len)?;
+    let len = u64::from_le_bytes(len);
+
+    let mut payload = Vec::new();
+    reader.take(len).read_to_end(&mut payload)?;
+    let found = crc32fast::hash(&payload);
+    if payload.len() as u64 != len || found != expected {
+        return Err(SnapshotError::ChecksumMismatch { found, expected });
+    }
+
+    S::from_contents(serde_cbor::from_slice(&payload)?)
+}
+
+/// Writes the snapshot of the input network into the file at `path`,
+/// replacing it if it already exists.
+pub fn save_snapshot_to_file<S, P>(network: &S, path: P) -> Result<(), SnapshotError>
+where
+    S: Snapshot,
+    P: AsRef<Path>,
+{
+    save_snapshot(network, BufWriter::new(File::create(path)?))
+}
+
+/// Reads a network back from the snapshot file at `path`.
+pub fn load_snapshot_from_file<S, P>(path: P) -> Result<S, SnapshotError>
+where
+    S: Snapshot,
+    P: AsRef<Path>,
+{
+    load_snapshot(BufReader::new(File::open(path)?))
+}
+
+/// (De)serialisation of the `Osrank` fractions, which don't implement
+/// `Serialize` and `Deserialize` themselves. To be used via
+/// `#[serde(with = "crate::types::snapshot::osrank_format")]`.
+pub(crate) mod osrank_format {
+    use super::fraction::Sign;
+    use crate::types::Osrank;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    enum OsrankRepr {
+        Rational {
+            negative: bool,
+            numer: u64,
+            denom: u64,
+        },
+        Infinity {
+            negative: bool,
+        },
+        NaN,
+    }
+
+    pub fn serialize<S>(osrank: &Osrank, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let negative = osrank.sign() == Some(Sign::Minus);
+        let repr = match (osrank.numer(), osrank.denom()) {
+            _ if osrank.is_nan() => OsrankRepr::NaN,
+            _ if osrank.is_infinite() => OsrankRepr::Infinity { negative },
+            (Some(numer), Some(denom)) => OsrankRepr::Rational {
+                negative,
+                numer: *numer,
+                denom: *denom,
+            },
+            _ => OsrankRepr::NaN,
+        };
+        repr.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Osrank, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match OsrankRepr::deserialize(deserializer)? {
+            OsrankRepr::Rational {
+                negative: false,
+                numer,
+                denom,
+            } => Osrank::new(numer, denom),
+            OsrankRepr::Rational {
+                negative: true,
+                numer,
+                denom,
+            } => Osrank::new_neg(numer, denom),
+            OsrankRepr::Infinity { negative: false } => Osrank::infinity(),
+            OsrankRepr::Infinity { negative: true } => Osrank::neg_infinity(),
+            OsrankRepr::NaN => Osrank::nan(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::algorithm::Normalised;
+    use crate::types::mock::MockNetwork;
+    use crate::types::network::{Artifact, ArtifactType, DependencyType, Network};
+    use crate::types::Osrank;
+    use oscoin_graph_api::{Graph, GraphObject, GraphWriter};
+
+    fn network_fixture() -> MockNetwork {
+        let mut network = Network::default();
+
+        network.add_node(
+            "p1".to_string(),
+            ArtifactType::Project {
+                osrank: Osrank::new(1u64, 3u64),
+            },
+        );
+        network.add_node(
+            "a1".to_string(),
+            ArtifactType::Account {
+                osrank: Osrank::new(2u64, 3u64),
+            },
+        );
+        network.add_edge(
+            0,
+            &"p1".to_string(),
+            &"a1".to_string(),
+            1.0,
+            DependencyType::Influence(1.0),
+        );
+
+        network
+    }
+
+    fn snapshot_bytes<S: Snapshot>(network: &S) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        save_snapshot(network, &mut bytes).unwrap();
+        bytes
+    }
+
+    fn edges_of(network: &MockNetwork) -> Vec<(usize, String, String, DependencyType<f64>)> {
+        let mut edges = Vec::new();
+        for node in network.nodes() {
+            for edge_ref in network.edges_directed(node.id(), oscoin_graph_api::Direction::Outgoing)
+            {
+                let edge = network.get_edge(edge_ref.id).unwrap();
+                edges.push((
+                    *edge_ref.id,
+                    edge_ref.from.clone(),
+                    edge_ref.to.clone(),
+                    edge.data().clone(),
+                ));
+            }
+        }
+        edges
+    }
+
+    #[quickcheck]
+    fn snapshots_roundtrip(network: MockNetwork) -> bool {
+        let loaded: MockNetwork = load_snapshot(&snapshot_bytes(&network)[..]).unwrap();
+
+        let nodes: Vec<Artifact<String>> = network.nodes().cloned().collect();
+        let loaded_nodes: Vec<Artifact<String>> = loaded.nodes().cloned().collect();
+
+        nodes == loaded_nodes && edges_of(&network) == edges_of(&loaded)
+    }
+
+    #[test]
+    fn normalised_snapshots_keep_the_ranks() {
+        let network = Normalised::new(network_fixture());
+        let loaded: Normalised<MockNetwork> = load_snapshot(&snapshot_bytes(&network)[..]).unwrap();
+
+        assert_eq!(
+            loaded.get_node(&"a1".to_string()).unwrap().data(),
+            &ArtifactType::Account {
+                osrank: Osrank::new(2u64, 3u64),
+            }
+        );
+        assert_eq!(loaded.edges(&"p1".to_string()).count(), 1);
+    }
+
+    #[test]
+    fn load_snapshot_rejects_other_versions() {
+        let mut bytes = snapshot_bytes(&network_fixture());
+        bytes[8..12].copy_from_slice(&0u32.to_le_bytes());
+
+        match load_snapshot::<MockNetwork, _>(&bytes[..]) {
+            Err(SnapshotError::UnsupportedVersion { found, expected }) => {
+                assert_eq!(found, 0);
+                assert_eq!(expected, SNAPSHOT_VERSION);
+            }
+            other => panic!("expected a version error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn load_snapshot_rejects_other_kinds() {
+        let bytes = snapshot_bytes(&network_fixture());
+
+        match load_snapshot::<Normalised<MockNetwork>, _>(&bytes[..]) {
+            Err(SnapshotError::WrongKind { found, expected }) => {
+                assert_eq!(found, SnapshotKind::Network);
+                assert_eq!(expected, SnapshotKind::Normalised);
+            }
+            other => panic!("expected a kind error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn load_snapshot_detects_corruption() {
+        let mut bytes = snapshot_bytes(&network_fixture());
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+
+        match load_snapshot::<MockNetwork, _>(&bytes[..]) {
+            Err(SnapshotError::ChecksumMismatch { .. }) => (),
+            other => panic!("expected a checksum error, got {:?}", other.map(|_| ())),
+        }
+
+        match load_snapshot::<MockNetwork, _>(&b"not a snapshot"[..]) {
+            Err(SnapshotError::NotASnapshot) => (),
+            other => panic!("expected a magic error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -89,23 +89,194 @@ where
             .count()
     }
 
+    /// Builds a `VisitCounts` index, which maps every element to the number
+    /// of visits it received *in all walks*. Building the index requires a
+    /// single (parallel) pass over the walks, after which each lookup is O(1).
+    ///
+    /// ```
+    /// use osrank::types::walk::*;
+    ///
+    /// let ids = vec!["a", "b", "c", "c", "a", "a"];
+    /// let mut walks: RandomWalks<String> = RandomWalks::new();
+    ///
+    /// for i in ids {
+    ///     let mut walk = RandomWalk::new(String::from(i));
+    ///     walk.add_next(String::from(i));
+    ///     walks.add_walk(walk);
+    /// }
+    ///
+    /// let visits = walks.visit_counts();
+    ///
+    /// assert_eq!(visits.count_visits(&String::from("c")), 4);
+    /// assert_eq!(visits.count_visits(&String::from("d")), 0);
+    /// assert_eq!(visits.total_walks(), 6);
+    /// ```
+    pub fn visit_counts(&self) -> VisitCounts<Id> {
+        let (visits, squared_visits) = self
+            .random_walks
+            .par_iter()
+            .fold(
+                || (FnvHashMap::default(), FnvHashMap::default()),
+                |(mut visits, mut squared), rw| {
+                    for (idx, count) in rw.random_walk_visits.iter() {
+                        *visits.entry(idx.clone()).or_insert(0) += count;
+                        *squared.entry(idx.clone()).or_insert(0) += count * count;
+                    }
+                    (visits, squared)
+                },
+            )
+            .reduce(
+                || (FnvHashMap::default(), FnvHashMap::default()),
+                |(v1, s1), (v2, s2)| (merge_counts(v1, v2), merge_counts(s1, s2)),
+            );
+
+        VisitCounts {
+            visits,
+            squared_visits,
+            total_walks: self.random_walks.len(),
+        }
+    }
+
     pub fn append(&mut self, mut rhs: Self) {
         self.random_walks.append(&mut rhs.random_walks)
     }
+
+    /// Removes all the walks for which the predicate `f` returns `false`,
+    /// returning them to the caller.
+    ///
+    /// ```
+    /// use osrank::types::walk::*;
+    ///
+    /// let ids = vec!["a", "b", "c"];
+    /// let mut walks: RandomWalks<String> = RandomWalks::new();
+    ///
+    /// for i in ids {
+    ///     walks.add_walk(RandomWalk::new(String::from(i)));
+    /// }
+    ///
+    /// let removed = walks.retain(|w| w.count_visits(&String::from("b")) == 0);
+    ///
+    /// assert_eq!(walks.len(), 2);
+    /// assert_eq!(removed.count_walks_from(&String::from("b")), 1);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F) -> Self
+    where
+        F: FnMut(&RandomWalk<Id>) -> bool,
+    {
+        let (kept, removed) = self.random_walks.drain(..).partition(|rw| f(rw));
+        self.random_walks = kept;
+        RandomWalks {
+            random_walks: removed,
+        }
+    }
+
+    /// Returns an iterator over all the walks in this collection.
+    pub fn iter(&self) -> std::slice::Iter<'_, RandomWalk<Id>> {
+        self.random_walks.iter()
+    }
+}
+
+impl<Id> IntoIterator for RandomWalks<Id>
+where
+    Id: Hash + Eq + Sync + Send,
+{
+    type Item = RandomWalk<Id>;
+    type IntoIter = std::vec::IntoIter<RandomWalk<Id>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.random_walks.into_iter()
+    }
 }
 
 type Count = usize;
 
+/// Merges two maps of counts, always merging the smaller one into the bigger.
+fn merge_counts<Id: Eq + Hash>(
+    mut acc1: FnvHashMap<Id, Count>,
+    mut acc2: FnvHashMap<Id, Count>,
+) -> FnvHashMap<Id, Count> {
+    if acc1.len() < acc2.len() {
+        std::mem::swap(&mut acc1, &mut acc2);
+    }
+    for (idx, count) in acc2 {
+        *acc1.entry(idx).or_insert(0) += count;
+    }
+    acc1
+}
+
+/// An index over a collection of `RandomWalks`, storing the total number of
+/// visits for each element, alongside the number of walks it was built from.
+/// It also stores, for each element, the sum of the squares of its visits in
+/// every single walk, which is what's needed to estimate their variance.
+#[derive(Debug)]
+pub struct VisitCounts<Id>
+where
+    Id: Hash + Eq + Sync + Send,
+{
+    visits: FnvHashMap<Id, Count>,
+    squared_visits: FnvHashMap<Id, Count>,
+    total_walks: Count,
+}
+
+impl<Id> VisitCounts<Id>
+where
+    Id: Clone + Eq + Hash + Sync + Send,
+{
+    /// Returns the number of visits for the given element *in all walks*.
+    pub fn count_visits(&self, idx: &Id) -> Count {
+        *self.visits.get(idx).unwrap_or(&0)
+    }
+
+    /// Returns the number of walks this index was built from.
+    pub fn total_walks(&self) -> Count {
+        self.total_walks
+    }
+
+    /// Returns the standard error of the mean number of visits *per walk*
+    /// for the given element, i.e. the sample standard deviation of its
+    /// visits across all walks, divided by the square root of their number.
+    ///
+    /// ```
+    /// use osrank::types::walk::*;
+    ///
+    /// let mut walks: RandomWalks<String> = RandomWalks::new();
+    /// for i in &["a", "b"] {
+    ///     walks.add_walk(RandomWalk::new(String::from(*i)));
+    /// }
+    ///
+    /// let visits = walks.visit_counts();
+    ///
+    /// // "a" gets visited 1 and 0 times, so the standard deviation is
+    /// // sqrt(1/2), and the standard error sqrt(1/2) / sqrt(2) = 1/2.
+    /// assert!((visits.std_error_of_visits(&String::from("a")) - 0.5).abs() < 1e-12);
+    /// assert_eq!(visits.std_error_of_visits(&String::from("c")), 0.0);
+    /// ```
+    pub fn std_error_of_visits(&self, idx: &Id) -> f64 {
+        if self.total_walks < 2 {
+            return 0.0;
+        }
+
+        let n = self.total_walks as f64;
+        let mean = self.count_visits(idx) as f64 / n;
+        let squared = *self.squared_visits.get(idx).unwrap_or(&0) as f64;
+        let variance = ((squared - n * mean * mean) / (n - 1.0)).max(0.0);
+
+        (variance / n).sqrt()
+    }
+}
+
 #[derive(Debug)]
 /// A random walk over a `Graph`. Each walk stores the source of the walk as
 /// well as a mapping between a certain node `Id` and the number of visits on
-/// that element.
+/// that element. It also stores the path it took, so that it can be cut and
+/// resumed from any of its segments.
 pub struct RandomWalk<Id>
 where
     Id: Hash + Eq + Sync + Send,
 {
     random_walk_source: Id,
     random_walk_visits: FnvHashMap<Id, Count>,
+    random_walk_path: Vec<Id>,
 }
 
 impl<Id> RandomWalk<Id>
@@ -119,6 +290,7 @@ where
         let mut m = FnvHashMap::default();
         m.insert(source.clone(), 1);
         RandomWalk {
+            random_walk_path: vec![source.clone()],
             random_walk_source: source,
             random_walk_visits: m,
         }
@@ -129,7 +301,41 @@ where
         if let Some(visits) = self.random_walk_visits.get_mut(&idx) {
             *visits += 1;
         } else {
-            self.random_walk_visits.insert(idx, 1);
+            self.random_walk_visits.insert(idx.clone(), 1);
+        }
+        self.random_walk_path.push(idx);
+    }
+
+    /// Shortens the walk, keeping only its first `len` segments. The source
+    /// is always kept, even if `len` is zero.
+    ///
+    /// ```
+    /// use osrank::types::walk::*;
+    ///
+    /// let mut walk = RandomWalk::new("a");
+    /// for i in &["b", "a", "c"] {
+    ///     walk.add_next(*i);
+    /// }
+    ///
+    /// walk.truncate(2);
+    ///
+    /// assert_eq!(walk.path(), &["a", "b"]);
+    /// assert_eq!(walk.count_visits(&"a"), 1);
+    /// assert_eq!(walk.count_visits(&"c"), 0);
+    /// ```
+    pub fn truncate(&mut self, len: usize) {
+        let len = len.max(1);
+        if len >= self.random_walk_path.len() {
+            return;
+        }
+
+        for idx in self.random_walk_path.drain(len..) {
+            if let Some(visits) = self.random_walk_visits.get_mut(&idx) {
+                *visits -= 1;
+                if *visits == 0 {
+                    self.random_walk_visits.remove(&idx);
+                }
+            }
         }
     }
 
@@ -138,6 +344,29 @@ where
         *self.random_walk_visits.get(idx).unwrap_or(&0)
     }
 
+    /// Returns the `Id` of the source (i.e. beginning) of the walk.
+    pub fn source(&self) -> &Id {
+        &self.random_walk_source
+    }
+
+    /// Returns an iterator over all the (unique) segments visited by the walk.
+    pub fn visited(&self) -> impl Iterator<Item = &Id> {
+        self.random_walk_visits.keys()
+    }
+
+    /// Returns all the segments of the walk, in the order they were visited,
+    /// starting from its source.
+    pub fn path(&self) -> &[Id] {
+        &self.random_walk_path
+    }
+
+    /// Returns the last segment of the walk, i.e. where it would resume from.
+    pub fn last(&self) -> &Id {
+        self.random_walk_path
+            .last()
+            .unwrap_or(&self.random_walk_source)
+    }
+
     /// Given the `Id` of a segment within the walk, returns the `Id` of the
     /// source of the walk, if the input `Id` belongs to the walk.
     pub fn source_from(&self, segment_id: &Id) -> Option<&Id> {
@@ -157,9 +386,14 @@ where
 ///    calculation;
 /// 2. Ensure that the entire `Graph` is explored and that random walks
 ///    eventually "explore" all the nodes.
+///
+/// Each trusted node carries a _trust score_, which is used by the
+/// personalized mode to decide how often walks (re)start from that node.
+/// Nodes added without an explicit score have a trust of 1.0.
 #[derive(Debug)]
 pub struct SeedSet<Id> {
     trusted_nodes: Vec<Id>,
+    trust_scores: Vec<f64>,
 }
 
 impl<I> SeedSet<I> {
@@ -167,13 +401,26 @@ impl<I> SeedSet<I> {
     pub fn new() -> Self {
         SeedSet {
             trusted_nodes: Vec::new(),
+            trust_scores: Vec::new(),
         }
     }
 
     /// Creates a new `SeedSet` collection from a vector of identifiers.
     pub fn from(nodes: Vec<I>) -> Self {
+        let trust_scores = vec![1.0; nodes.len()];
         SeedSet {
             trusted_nodes: nodes,
+            trust_scores,
+        }
+    }
+
+    /// Creates a new `SeedSet` collection from a vector of identifiers,
+    /// each paired with its trust score.
+    pub fn from_trusted(nodes: Vec<(I, f64)>) -> Self {
+        let (trusted_nodes, trust_scores) = nodes.into_iter().unzip();
+        SeedSet {
+            trusted_nodes,
+            trust_scores,
         }
     }
 
@@ -182,7 +429,13 @@ impl<I> SeedSet<I> {
     }
 
     pub fn add_node(&mut self, node: I) {
-        self.trusted_nodes.push(node)
+        self.add_trusted_node(node, 1.0)
+    }
+
+    /// Adds a node to the set, with the given trust score.
+    pub fn add_trusted_node(&mut self, node: I, trust: f64) {
+        self.trusted_nodes.push(node);
+        self.trust_scores.push(trust)
     }
 
     pub fn is_empty(&self) -> bool {
@@ -195,6 +448,14 @@ impl<I> SeedSet<I> {
             inner: &self.trusted_nodes,
         }
     }
+
+    /// Returns an iterator over the trusted nodes, paired with their trust
+    /// score.
+    pub fn trust_iter(&self) -> impl Iterator<Item = (&I, f64)> {
+        self.trusted_nodes
+            .iter()
+            .zip(self.trust_scores.iter().cloned())
+    }
 }
 
 pub struct SeedSetIter<'a, I> {
@@ -223,6 +484,16 @@ mod tests {
         assert_eq!(SeedSet::from(nodes).is_empty(), false);
     }
 
+    #[test]
+    fn seed_set_trust_iter() {
+        let mut seeds = SeedSet::from_trusted(vec![("a", 0.5), ("b", 2.0)]);
+        seeds.add_node("c");
+        assert_eq!(
+            seeds.trust_iter().collect::<Vec<_>>(),
+            vec![(&"a", 0.5), (&"b", 2.0), (&"c", 1.0)]
+        );
+    }
+
     #[quickcheck]
     fn seed_set_seedset_iter(nodes: Vec<u32>) {
         assert_eq!(
@@ -276,6 +547,27 @@ mod tests {
         assert_eq!(w.source_from(&"baz"), None);
     }
 
+    #[quickcheck]
+    fn random_walk_truncate_agrees_with_a_shorter_walk(path: Vec<u8>, len: usize) {
+        if let Some((source, rest)) = path.split_first() {
+            let mut walk = RandomWalk::new(*source);
+            let mut shorter = RandomWalk::new(*source);
+            for (ix, segment) in rest.iter().enumerate() {
+                walk.add_next(*segment);
+                if ix + 1 < len {
+                    shorter.add_next(*segment);
+                }
+            }
+
+            walk.truncate(len);
+
+            assert_eq!(walk.path(), shorter.path());
+            for segment in &path {
+                assert_eq!(walk.count_visits(segment), shorter.count_visits(segment));
+            }
+        }
+    }
+
     #[test]
     fn random_walks_count_walks_from() {
         let ids = vec!["a", "b", "c", "a", "a"];
@@ -288,4 +580,26 @@ mod tests {
 
         assert_eq!(walks.count_walks_from(&String::from("a")), 3);
     }
+
+    #[quickcheck]
+    fn random_walks_visit_counts_agrees_with_count_visits(paths: Vec<Vec<u8>>) {
+        let mut walks: RandomWalks<u8> = RandomWalks::new();
+
+        for path in &paths {
+            if let Some((source, rest)) = path.split_first() {
+                let mut walk = RandomWalk::new(*source);
+                for segment in rest {
+                    walk.add_next(*segment);
+                }
+                walks.add_walk(walk);
+            }
+        }
+
+        let visits = walks.visit_counts();
+
+        assert_eq!(visits.total_walks(), walks.len());
+        for segment in paths.iter().flatten() {
+            assert_eq!(visits.count_visits(segment), walks.count_visits(segment));
+        }
+    }
 }
@@ -1 +1,2 @@
 pub mod quickcheck;
+pub mod xml;
@@ -0,0 +1,277 @@
+#![allow(unknown_lints)]
+#![warn(clippy::all)]
+
+//! Just enough XML to write and read back the GraphML and GEXF files our
+//! exporters produce (and the ones Gephi & co. produce out of them).
+//!
+//! This is not a validating parser: DTDs, processing instructions and
+//! comments are skipped, and namespaces are kept as part of the names.
+
+extern crate quick_xml;
+
+use core::fmt;
+use core::str;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// An error arising while parsing an XML document, alongside the (byte)
+/// offset where it occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlError {
+    pub offset: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for XmlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid XML at offset {}: {}", self.offset, self.reason)
+    }
+}
+
+fn error<T>(offset: usize, reason: &str) -> Result<T, XmlError> {
+    Err(XmlError {
+        offset,
+        reason: reason.to_string(),
+    })
+}
+
+/// An XML element, with its attributes, children and the concatenation of
+/// all the text directly within it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    /// Returns the value of the attribute with the given name, if any.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the first child with the given name, if any.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    /// Returns all the children with the given name.
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+}
+
+/// Escapes the input so that it can be used both as text and as the value
+/// of an attribute. Control characters are escaped as well, so that they
+/// survive a round-trip.
+pub fn escape(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c if c.is_control() => escaped.push_str(&format!("&#x{:X};", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Replaces the entity and character references in the input, which starts
+/// at `offset` in the document.
+fn unescape(input: &str, offset: usize) -> Result<String, XmlError> {
+    let mut unescaped = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        let at = offset + input.len() - rest.len() + start;
+        let end = match rest[start..].find(';') {
+            None => return error(at, "unterminated reference"),
+            Some(end) => start + end,
+        };
+
+        let reference = &rest[start + 1..end];
+        let c = match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if reference.starts_with("#x") => u32::from_str_radix(&reference[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32),
+            _ if reference.starts_with('#') => reference[1..]
+                .parse::<u32>()
+                .ok()
+                .and_then(std::char::from_u32),
+            _ => None,
+        };
+
+        match c {
+            None => return error(at, "unknown reference"),
+            Some(c) => unescaped.push(c),
+        }
+        rest = &rest[end + 1..];
+    }
+
+    unescaped.push_str(rest);
+    Ok(unescaped)
+}
+
+fn utf8(bytes: &[u8], offset: usize) -> Result<&str, XmlError> {
+    str::from_utf8(bytes).or_else(|_| error(offset, "invalid UTF-8"))
+}
+
+/// Parses the attributes of a tag, unescaping their values.
+fn parse_attributes(tag: &BytesStart, offset: usize) -> Result<Vec<(String, String)>, XmlError> {
+    let mut attributes = Vec::new();
+
+    for attribute in tag.attributes() {
+        let attribute = attribute.map_err(|e| XmlError {
+            offset,
+            reason: e.to_string(),
+        })?;
+        let name = utf8(attribute.key, offset)?.to_string();
+        let value = unescape(utf8(&attribute.value, offset)?, offset)?;
+        attributes.push((name, value));
+    }
+
+    Ok(attributes)
+}
+
+/// Parses an XML document, returning its root element.
+///
+/// The tokenisation is left to `quick_xml`, which also rejects the `<!`
+/// constructs other than comments, CDATA sections and DOCTYPEs. The
+/// references are replaced by `unescape`, so that the control characters
+/// escaped by `escape` survive a round-trip.
+pub fn parse(input: &str) -> Result<Element, XmlError> {
+    let mut reader = Reader::from_str(input);
+    reader.check_end_names(true).check_comments(true);
+
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root: Option<Element> = None;
+    let mut buf = Vec::new();
+
+    loop {
+        let event = reader.read_event(&mut buf).map_err(|e| XmlError {
+            offset: reader.buffer_position(),
+            reason: e.to_string(),
+        })?;
+        let pos = reader.buffer_position();
+
+        match event {
+            Event::Start(ref tag) | Event::Empty(ref tag) => {
+                if root.is_some() {
+                    return error(pos, "more than one root element");
+                }
+                let element = Element {
+                    name: utf8(tag.name(), pos)?.to_string(),
+                    attributes: parse_attributes(tag, pos)?,
+                    children: Vec::new(),
+                    text: String::new(),
+                };
+
+                match (&event, stack.last_mut()) {
+                    (Event::Start(_), _) => stack.push(element),
+                    (_, Some(parent)) => parent.children.push(element),
+                    (_, None) => root = Some(element),
+                }
+            }
+            Event::End(_) => {
+                let element = match stack.pop() {
+                    Some(e) => e,
+                    None => return error(pos, "closing tag without an opening one"),
+                };
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::Text(ref text) | Event::CData(ref text) => {
+                let raw = utf8(text.escaped(), pos)?;
+                let text = match event {
+                    Event::CData(_) => raw.to_string(),
+                    _ => unescape(raw, pos)?,
+                };
+                match stack.last_mut() {
+                    Some(parent) => parent.text.push_str(&text),
+                    None if text.trim().is_empty() => (),
+                    None => return error(pos, "text outside of the root element"),
+                }
+            }
+            Event::Decl(_) | Event::PI(_) | Event::Comment(_) | Event::DocType(_) => (),
+            Event::Eof => break,
+        }
+
+        buf.clear();
+    }
+
+    match (root, stack.is_empty()) {
+        (Some(root), true) => Ok(root),
+        (_, false) => error(input.len(), "unclosed element"),
+        (None, true) => error(input.len(), "no root element"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[quickcheck]
+    fn escape_roundtrips(text: String) -> bool {
+        let document = format!("<a b=\"{}\">{}</a>", escape(&text), escape(&text));
+        match parse(&document) {
+            Ok(root) => root.attribute("b") == Some(text.as_str()) && root.text == text,
+            Err(_) => false,
+        }
+    }
+
+    #[test]
+    fn parse_builds_the_tree() {
+        let root = parse(
+            r#"<?xml version="1.0"?>
+            <!-- a comment -->
+            <graph id='g' kind="a &gt; b">
+              <node id="n1"><data key="rank">0.5</data></node>
+              <node id="n2"/>
+            </graph>"#,
+        )
+        .unwrap();
+
+        assert_eq!(root.name, "graph");
+        assert_eq!(root.attribute("kind"), Some("a > b"));
+        assert_eq!(root.children_named("node").count(), 2);
+        assert_eq!(
+            root.child("node")
+                .and_then(|n| n.child("data"))
+                .map(|d| d.text.as_str()),
+            Some("0.5")
+        );
+
+        assert!(parse("<a><b></a>").is_err());
+        assert!(parse("<a></a><b/>").is_err());
+    }
+
+    #[test]
+    fn parse_keeps_cdata_verbatim() {
+        let root = parse("<a>x <![CDATA[<b> &amp; ]]> y</a>").unwrap();
+        assert_eq!(root.text, "x <b> &amp;  y");
+        assert!(root.children.is_empty());
+    }
+
+    #[test]
+    fn parse_rejects_unknown_declarations() {
+        assert!(parse("<a><!ELEMENT a ANY></a>").is_err());
+        assert!(parse("<a><![IGNORE[ <b/> ]]></a>").is_err());
+    }
+}